@@ -0,0 +1,52 @@
+// The bright parts of the scene are extracted into a texture of half the size, downsampled
+// repeatedly and then upsampled again, adding each level to the next larger one.
+
+#include "common/post_process.wgsl"
+
+// Averages four bilinear samples around the texel, which covers a 4x4 texel box
+fn sample_box(tex_coords: vec2<f32>) -> vec3<f32> {
+    let d = source_texel_size();
+    let a = textureSample(source_texture, source_sampler, tex_coords + vec2<f32>(-d.x, -d.y)).rgb;
+    let b = textureSample(source_texture, source_sampler, tex_coords + vec2<f32>(d.x, -d.y)).rgb;
+    let c = textureSample(source_texture, source_sampler, tex_coords + vec2<f32>(-d.x, d.y)).rgb;
+    let e = textureSample(source_texture, source_sampler, tex_coords + vec2<f32>(d.x, d.y)).rgb;
+
+    return (a + b + c + e) * 0.25;
+}
+
+// Keeps the part of the color above the threshold, with a quadratic transition of the width of
+// the knee below it
+@fragment
+fn prefilter_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_box(in.tex_coords);
+    let brightness = max(color.r, max(color.g, color.b));
+    let knee = max(post_process.bloom_knee, 1e-5);
+    let soft = clamp(brightness - post_process.bloom_threshold + knee, 0.0, 2.0 * knee);
+    let contribution = max(soft * soft / (4.0 * knee), brightness - post_process.bloom_threshold);
+
+    return vec4<f32>(color * contribution / max(brightness, 1e-5), 1.0);
+}
+
+@fragment
+fn downsample_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(sample_box(in.tex_coords), 1.0);
+}
+
+// Blurs the smaller level with a 3x3 tent filter, the result is added to the larger level
+@fragment
+fn upsample_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let d = source_texel_size();
+    var color = textureSample(source_texture, source_sampler, in.tex_coords).rgb * 4.0;
+    color += textureSample(source_texture, source_sampler, in.tex_coords + vec2<f32>(-d.x, 0.0)).rgb * 2.0;
+    color += textureSample(source_texture, source_sampler, in.tex_coords + vec2<f32>(d.x, 0.0)).rgb * 2.0;
+    color += textureSample(source_texture, source_sampler, in.tex_coords + vec2<f32>(0.0, -d.y)).rgb * 2.0;
+    color += textureSample(source_texture, source_sampler, in.tex_coords + vec2<f32>(0.0, d.y)).rgb * 2.0;
+    color += textureSample(source_texture, source_sampler, in.tex_coords + vec2<f32>(-d.x, -d.y)).rgb;
+    color += textureSample(source_texture, source_sampler, in.tex_coords + vec2<f32>(d.x, -d.y)).rgb;
+    color += textureSample(source_texture, source_sampler, in.tex_coords + vec2<f32>(-d.x, d.y)).rgb;
+    color += textureSample(source_texture, source_sampler, in.tex_coords + vec2<f32>(d.x, d.y)).rgb;
+
+    return vec4<f32>(color / 16.0, 1.0);
+}
+
+// vim: set filetype=wgsl :
@@ -0,0 +1,6 @@
+struct Camera {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+}
+
+// vim: set filetype=wgsl :
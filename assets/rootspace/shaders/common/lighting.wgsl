@@ -0,0 +1,30 @@
+const LIGHT_KIND_POINT = 0u;
+const LIGHT_KIND_DIRECTIONAL = 1u;
+const LIGHT_KIND_SPOT = 2u;
+
+struct Light {
+    model: mat4x4<f32>,
+    direction: vec4<f32>,
+    ambient_color: vec4<f32>,
+    diffuse_color: vec4<f32>,
+    specular_color: vec4<f32>,
+    attenuation: vec3<f32>,
+    kind: u32,
+    ambient_intensity: f32,
+    point_intensity: f32,
+    cos_inner_cone_angle: f32,
+    cos_outer_cone_angle: f32,
+    shadow_world_to_clip: mat4x4<f32>,
+    shadow_atlas_rect: vec4<f32>,
+    shadow_bias: f32,
+    casts_shadows: u32,
+}
+
+struct Lights {
+    num_lights: u32,
+    shadow_pcf_radius: u32,
+    shadow_texel_size: f32,
+    items: array<Light>,
+}
+
+// vim: set filetype=wgsl :
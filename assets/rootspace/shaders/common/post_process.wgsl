@@ -0,0 +1,48 @@
+struct PostProcess {
+    bloom_threshold: f32,
+    bloom_knee: f32,
+    bloom_intensity: f32,
+    exposure: f32,
+    tonemapping: u32,
+    gamma_exponent: f32,
+    fxaa_edge_threshold: f32,
+    fxaa_subpixel_blending: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@group(0) @binding(2)
+var<uniform> post_process: PostProcess;
+
+@group(0) @binding(3)
+var secondary_texture: texture_2d<f32>;
+
+// A single triangle that covers the whole render target, drawn with three vertices
+@vertex
+fn vertex_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let tex_coords = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    let clip_position = vec4<f32>(tex_coords.x * 2.0 - 1.0, 1.0 - tex_coords.y * 2.0, 0.0, 1.0);
+
+    return VertexOutput(clip_position, tex_coords);
+}
+
+// Relative luminance of linear Rec. 709 colors
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// Returns the size of a texel of the source texture in texture coordinates
+fn source_texel_size() -> vec2<f32> {
+    return 1.0 / vec2<f32>(textureDimensions(source_texture));
+}
+
+// vim: set filetype=wgsl :
@@ -0,0 +1,27 @@
+// Blends the joint matrices of a skinned vertex. Expects the including shader to bind the joint
+// matrices of all skinned instances as `joint_matrices: array<mat4x4<f32>>`.
+
+/// The joint offset of instances that are drawn without skinning
+const NO_SKELETON: u32 = 0xffffffffu;
+
+/// Returns the matrix that moves a vertex from its bind pose into the current pose of the
+/// skeleton, in the model space of the mesh
+fn skin_matrix(joints: vec4<u32>, weights: vec4<f32>, joint_offset: u32) -> mat4x4<f32> {
+    let total = dot(weights, vec4<f32>(1.0));
+    if joint_offset == NO_SKELETON || total <= 0.0 {
+        return mat4x4<f32>(
+            vec4<f32>(1.0, 0.0, 0.0, 0.0),
+            vec4<f32>(0.0, 1.0, 0.0, 0.0),
+            vec4<f32>(0.0, 0.0, 1.0, 0.0),
+            vec4<f32>(0.0, 0.0, 0.0, 1.0),
+        );
+    }
+
+    var skin = mat4x4<f32>(vec4<f32>(0.0), vec4<f32>(0.0), vec4<f32>(0.0), vec4<f32>(0.0));
+    for (var i = 0; i < 4; i++) {
+        skin += joint_matrices[joint_offset + joints[i]] * (weights[i] / total);
+    }
+    return skin;
+}
+
+// vim: set filetype=wgsl :
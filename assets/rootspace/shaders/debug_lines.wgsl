@@ -0,0 +1,46 @@
+#include "common/camera.wgsl"
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+@vertex
+fn world_vertex_main(
+    vertex: VertexInput,
+) -> VertexOutput {
+    let view_position = camera.view * vec4<f32>(vertex.position, 1.0);
+
+    return VertexOutput(
+        camera.projection * view_position,
+        vertex.color,
+    );
+}
+
+// Screen lines are converted to normalized device coordinates on the CPU
+@vertex
+fn screen_vertex_main(
+    vertex: VertexInput,
+) -> VertexOutput {
+    return VertexOutput(
+        vec4<f32>(vertex.position.xy, 0.0, 1.0),
+        vertex.color,
+    );
+}
+
+@fragment
+fn fragment_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return in.color;
+}
+
+// vim: set filetype=wgsl :
@@ -0,0 +1,115 @@
+// Fast approximate anti-aliasing after Timothy Lottes, simplified to a fixed number of search
+// steps along the edge
+
+#include "common/post_process.wgsl"
+
+const EDGE_THRESHOLD_MIN = 0.0312;
+const SEARCH_STEPS = 8;
+
+fn luma_at(tex_coords: vec2<f32>) -> f32 {
+    // Perceptual luma of the linear colors
+    return sqrt(luminance(textureSampleLevel(source_texture, source_sampler, tex_coords, 0.0).rgb));
+}
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let d = source_texel_size();
+    let uv = in.tex_coords;
+    let center = textureSampleLevel(source_texture, source_sampler, uv, 0.0);
+
+    let luma_m = sqrt(luminance(center.rgb));
+    let luma_n = luma_at(uv + vec2<f32>(0.0, -d.y));
+    let luma_s = luma_at(uv + vec2<f32>(0.0, d.y));
+    let luma_w = luma_at(uv + vec2<f32>(-d.x, 0.0));
+    let luma_e = luma_at(uv + vec2<f32>(d.x, 0.0));
+
+    let luma_max = max(luma_m, max(max(luma_n, luma_s), max(luma_w, luma_e)));
+    let luma_min = min(luma_m, min(min(luma_n, luma_s), min(luma_w, luma_e)));
+    let range = luma_max - luma_min;
+    if range < max(EDGE_THRESHOLD_MIN, luma_max * post_process.fxaa_edge_threshold) {
+        return center;
+    }
+
+    let luma_nw = luma_at(uv + vec2<f32>(-d.x, -d.y));
+    let luma_ne = luma_at(uv + vec2<f32>(d.x, -d.y));
+    let luma_sw = luma_at(uv + vec2<f32>(-d.x, d.y));
+    let luma_se = luma_at(uv + vec2<f32>(d.x, d.y));
+
+    // Blend towards the neighbours in proportion to the local contrast
+    let luma_average = (2.0 * (luma_n + luma_s + luma_w + luma_e) + luma_nw + luma_ne + luma_sw + luma_se) / 12.0;
+    let subpixel = smoothstep(0.0, 1.0, saturate(abs(luma_average - luma_m) / range));
+    let subpixel_offset = subpixel * subpixel * post_process.fxaa_subpixel_blending;
+
+    // The edge is horizontal if the luma changes more from top to bottom than from left to right
+    let horizontal_contrast = abs(luma_nw + luma_ne - 2.0 * luma_n) + 2.0 * abs(luma_w + luma_e - 2.0 * luma_m)
+        + abs(luma_sw + luma_se - 2.0 * luma_s);
+    let vertical_contrast = abs(luma_nw + luma_sw - 2.0 * luma_w) + 2.0 * abs(luma_n + luma_s - 2.0 * luma_m)
+        + abs(luma_ne + luma_se - 2.0 * luma_e);
+    let is_horizontal = horizontal_contrast >= vertical_contrast;
+
+    // Step towards the neighbour across the edge with the larger contrast
+    var step_length = select(d.x, d.y, is_horizontal);
+    let luma_negative = select(luma_w, luma_n, is_horizontal);
+    let luma_positive = select(luma_e, luma_s, is_horizontal);
+    let gradient_negative = abs(luma_negative - luma_m);
+    let gradient_positive = abs(luma_positive - luma_m);
+    var luma_opposite = luma_positive;
+    if gradient_negative >= gradient_positive {
+        step_length = -step_length;
+        luma_opposite = luma_negative;
+    }
+    let gradient_scaled = 0.25 * max(gradient_negative, gradient_positive);
+    let luma_edge = 0.5 * (luma_m + luma_opposite);
+
+    // Search along the edge in both directions for its ends
+    var edge_uv = uv;
+    var along = vec2<f32>(d.x, 0.0);
+    if is_horizontal {
+        edge_uv.y += 0.5 * step_length;
+    } else {
+        edge_uv.x += 0.5 * step_length;
+        along = vec2<f32>(0.0, d.y);
+    }
+
+    var uv_negative = edge_uv - along;
+    var uv_positive = edge_uv + along;
+    var delta_negative = luma_at(uv_negative) - luma_edge;
+    var delta_positive = luma_at(uv_positive) - luma_edge;
+    for (var i = 0; i < SEARCH_STEPS; i++) {
+        let done_negative = abs(delta_negative) >= gradient_scaled;
+        let done_positive = abs(delta_positive) >= gradient_scaled;
+        if done_negative && done_positive {
+            break;
+        }
+        if !done_negative {
+            uv_negative -= along;
+            delta_negative = luma_at(uv_negative) - luma_edge;
+        }
+        if !done_positive {
+            uv_positive += along;
+            delta_positive = luma_at(uv_positive) - luma_edge;
+        }
+    }
+
+    let distance_negative = select(uv.y - uv_negative.y, uv.x - uv_negative.x, is_horizontal);
+    let distance_positive = select(uv_positive.y - uv.y, uv_positive.x - uv.x, is_horizontal);
+    let nearest = min(distance_negative, distance_positive);
+    let edge_length = distance_negative + distance_positive;
+
+    // Only blend if the nearer end of the edge lies on the other side of the edge luma
+    let nearest_delta = select(delta_positive, delta_negative, distance_negative < distance_positive);
+    let correct_variation = (nearest_delta < 0.0) != (luma_m - luma_edge < 0.0);
+    let edge_offset = select(0.0, 0.5 - nearest / edge_length, correct_variation);
+
+    let offset = max(edge_offset, subpixel_offset);
+    var final_uv = uv;
+    if is_horizontal {
+        final_uv.y += offset * step_length;
+    } else {
+        final_uv.x += offset * step_length;
+    }
+
+    return textureSampleLevel(source_texture, source_sampler, final_uv, 0.0);
+}
+
+// vim: set filetype=wgsl :
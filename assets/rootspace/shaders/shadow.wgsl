@@ -0,0 +1,104 @@
+// Feature flags: SKINNING deforms the vertices with the joint matrices of the instance, ALPHA_TEST
+// discards fragments whose alpha falls below the alpha cutoff of the material
+
+#ifdef SKINNING
+#include "common/skinning.wgsl"
+#endif
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+#ifdef ALPHA_TEST
+    @location(2) tex_coords: vec2<f32>,
+#endif
+#ifdef SKINNING
+    @location(14) joints: vec4<u32>,
+    @location(15) weights: vec4<f32>,
+#endif
+}
+
+struct InstanceInput {
+    @location(4) model_0: vec4<f32>,
+    @location(5) model_1: vec4<f32>,
+    @location(6) model_2: vec4<f32>,
+    @location(7) model_3: vec4<f32>,
+    @location(13) joint_offset: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+#ifdef ALPHA_TEST
+    @location(0) tex_coords: vec2<f32>,
+#endif
+}
+
+struct Shadow {
+    world_to_clip: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> shadow: Shadow;
+
+#ifdef SKINNING
+@group(1) @binding(0)
+var<storage, read> joint_matrices: array<mat4x4<f32>>;
+#endif
+
+#ifdef ALPHA_TEST
+// Matches the material uniform of with_camera_and_material.wgsl
+struct Material {
+    ambient_reflectivity: f32,
+    diffuse_reflectivity: f32,
+    specular_reflectivity: f32,
+    smoothness: f32,
+    alpha_cutoff: f32,
+    opacity: f32,
+}
+
+// The joint bind group occupies group 1 even without skinning, such that the material group
+// stays at the same index
+@group(2) @binding(0)
+var t_diffuse: texture_2d<f32>;
+
+@group(2) @binding(1)
+var s_diffuse: sampler;
+
+@group(2) @binding(2)
+var<uniform> material: Material;
+#endif
+
+@vertex
+fn vertex_main(
+    vertex: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    let model = mat4x4<f32>(
+        instance.model_0,
+        instance.model_1,
+        instance.model_2,
+        instance.model_3,
+    );
+
+    var local_position = vec4<f32>(vertex.position, 1.0);
+#ifdef SKINNING
+    local_position = skin_matrix(vertex.joints, vertex.weights, instance.joint_offset) * local_position;
+#endif
+
+    var out: VertexOutput;
+    out.clip_position = shadow.world_to_clip * model * local_position;
+#ifdef ALPHA_TEST
+    out.tex_coords = vertex.tex_coords;
+#endif
+    return out;
+}
+
+#ifdef ALPHA_TEST
+@fragment
+fn fragment_main(in: VertexOutput) {
+    let alpha = textureSample(t_diffuse, s_diffuse, in.tex_coords).a * material.opacity;
+    if alpha < material.alpha_cutoff {
+        discard;
+    }
+}
+#endif
+
+// vim: set filetype=wgsl :
@@ -0,0 +1,31 @@
+// Combines the scene with its bloom, maps the HDR colors to the displayable range and encodes
+// them with the gamma of the display
+
+#include "common/post_process.wgsl"
+
+// Fit of the ACES filmic tone curve by Krzysztof Narkowicz
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+
+    return saturate((color * (a * color + b)) / (color * (c * color + d) + e));
+}
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let scene = textureSample(source_texture, source_sampler, in.tex_coords).rgb;
+    let bloom = textureSample(secondary_texture, source_sampler, in.tex_coords).rgb;
+
+    var color = (scene + bloom * post_process.bloom_intensity) * post_process.exposure;
+    if post_process.tonemapping != 0u {
+        color = aces(color);
+    }
+    color = pow(saturate(color), vec3<f32>(post_process.gamma_exponent));
+
+    return vec4<f32>(color, 1.0);
+}
+
+// vim: set filetype=wgsl :
@@ -1,4 +1,13 @@
-// Shader pre-processing hint: https://elyshaffir.github.io/Taiga-Blog/2022/01/08/using_include_statements_in_wgsl.html
+// Feature flags: ALPHA_TEST discards fragments below the alpha cutoff, ALPHA_BLEND keeps the alpha
+// of the diffuse texture for blending, SKINNING deforms the vertices with the joint matrices of
+// the instance
+
+#include "common/camera.wgsl"
+#include "common/lighting.wgsl"
+#ifdef SKINNING
+#include "common/skinning.wgsl"
+#endif
+
 // Function reference: https://webgpufundamentals.org/webgpu/lessons/webgpu-wgsl-function-reference.html
 
 const TAU = 6.283185307179586476925286766559005768394338798;
@@ -7,19 +16,24 @@ struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) tex_coords: vec2<f32>,
+    @location(3) tangent: vec4<f32>,
+#ifdef SKINNING
+    @location(14) joints: vec4<u32>,
+    @location(15) weights: vec4<f32>,
+#endif
 }
 
 struct InstanceInput {
-    @location(4) model_view_0: vec4<f32>,
-    @location(5) model_view_1: vec4<f32>,
-    @location(6) model_view_2: vec4<f32>,
-    @location(7) model_view_3: vec4<f32>,
+    @location(4) model_0: vec4<f32>,
+    @location(5) model_1: vec4<f32>,
+    @location(6) model_2: vec4<f32>,
+    @location(7) model_3: vec4<f32>,
     @location(8) normal_0: vec4<f32>,
     @location(9) normal_1: vec4<f32>,
     @location(10) normal_2: vec4<f32>,
     @location(11) normal_3: vec4<f32>,
-    @location(12) with_camera: f32,
-    @location(13) with_material: f32,
+    @location(12) with_material: f32,
+    @location(13) joint_offset: u32,
 }
 
 struct VertexOutput {
@@ -27,21 +41,9 @@ struct VertexOutput {
     @location(0) view_position: vec3<f32>,
     @location(1) view_normal: vec3<f32>,
     @location(2) tex_coords: vec2<f32>,
-    @location(3) light_position: vec3<f32>,
-    @location(4) with_material: f32,
-}
-
-struct Camera {
-    projection: mat4x4<f32>,
-}
-
-struct Light {
-    model_view: mat4x4<f32>,
-    ambient_color: vec4<f32>,
-    diffuse_color: vec4<f32>,
-    specular_color: vec4<f32>,
-    ambient_intensity: f32,
-    point_intensity: f32,
+    @location(3) with_material: f32,
+    @location(4) view_tangent: vec4<f32>,
+    @location(5) world_position: vec3<f32>,
 }
 
 struct Material {
@@ -49,13 +51,21 @@ struct Material {
     diffuse_reflectivity: f32,
     specular_reflectivity: f32,
     smoothness: f32,
+    alpha_cutoff: f32,
+    opacity: f32,
 }
 
 @group(0) @binding(0)
 var<uniform> camera: Camera;
 
 @group(1) @binding(0)
-var<uniform> light: Light;
+var<storage, read> lights: Lights;
+
+@group(1) @binding(1)
+var t_shadow_atlas: texture_depth_2d;
+
+@group(1) @binding(2)
+var s_shadow: sampler_comparison;
 
 @group(2) @binding(0)
 var t_diffuse: texture_2d<f32>;
@@ -66,6 +76,100 @@ var s_diffuse: sampler;
 @group(2) @binding(2)
 var<uniform> material: Material;
 
+@group(2) @binding(3)
+var t_normal: texture_2d<f32>;
+
+@group(2) @binding(4)
+var t_specular: texture_2d<f32>;
+
+#ifdef SKINNING
+@group(3) @binding(0)
+var<storage, read> joint_matrices: array<mat4x4<f32>>;
+#endif
+
+/// Perturb the interpolated surface normal with a tangent-space normal map, using the tangent
+/// supplied by the mesh
+fn perturb_normal_with_tangent(
+    /// The surface normal in view space (unit vector)
+    N: vec3<f32>,
+    /// The tangent in view space, with the bitangent handedness in the fourth component
+    T: vec4<f32>,
+    /// The normal map sample, remapped to [-1, 1]
+    map_normal: vec3<f32>,
+) -> vec3<f32> {
+    // Re-orthogonalize the interpolated tangent with respect to the normal
+    let T_ortho = normalize(T.xyz - dot(T.xyz, N) * N);
+    let B = cross(N, T_ortho) * select(1.0, -1.0, T.w < 0.0);
+    let TBN = mat3x3<f32>(T_ortho, B, N);
+    return normalize(TBN * map_normal);
+}
+
+/// Perturb the interpolated surface normal with a tangent-space normal map. The tangent frame is
+/// derived from screen-space derivatives, for meshes that do not supply tangents.
+fn perturb_normal(
+    /// The surface normal in view space (unit vector)
+    N: vec3<f32>,
+    /// The surface position in view space
+    P: vec3<f32>,
+    /// The texture coordinates of the fragment
+    uv: vec2<f32>,
+    /// The normal map sample, remapped to [-1, 1]
+    map_normal: vec3<f32>,
+) -> vec3<f32> {
+    let dp1 = dpdx(P);
+    let dp2 = dpdy(P);
+    let duv1 = dpdx(uv);
+    let duv2 = dpdy(uv);
+    let dp2perp = cross(dp2, N);
+    let dp1perp = cross(N, dp1);
+    let T = dp2perp * duv1.x + dp1perp * duv2.x;
+    let B = dp2perp * duv1.y + dp1perp * duv2.y;
+    let inv_max = inverseSqrt(max(max(dot(T, T), dot(B, B)), 1e-12));
+    let TBN = mat3x3<f32>(T * inv_max, B * inv_max, N);
+    return normalize(TBN * map_normal);
+}
+
+/// Determine the fraction of light that reaches a surface point, filtering the light's shadow map
+/// over a square of texels (percentage-closer filtering)
+fn shadow_factor(
+    // Light source properties
+    light: Light,
+    /// The surface position in world space
+    P_world: vec3<f32>,
+) -> f32 {
+    if light.casts_shadows == 0u {
+        return 1.0;
+    }
+
+    let clip = light.shadow_world_to_clip * vec4<f32>(P_world, 1.0);
+    let ndc = clip.xyz / clip.w;
+    // Points outside the shadow map are considered lit
+    if clip.w <= 0.0 || any(abs(ndc.xy) > vec2<f32>(1.0)) || ndc.z > 1.0 {
+        return 1.0;
+    }
+
+    let rect = light.shadow_atlas_rect;
+    let uv = rect.xy + (ndc.xy * vec2<f32>(0.5, -0.5) + 0.5) * rect.zw;
+    let depth = ndc.z - light.shadow_bias;
+
+    // Keep the filter kernel within the light's tile of the atlas
+    let texel = lights.shadow_texel_size;
+    let uv_min = rect.xy + 0.5 * texel;
+    let uv_max = rect.xy + rect.zw - 0.5 * texel;
+    let radius = i32(lights.shadow_pcf_radius);
+
+    var lit = 0.0;
+    for (var y = -radius; y <= radius; y++) {
+        for (var x = -radius; x <= radius; x++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            let sample_uv = clamp(uv + offset, uv_min, uv_max);
+            lit += textureSampleCompareLevel(t_shadow_atlas, s_shadow, sample_uv, depth);
+        }
+    }
+    let taps = f32((2 * radius + 1) * (2 * radius + 1));
+    return lit / taps;
+}
+
 /// Calculate ambient, diffuse, and specular lighting based on the Blinn-Phong model
 fn blinn_phong(
     // Light source properties
@@ -82,9 +186,14 @@ fn blinn_phong(
     L: vec3<f32>,
     /// The viewing direction (unit vector)
     V: vec3<f32>,
+    /// Scales all components with the distance to the light source
+    attenuation: f32,
+    /// Scales the diffuse and specular components with the angle to the axis of a spot light and
+    /// the fraction of the surface not in shadow
+    visibility: f32,
 ) -> vec3<f32> {
-    let Ia = light.ambient_intensity;
-    let Ip = light.point_intensity;
+    let Ia = attenuation * light.ambient_intensity;
+    let Ip = attenuation * visibility * light.point_intensity;
     let ambient_color = light.ambient_color.rgb;
     let diffuse_color = texture_color;
     let specular_color = light.specular_color.rgb;
@@ -101,16 +210,55 @@ fn blinn_phong(
     return Ca + Cd + Cs;
 }
 
+/// Sum the Blinn-Phong contributions of all light sources
+fn shade(
+    /// Material properties
+    material: Material,
+    /// Textured color of the object
+    texture_color: vec3<f32>,
+    /// The surface position in view space
+    P: vec3<f32>,
+    /// The surface normal in view space (unit vector)
+    N: vec3<f32>,
+    /// The surface position in world space
+    P_world: vec3<f32>,
+) -> vec3<f32> {
+    let V = normalize(-P);
+    var color = vec3<f32>(0.0);
+    for (var i = 0u; i < lights.num_lights; i++) {
+        let light = lights.items[i];
+        let light_position = (camera.view * light.model * vec4<f32>(0.0, 0.0, 0.0, 1.0)).xyz;
+        let light_direction = normalize((camera.view * vec4<f32>(light.direction.xyz, 0.0)).xyz);
+
+        var L = -light_direction;
+        var attenuation = 1.0;
+        var spot_factor = 1.0;
+        if light.kind != LIGHT_KIND_DIRECTIONAL {
+            let to_light = light_position - P;
+            let d = length(to_light);
+            L = to_light / d;
+            attenuation = 1.0 / max(dot(light.attenuation, vec3<f32>(1.0, d, d * d)), 1e-6);
+        }
+        if light.kind == LIGHT_KIND_SPOT {
+            spot_factor = smoothstep(light.cos_outer_cone_angle, light.cos_inner_cone_angle, dot(-L, light_direction));
+        }
+
+        let visibility = spot_factor * shadow_factor(light, P_world);
+        color += blinn_phong(light, material, texture_color, N, L, V, attenuation, visibility);
+    }
+    return color;
+}
+
 @vertex
 fn vertex_main(
     vertex: VertexInput,
     instance: InstanceInput,
 ) -> VertexOutput {
-    let model_view = mat4x4<f32>(
-        instance.model_view_0,
-        instance.model_view_1,
-        instance.model_view_2,
-        instance.model_view_3,
+    let model = mat4x4<f32>(
+        instance.model_0,
+        instance.model_1,
+        instance.model_2,
+        instance.model_3,
     );
 
     let normal = mat4x4<f32>(
@@ -120,40 +268,79 @@ fn vertex_main(
         instance.normal_3,
     );
 
-    let with_camera = step(0.5, instance.with_camera);
-    let local_position = vec4<f32>(vertex.position, 1.0);
-    let view_position = model_view * local_position;
-    let clip_position = with_camera * camera.projection * view_position + (1.0 - with_camera) * view_position;
+    var local_position = vec4<f32>(vertex.position, 1.0);
+    var local_normal = vec4<f32>(vertex.normal, 0.0);
+    var local_tangent = vec4<f32>(vertex.tangent.xyz, 0.0);
+#ifdef SKINNING
+    let skin = skin_matrix(vertex.joints, vertex.weights, instance.joint_offset);
+    local_position = skin * local_position;
+    local_normal = skin * local_normal;
+    local_tangent = skin * local_tangent;
+#endif
+
+    let world_position = model * local_position;
+    let view_position = camera.view * world_position;
+    let clip_position = camera.projection * view_position;
 
-    let view_normal = normalize(normal * vec4<f32>(vertex.normal, 0.0));
+    let world_normal = vec4<f32>((normal * local_normal).xyz, 0.0);
+    let view_normal = normalize(camera.view * world_normal);
+    let view_tangent = vec4<f32>((camera.view * model * local_tangent).xyz, vertex.tangent.w);
 
     return VertexOutput(
         clip_position,
         view_position.xyz,
         view_normal.xyz,
         vertex.tex_coords,
-        (light.model_view * vec4(0.0, 0.0, 0.0, 1.0)).xyz,
         instance.with_material,
+        view_tangent,
+        world_position.xyz,
     );
 }
 
+/// Shade a fragment and return its color, with the alpha of the diffuse texture scaled by the
+/// opacity of the material
+fn surface_color(in: VertexOutput) -> vec4<f32> {
+    let with_material = step(0.5, in.with_material);
+    // Objects without material take on the color of the first light source
+    var light_color = vec4<f32>(1.0);
+    if lights.num_lights > 0u {
+        light_color = lights.items[0].diffuse_color;
+    }
+    let texture_color = with_material * textureSample(t_diffuse, s_diffuse, in.tex_coords) + (1.0 - with_material) * light_color;
+    let map_normal = textureSample(t_normal, s_diffuse, in.tex_coords).xyz * 2.0 - 1.0;
+    let specular_scale = textureSample(t_specular, s_diffuse, in.tex_coords).r;
+
+    let N = normalize(in.view_normal);
+    // Derivatives must be evaluated in uniform control flow, hence both frames are computed
+    let derived_normal = perturb_normal(N, in.view_position, in.tex_coords, map_normal);
+    let has_tangent = dot(in.view_tangent.xyz, in.view_tangent.xyz) > 1e-12;
+    let tangent_normal = perturb_normal_with_tangent(N, in.view_tangent, map_normal);
+    let mapped_normal = select(derived_normal, tangent_normal, has_tangent);
+    let normal = normalize(with_material * mapped_normal + (1.0 - with_material) * N);
+
+    var mapped_material = material;
+    mapped_material.specular_reflectivity *= mix(1.0, specular_scale, with_material);
+
+    let color = shade(mapped_material, texture_color.rgb, in.view_position, normal, in.world_position);
+
+    return vec4<f32>(color, texture_color.a * mix(1.0, material.opacity, with_material));
+}
+
 @fragment
 fn fragment_main(
     in: VertexOutput
 ) -> @location(0) vec4<f32> {
-    let with_material = step(0.5, in.with_material);
-    let texture_color = with_material * textureSample(t_diffuse, s_diffuse, in.tex_coords) + (1.0 - with_material) * light.diffuse_color;
-
-    let color = blinn_phong(
-        light,
-        material,
-        texture_color.rgb,
-        in.view_normal,
-        normalize(in.light_position - in.view_position),
-        normalize(-in.view_position),
-    );
-
-    return vec4<f32>(color, 1.0);
+    let color = surface_color(in);
+#ifdef ALPHA_TEST
+    if color.a < material.alpha_cutoff {
+        discard;
+    }
+#endif
+#ifdef ALPHA_BLEND
+    return color;
+#else
+    return vec4<f32>(color.rgb, 1.0);
+#endif
 }
 
 // vim: set filetype=wgsl :
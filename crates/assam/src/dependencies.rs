@@ -0,0 +1,280 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+/// Identifies an asset by its group and name within the asset tree
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AssetId {
+    pub group: String,
+    pub name: String,
+}
+
+impl AssetId {
+    pub fn new<S: AsRef<str>>(group: S, name: S) -> Self {
+        AssetId {
+            group: group.as_ref().to_string(),
+            name: name.as_ref().to_string(),
+        }
+    }
+
+    /// Returns the asset at a path relative to the directory of this asset within the same group,
+    /// such as a texture referenced by a model. Paths that leave the group result in an invalid
+    /// asset name.
+    #[must_use]
+    pub fn sibling(&self, path: &str) -> AssetId {
+        let mut components: Vec<&str> = self.name.split('/').collect();
+        components.pop();
+        for component in path.split('/') {
+            match component {
+                "" | "." => (),
+                ".." if components.last().is_some_and(|c| *c != "..") => {
+                    components.pop();
+                }
+                c => components.push(c),
+            }
+        }
+
+        AssetId::new(self.group.as_str(), &components.join("/"))
+    }
+}
+
+impl Display for AssetId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.group, self.name)
+    }
+}
+
+/// Directed graph of assets, where each edge points from a dependent asset to one of its dependencies
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DependencyGraph {
+    edges: BTreeMap<AssetId, BTreeSet<AssetId>>,
+}
+
+impl DependencyGraph {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    #[must_use]
+    pub fn contains(&self, asset: &AssetId) -> bool {
+        self.edges.contains_key(asset)
+    }
+
+    /// Registers an asset without any dependencies
+    pub fn insert_asset(&mut self, asset: AssetId) {
+        self.edges.entry(asset).or_default();
+    }
+
+    /// Registers that `dependent` requires `dependency` to load
+    pub fn insert_dependency(&mut self, dependent: AssetId, dependency: AssetId) {
+        self.edges.entry(dependency.clone()).or_default();
+        self.edges.entry(dependent).or_default().insert(dependency);
+    }
+
+    pub fn assets(&self) -> impl Iterator<Item = &AssetId> {
+        self.edges.keys()
+    }
+
+    /// Returns the direct dependencies of the specified asset
+    pub fn dependencies<'a>(&'a self, asset: &AssetId) -> impl Iterator<Item = &'a AssetId> + use<'a> {
+        self.edges.get(asset).into_iter().flat_map(|deps| deps.iter())
+    }
+
+    /// Returns the assets that directly depend on the specified asset
+    pub fn dependents<'a>(&'a self, asset: &'a AssetId) -> impl Iterator<Item = &'a AssetId> {
+        self.edges
+            .iter()
+            .filter(move |(_, deps)| deps.contains(asset))
+            .map(|(dependent, _)| dependent)
+    }
+
+    /// Returns every elementary cycle found by a depth-first traversal, each starting and ending
+    /// with the same asset
+    #[must_use]
+    pub fn find_cycles(&self) -> Vec<Vec<AssetId>> {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum Mark {
+            Unvisited,
+            InProgress,
+            Done,
+        }
+
+        fn visit<'a>(
+            graph: &'a DependencyGraph,
+            asset: &'a AssetId,
+            marks: &mut BTreeMap<&'a AssetId, Mark>,
+            path: &mut Vec<&'a AssetId>,
+            cycles: &mut Vec<Vec<AssetId>>,
+        ) {
+            marks.insert(asset, Mark::InProgress);
+            path.push(asset);
+
+            for dep in graph.dependencies(asset) {
+                match marks.get(dep).copied().unwrap_or(Mark::Unvisited) {
+                    Mark::Unvisited => visit(graph, dep, marks, path, cycles),
+                    Mark::InProgress => {
+                        let start = path.iter().position(|&a| a == dep).unwrap_or(0);
+                        let mut cycle: Vec<AssetId> = path[start..].iter().map(|&a| a.clone()).collect();
+                        cycle.push(dep.clone());
+                        cycles.push(cycle);
+                    }
+                    Mark::Done => (),
+                }
+            }
+
+            path.pop();
+            marks.insert(asset, Mark::Done);
+        }
+
+        let mut marks = BTreeMap::new();
+        let mut path = Vec::new();
+        let mut cycles = Vec::new();
+        for asset in self.assets() {
+            if !marks.contains_key(asset) {
+                visit(self, asset, &mut marks, &mut path, &mut cycles);
+            }
+        }
+
+        cycles
+    }
+}
+
+/// Result of validating the asset tree against the dependency graph of its root assets
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Assets that are referenced but do not exist, together with the assets referencing them
+    pub missing: BTreeMap<AssetId, BTreeSet<AssetId>>,
+    /// Assets that exist in the asset tree but are never referenced
+    pub unreferenced: BTreeSet<AssetId>,
+    /// Assets that exist but could not be read, together with the reason
+    pub invalid: BTreeMap<AssetId, String>,
+    /// Dependency cycles
+    pub cycles: Vec<Vec<AssetId>>,
+}
+
+impl ValidationReport {
+    /// Compares the dependency graph with the assets present in the asset tree
+    pub fn new<I: IntoIterator<Item = AssetId>>(graph: &DependencyGraph, present: I) -> Self {
+        let present: BTreeSet<AssetId> = present.into_iter().collect();
+
+        let missing = graph
+            .assets()
+            .filter(|a| !present.contains(a))
+            .map(|a| (a.clone(), graph.dependents(a).cloned().collect()))
+            .collect();
+
+        let unreferenced = present.into_iter().filter(|a| !graph.contains(a)).collect();
+
+        ValidationReport {
+            missing,
+            unreferenced,
+            invalid: BTreeMap::new(),
+            cycles: graph.find_cycles(),
+        }
+    }
+
+    /// Returns `true` if no assets are missing or invalid and no cycles were found. Unreferenced
+    /// assets are reported, but do not count as a failure.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.invalid.is_empty() && self.cycles.is_empty()
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Missing assets: {}", self.missing.len())?;
+        for (asset, dependents) in &self.missing {
+            let dependents: Vec<String> = dependents.iter().map(ToString::to_string).collect();
+            if dependents.is_empty() {
+                writeln!(f, "  {asset}")?;
+            } else {
+                writeln!(f, "  {asset} (required by {})", dependents.join(", "))?;
+            }
+        }
+
+        writeln!(f, "Invalid assets: {}", self.invalid.len())?;
+        for (asset, reason) in &self.invalid {
+            writeln!(f, "  {asset}: {reason}")?;
+        }
+
+        writeln!(f, "Unreferenced assets: {}", self.unreferenced.len())?;
+        for asset in &self.unreferenced {
+            writeln!(f, "  {asset}")?;
+        }
+
+        writeln!(f, "Dependency cycles: {}", self.cycles.len())?;
+        for cycle in &self.cycles {
+            let cycle: Vec<String> = cycle.iter().map(ToString::to_string).collect();
+            writeln!(f, "  {}", cycle.join(" -> "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(name: &str) -> AssetId {
+        AssetId::new("test", name)
+    }
+
+    #[test]
+    fn sibling_resolves_paths_relative_to_the_directory_of_the_asset() {
+        let model = AssetId::new("models", "sponza/sponza.obj");
+
+        assert_eq!(
+            model.sibling("textures/floor.png"),
+            AssetId::new("models", "sponza/textures/floor.png")
+        );
+        assert_eq!(
+            model.sibling("./sponza.mtl"),
+            AssetId::new("models", "sponza/sponza.mtl")
+        );
+        assert_eq!(model.sibling("../cube.png"), AssetId::new("models", "cube.png"));
+        assert_eq!(model.sibling("../../cube.png"), AssetId::new("models", "../cube.png"));
+        assert_eq!(id("cube.ply").sibling("cube.mtl"), id("cube.mtl"));
+    }
+
+    #[test]
+    fn dependency_graph_records_edges_in_both_directions() {
+        let mut g = DependencyGraph::default();
+        g.insert_dependency(id("scene"), id("model"));
+        g.insert_dependency(id("model"), id("texture"));
+
+        assert_eq!(g.len(), 3);
+        assert_eq!(g.dependencies(&id("model")).collect::<Vec<_>>(), vec![&id("texture")]);
+        assert_eq!(g.dependents(&id("model")).collect::<Vec<_>>(), vec![&id("scene")]);
+        assert!(g.find_cycles().is_empty());
+    }
+
+    #[test]
+    fn dependency_graph_finds_cycles() {
+        let mut g = DependencyGraph::default();
+        g.insert_dependency(id("a"), id("b"));
+        g.insert_dependency(id("b"), id("c"));
+        g.insert_dependency(id("c"), id("a"));
+
+        assert_eq!(g.find_cycles(), vec![vec![id("a"), id("b"), id("c"), id("a")]]);
+    }
+
+    #[test]
+    fn validation_report_lists_missing_and_unreferenced_assets() {
+        let mut g = DependencyGraph::default();
+        g.insert_dependency(id("scene"), id("model"));
+
+        let report = ValidationReport::new(&g, vec![id("scene"), id("unused")]);
+
+        assert!(!report.is_ok());
+        assert_eq!(report.missing.keys().collect::<Vec<_>>(), vec![&id("model")]);
+        assert_eq!(report.missing[&id("model")], BTreeSet::from([id("scene")]));
+        assert_eq!(report.unreferenced, BTreeSet::from([id("unused")]));
+    }
+}
@@ -5,8 +5,10 @@ use ecs::Resources;
 use std::path::Path;
 use std::path::PathBuf;
 
+pub mod dependencies;
 pub mod resources;
 
+pub use self::dependencies::{AssetId, DependencyGraph, ValidationReport};
 pub use self::resources::{AssetDatabase, AssetDatabaseDeps};
 
 pub trait LoadAsset {
@@ -19,6 +21,13 @@ pub trait SaveAsset {
     fn to_path(&self, path: &Path) -> impl Future<Output = anyhow::Result<()>> + Send;
 }
 
+/// Lists the assets an asset file refers to without fully loading it, which allows validating
+/// the asset tree offline. Receives the identity of the asset as well as its path, such that
+/// files referenced relative to the asset can be resolved with [`AssetId::sibling`].
+pub trait AssetDependencies {
+    fn dependencies(asset: &AssetId, path: &Path) -> impl Future<Output = anyhow::Result<Vec<AssetId>>> + Send;
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("The asset tree was not found")]
@@ -33,4 +42,6 @@ pub enum Error {
     InvalidCharacters(String, String),
     #[error("The specified file format is not supported for loading assets")]
     UnsupportedFileFormat,
+    #[error("The asset dependencies form a cycle: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(" -> "))]
+    DependencyCycle(Vec<AssetId>),
 }
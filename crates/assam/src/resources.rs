@@ -1,10 +1,10 @@
 use anyhow::Context;
 use directories::ProjectDirs;
 use std::path::{Path, PathBuf};
-use std::sync::LazyLock;
-use tokio::fs::{create_dir_all, remove_dir_all};
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::fs::{create_dir_all, read_dir, remove_dir_all};
 
-use super::{Error, LoadAsset, SaveAsset};
+use super::{AssetId, DependencyGraph, Error, LoadAsset, SaveAsset};
 use ecs::{Resource, Resources, WithDependencies};
 use file_manipulation::copy_recursive;
 
@@ -19,6 +19,11 @@ static WITHIN_REPO_ASSETS: LazyLock<PathBuf> = LazyLock::new(|| {
         .unwrap()
 });
 
+tokio::task_local! {
+    /// The assets being loaded by the current call chain, outermost first
+    static LOADING: Vec<AssetId>;
+}
+
 static GROUP_AND_NAME_ALLOWLIST: LazyLock<regex::Regex> = LazyLock::new(|| {
     regex::RegexBuilder::new("^[-._0-9a-zA-Z]+$")
         .multi_line(true)
@@ -41,9 +46,14 @@ pub struct AssetDatabase {
     game_name: String,
     project_dirs: ProjectDirs,
     assets: PathBuf,
+    dependencies: Arc<Mutex<DependencyGraph>>,
 }
 
 impl AssetDatabase {
+    /// Loads an asset and records it in the dependency graph. If the asset is loaded while
+    /// loading another asset, the new asset is recorded as its dependency. Assets loaded
+    /// concurrently are tracked independently, unless they are spawned as separate tasks, which
+    /// makes them appear as top-level assets.
     #[tracing::instrument(skip_all)]
     pub async fn load_asset<A, S>(&self, res: &Resources, group: S, name: S) -> anyhow::Result<A::Output>
     where
@@ -57,7 +67,12 @@ impl AssetDatabase {
                 group.as_ref()
             )
         })?;
-        let asset = A::with_path(res, &path).await.with_context(|| {
+
+        let asset_id = AssetId::new(group.as_ref(), name.as_ref());
+        let loading = self.begin_loading(&asset_id)?;
+        let asset = LOADING.scope(loading, A::with_path(res, &path)).await;
+
+        let asset = asset.with_context(|| {
             format!(
                 "Loading a {} asset from path '{}'",
                 std::any::type_name::<A>(),
@@ -68,6 +83,102 @@ impl AssetDatabase {
         Ok(asset)
     }
 
+    /// Returns a snapshot of the dependencies recorded while loading assets
+    #[must_use]
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        self.dependencies
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Lists all assets present in the specified group, including those in its subdirectories.
+    /// Hidden files and directories are ignored.
+    pub async fn list_assets<S: AsRef<str>>(&self, group: S) -> anyhow::Result<Vec<AssetId>> {
+        let group = group.as_ref();
+        let group_path = self.assets.join(group);
+        let mut assets = Vec::new();
+        if !group_path.is_dir() {
+            return Ok(assets);
+        }
+
+        // Directories to visit, with their path relative to the group
+        let mut directories = vec![(group_path, String::new())];
+        while let Some((dir_path, prefix)) = directories.pop() {
+            let mut entries = read_dir(&dir_path)
+                .await
+                .with_context(|| format!("Reading the directory '{}'", dir_path.display()))?;
+            while let Some(entry) = entries.next_entry().await? {
+                let Some(file_name) = entry.file_name().to_str().map(std::string::ToString::to_string) else {
+                    continue;
+                };
+
+                if file_name.starts_with('.') || !GROUP_AND_NAME_ALLOWLIST.is_match(&file_name) {
+                    continue;
+                }
+
+                let name = format!("{prefix}{file_name}");
+                let file_type = entry.file_type().await?;
+                if file_type.is_dir() {
+                    directories.push((entry.path(), format!("{name}/")));
+                } else if file_type.is_file() {
+                    assets.push(AssetId::new(group, &name));
+                }
+            }
+        }
+
+        assets.sort();
+        Ok(assets)
+    }
+
+    /// Lists all assets present in the asset tree. Hidden files and directories are ignored.
+    pub async fn list_all_assets(&self) -> anyhow::Result<Vec<AssetId>> {
+        let mut assets = Vec::new();
+        let mut entries = read_dir(&self.assets)
+            .await
+            .with_context(|| format!("Reading the directory '{}'", self.assets.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_dir() {
+                continue;
+            }
+
+            if let Some(group) = entry.file_name().to_str()
+                && !group.starts_with('.')
+                && GROUP_AND_NAME_ALLOWLIST.is_match(group)
+            {
+                assets.extend(self.list_assets(group).await?);
+            }
+        }
+
+        assets.sort();
+        Ok(assets)
+    }
+
+    /// Records the asset in the dependency graph and returns the chain of assets being loaded by
+    /// the current call chain, including the asset itself
+    fn begin_loading(&self, asset_id: &AssetId) -> Result<Vec<AssetId>, Error> {
+        let mut loading = LOADING.try_with(Clone::clone).unwrap_or_default();
+
+        if let Some(start) = loading.iter().position(|a| a == asset_id) {
+            let mut cycle = loading[start..].to_vec();
+            cycle.push(asset_id.clone());
+            return Err(Error::DependencyCycle(cycle));
+        }
+
+        let mut dependencies = self
+            .dependencies
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(dependent) = loading.last() {
+            dependencies.insert_dependency(dependent.clone(), asset_id.clone());
+        } else {
+            dependencies.insert_asset(asset_id.clone());
+        }
+
+        loading.push(asset_id.clone());
+        Ok(loading)
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn save_asset<A, S>(&self, asset: &A, group: S, name: S) -> anyhow::Result<()>
     where
@@ -103,7 +214,7 @@ impl AssetDatabase {
         let group = group.as_ref();
         let name = name.as_ref();
 
-        if !(GROUP_AND_NAME_ALLOWLIST.is_match(group) && GROUP_AND_NAME_ALLOWLIST.is_match(name)) {
+        if !(GROUP_AND_NAME_ALLOWLIST.is_match(group) && is_valid_name(name)) {
             return Err(Error::InvalidCharacters(group.to_string(), name.to_string()));
         }
 
@@ -116,26 +227,28 @@ impl AssetDatabase {
         Ok(asset_path)
     }
 
+    /// Returns the group and name of the asset at the specified path. Assets in subdirectories of
+    /// their group are named by their path relative to the group.
     pub fn find_asset_name<P: AsRef<Path>>(&self, asset_path: P) -> Result<(String, String), Error> {
         let asset_path = asset_path.as_ref();
 
-        if !asset_path.starts_with(&self.assets) {
-            return Err(Error::OutOfTree(asset_path.to_path_buf()));
-        }
+        let relative_path = asset_path
+            .strip_prefix(&self.assets)
+            .map_err(|_| Error::OutOfTree(asset_path.to_path_buf()))?;
+        let mut components = relative_path.iter().map(|c| c.to_str());
 
-        let group = asset_path
-            .parent()
-            .and_then(|parent| parent.file_name())
-            .and_then(|file_name| file_name.to_str())
+        let group = components
+            .next()
+            .flatten()
             .map(std::string::ToString::to_string)
             .ok_or(Error::NoAssetGroup(asset_path.to_path_buf()))?;
-        let name = asset_path
-            .file_name()
-            .and_then(|file_name| file_name.to_str())
-            .map(std::string::ToString::to_string)
+        let name = components
+            .collect::<Option<Vec<_>>>()
+            .filter(|c| !c.is_empty())
+            .map(|c| c.join("/"))
             .ok_or(Error::NoAssetName(asset_path.to_path_buf()))?;
 
-        if !(GROUP_AND_NAME_ALLOWLIST.is_match(&group) && GROUP_AND_NAME_ALLOWLIST.is_match(&name)) {
+        if !(GROUP_AND_NAME_ALLOWLIST.is_match(&group) && is_valid_name(&name)) {
             return Err(Error::InvalidCharacters(group, name));
         }
 
@@ -143,6 +256,13 @@ impl AssetDatabase {
     }
 }
 
+/// Asset names consist of one or more path components separated by slashes, which must not
+/// refer to the current or parent directory
+fn is_valid_name(name: &str) -> bool {
+    name.split('/')
+        .all(|c| c != "." && c != ".." && GROUP_AND_NAME_ALLOWLIST.is_match(c))
+}
+
 impl Resource for AssetDatabase {}
 
 impl<D> WithDependencies<D> for AssetDatabase
@@ -194,6 +314,7 @@ where
             game_name: deps.name().to_string(),
             project_dirs,
             assets,
+            dependencies: Arc::default(),
         })
     }
 }
@@ -245,6 +366,105 @@ mod tests {
         let _rr = ResourceRegistry::push(End, AssetDatabase::with_deps(&deps).await.unwrap());
     }
 
+    #[tokio::test]
+    async fn asset_names_may_contain_subdirectories_within_their_group() {
+        let adb = AssetDatabase::with_deps(&TDeps::default()).await.unwrap();
+
+        let path = adb.find_asset("models", "sponza/textures/floor.png").unwrap();
+        assert_eq!(path, adb.assets.join("models").join("sponza/textures/floor.png"));
+        assert_eq!(
+            adb.find_asset_name(&path).unwrap(),
+            (String::from("models"), String::from("sponza/textures/floor.png"))
+        );
+
+        assert!(adb.find_asset("models", "../textures/floor.png").is_err());
+        assert!(adb.find_asset("models", "sponza//floor.png").is_err());
+        assert!(adb.find_asset("models/sponza", "floor.png").is_err());
+        assert!(adb.find_asset_name(adb.assets.join("models")).is_err());
+    }
+
+    /// Loads its dependencies one after another, yielding before each
+    struct Nested;
+
+    impl LoadAsset for Nested {
+        type Output = ();
+
+        async fn with_path(res: &Resources, path: &Path) -> anyhow::Result<Self::Output> {
+            let dependencies: &[&str] = match path.file_name().and_then(|n| n.to_str()) {
+                Some("a" | "b") => &["c"],
+                Some("cycle-a") => &["cycle-b"],
+                Some("cycle-b") => &["cycle-a"],
+                _ => &[],
+            };
+            for name in dependencies {
+                tokio::task::yield_now().await;
+                load_nested(res, name).await?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Boxes the recursive load, so that the future of [`Nested::with_path`] has a finite type
+    fn load_nested<'a>(
+        res: &'a Resources,
+        name: &'a str,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            let adb = res.read::<AssetDatabase>().clone();
+            adb.load_asset::<Nested, _>(res, "test", name).await
+        })
+    }
+
+    async fn resources_with_asset_database() -> Resources {
+        let mut res = Resources::with_capacity(1);
+        res.insert(AssetDatabase::with_deps(&TDeps::default()).await.unwrap());
+        res
+    }
+
+    #[tokio::test]
+    async fn concurrent_loads_of_the_same_asset_are_not_cycles() {
+        let res = resources_with_asset_database().await;
+        let adb = res.read::<AssetDatabase>().clone();
+
+        let (a, b) = tokio::join!(
+            adb.load_asset::<Nested, _>(&res, "test", "a"),
+            adb.load_asset::<Nested, _>(&res, "test", "b")
+        );
+
+        a.unwrap();
+        b.unwrap();
+        let graph = adb.dependency_graph();
+        assert_eq!(
+            graph.dependencies(&AssetId::new("test", "a")).collect::<Vec<_>>(),
+            vec![&AssetId::new("test", "c")]
+        );
+        assert_eq!(
+            graph.dependencies(&AssetId::new("test", "b")).collect::<Vec<_>>(),
+            vec![&AssetId::new("test", "c")]
+        );
+    }
+
+    #[tokio::test]
+    async fn loading_an_asset_within_its_own_dependencies_is_a_cycle() {
+        let res = resources_with_asset_database().await;
+        let adb = res.read::<AssetDatabase>().clone();
+
+        let error = adb.load_asset::<Nested, _>(&res, "test", "cycle-a").await.unwrap_err();
+
+        let cycle = error.chain().find_map(|e| match e.downcast_ref::<Error>() {
+            Some(Error::DependencyCycle(cycle)) => Some(cycle.clone()),
+            _ => None,
+        });
+        assert_eq!(
+            cycle,
+            Some(vec![
+                AssetId::new("test", "cycle-a"),
+                AssetId::new("test", "cycle-b"),
+                AssetId::new("test", "cycle-a")
+            ])
+        );
+    }
+
     #[tokio::test]
     async fn asset_database_world() {
         let deps = TDeps::default();
@@ -45,7 +45,7 @@ macro_rules! Reg {
 ///
 /// ```
 /// use serde::{Deserialize, Serialize};
-/// use ecs::{RegAdd, registry::{Element, End}, resource::Resource};
+/// use ecs::{RegAdd, Element, End, Resource};
 ///
 /// #[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
 /// struct A(usize);
@@ -0,0 +1,252 @@
+/// How the values between two keyframes are computed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Interpolation {
+    /// Holds the value of the preceding keyframe
+    Step,
+    /// Interpolates linearly, or spherically for rotations
+    #[default]
+    Linear,
+}
+
+/// Values that can be interpolated between keyframes
+pub trait Keyframe: Copy {
+    fn interpolate(a: Self, b: Self, t: f32) -> Self;
+}
+
+/// Translations and scales
+impl Keyframe for [f32; 3] {
+    fn interpolate(a: Self, b: Self, t: f32) -> Self {
+        [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t)
+    }
+}
+
+/// Rotation quaternions in the order x, y, z, w
+impl Keyframe for [f32; 4] {
+    fn interpolate(a: Self, b: Self, t: f32) -> Self {
+        let mut dot: f32 = (0..4).map(|i| a[i] * b[i]).sum();
+        // Take the shorter path around the hypersphere
+        let b = if dot < 0.0 {
+            dot = -dot;
+            b.map(|c| -c)
+        } else {
+            b
+        };
+
+        let (wa, wb) = if dot > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = dot.acos();
+            let sin_theta = theta.sin();
+            (((1.0 - t) * theta).sin() / sin_theta, (t * theta).sin() / sin_theta)
+        };
+
+        normalize_quat([0, 1, 2, 3].map(|i| a[i] * wa + b[i] * wb))
+    }
+}
+
+/// Keyframes of a single property of a node, sorted by time
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Keyframes<T> {
+    /// Time of each keyframe in seconds
+    pub times: Vec<f32>,
+    pub values: Vec<T>,
+    pub interpolation: Interpolation,
+}
+
+impl<T: Keyframe> Keyframes<T> {
+    /// Returns the value at `time`, holding the first and last value outside of the keyframes.
+    /// Returns `None` if there are no keyframes.
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let len = self.times.len().min(self.values.len());
+        let next = self.times[..len].partition_point(|&t| t <= time);
+        match next {
+            0 => self.values.first().copied(),
+            n if n == len => self.values.get(len - 1).copied(),
+            n => {
+                let (t0, t1) = (self.times[n - 1], self.times[n]);
+                let (a, b) = (self.values[n - 1], self.values[n]);
+                match self.interpolation {
+                    Interpolation::Step => Some(a),
+                    Interpolation::Linear => Some(T::interpolate(a, b, (time - t0) / (t1 - t0))),
+                }
+            }
+        }
+    }
+}
+
+/// The animated properties of a single node
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AnimationChannel {
+    /// Index of the node within the [`CpuNodeTree`](super::gltf_import::CpuNodeTree)
+    pub node: usize,
+    pub translation: Option<Keyframes<[f32; 3]>>,
+    /// Rotation quaternions in the order x, y, z, w
+    pub rotation: Option<Keyframes<[f32; 4]>>,
+    pub scale: Option<Keyframes<[f32; 3]>>,
+}
+
+/// The local transform of a node at a point in time. Properties that are not animated are `None`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NodePose {
+    pub translation: Option<[f32; 3]>,
+    /// Rotation quaternion in the order x, y, z, w
+    pub rotation: Option<[f32; 4]>,
+    pub scale: Option<[f32; 3]>,
+}
+
+/// Keyframe animation of the translation, rotation and scale of the nodes of a node tree
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AnimationClip {
+    pub name: Option<String>,
+    /// Time of the last keyframe in seconds
+    pub duration: f32,
+    pub channels: Vec<AnimationChannel>,
+}
+
+impl AnimationClip {
+    pub fn new(name: Option<String>, channels: Vec<AnimationChannel>) -> Self {
+        let last = |times: Option<&Vec<f32>>| times.and_then(|t| t.last().copied()).unwrap_or_default();
+        let duration = channels
+            .iter()
+            .flat_map(|c| {
+                [
+                    last(c.translation.as_ref().map(|k| &k.times)),
+                    last(c.rotation.as_ref().map(|k| &k.times)),
+                    last(c.scale.as_ref().map(|k| &k.times)),
+                ]
+            })
+            .fold(0.0, f32::max);
+
+        AnimationClip {
+            name,
+            duration,
+            channels,
+        }
+    }
+
+    /// Returns the pose of every animated node at `time`
+    pub fn sample(&self, time: f32) -> impl Iterator<Item = (usize, NodePose)> + '_ {
+        self.channels.iter().map(move |c| {
+            (
+                c.node,
+                NodePose {
+                    translation: c.translation.as_ref().and_then(|k| k.sample(time)),
+                    rotation: c.rotation.as_ref().and_then(|k| k.sample(time)),
+                    scale: c.scale.as_ref().and_then(|k| k.sample(time)),
+                },
+            )
+        })
+    }
+}
+
+fn normalize_quat(q: [f32; 4]) -> [f32; 4] {
+    let norm = q.iter().map(|c| c * c).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        q.map(|c| c / norm)
+    } else {
+        [0.0, 0.0, 0.0, 1.0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframes<T>(times: &[f32], values: Vec<T>, interpolation: Interpolation) -> Keyframes<T> {
+        Keyframes {
+            times: times.to_vec(),
+            values,
+            interpolation,
+        }
+    }
+
+    #[test]
+    fn keyframes_interpolate_and_hold_their_ends() {
+        let linear = keyframes(
+            &[1.0, 3.0],
+            vec![[0.0, 0.0, 0.0], [2.0, 4.0, -2.0]],
+            Interpolation::Linear,
+        );
+        assert_eq!(linear.sample(0.0), Some([0.0, 0.0, 0.0]));
+        assert_eq!(linear.sample(2.0), Some([1.0, 2.0, -1.0]));
+        assert_eq!(linear.sample(5.0), Some([2.0, 4.0, -2.0]));
+
+        let step = Keyframes {
+            interpolation: Interpolation::Step,
+            ..linear
+        };
+        assert_eq!(step.sample(2.9), Some([0.0, 0.0, 0.0]));
+        assert_eq!(step.sample(3.0), Some([2.0, 4.0, -2.0]));
+
+        assert_eq!(
+            keyframes::<[f32; 3]>(&[], vec![], Interpolation::Linear).sample(1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn rotations_take_the_shorter_path() {
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        // A quarter turn around y, with the second keyframe on the opposite hemisphere
+        let rotations = keyframes(
+            &[0.0, 1.0],
+            vec![[0.0, 0.0, 0.0, 1.0], [0.0, -half, 0.0, -half]],
+            Interpolation::Linear,
+        );
+
+        let q = rotations.sample(0.5).unwrap();
+        let expected = [
+            0.0,
+            (std::f32::consts::PI / 8.0).sin(),
+            0.0,
+            (std::f32::consts::PI / 8.0).cos(),
+        ];
+        for (a, b) in q.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-6, "{q:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn clip_duration_is_the_last_keyframe_of_any_channel() {
+        let clip = AnimationClip::new(
+            None,
+            vec![
+                AnimationChannel {
+                    node: 0,
+                    translation: Some(keyframes(&[0.0, 1.5], vec![[0.0; 3]; 2], Interpolation::Linear)),
+                    rotation: None,
+                    scale: None,
+                },
+                AnimationChannel {
+                    node: 2,
+                    translation: None,
+                    rotation: None,
+                    scale: Some(keyframes(&[0.5, 2.5], vec![[1.0; 3], [3.0; 3]], Interpolation::Linear)),
+                },
+            ],
+        );
+
+        assert_eq!(clip.duration, 2.5);
+        let poses: Vec<_> = clip.sample(1.5).collect();
+        assert_eq!(
+            poses[0],
+            (
+                0,
+                NodePose {
+                    translation: Some([0.0; 3]),
+                    ..Default::default()
+                }
+            )
+        );
+        assert_eq!(
+            poses[1],
+            (
+                2,
+                NodePose {
+                    scale: Some([2.0; 3]),
+                    ..Default::default()
+                }
+            )
+        );
+    }
+}
@@ -1,36 +1,301 @@
+use std::path::Path;
+
 use super::cpu_texture::CpuTexture;
+use crate::base::blend_mode::BlendMode;
+use crate::base::sampler_builder::SamplerSettings;
 use anyhow::Context;
-use assam::LoadAsset;
+use assam::{AssetDatabase, AssetDependencies, AssetId, LoadAsset};
+use ecs::Resources;
 use tracing::warn;
 
+pub const TEXTURE_ASSET_GROUP: &str = "textures";
+pub const MATERIAL_ASSET_GROUP: &str = "materials";
+pub const MATERIAL_FILE_EXTENSION: &str = "toml";
+
 #[derive(Debug)]
 pub struct CpuMaterial {
     pub label: Option<String>,
-    pub texture: CpuTexture,
+    pub diffuse_texture: CpuTexture,
+    pub normal_texture: Option<CpuTexture>,
+    pub specular_texture: Option<CpuTexture>,
     pub ambient_reflectivity: f32,
     pub diffuse_reflectivity: f32,
     pub specular_reflectivity: f32,
     pub smoothness: f32,
+    pub blend_mode: BlendMode,
+    pub alpha_cutoff: f32,
+    /// Multiplies the alpha of the diffuse texture
+    pub opacity: f32,
+    pub sampler: SamplerSettings,
 }
 
-impl LoadAsset for CpuMaterial {
-    type Output = Self;
+impl CpuMaterial {
+    /// Returns the asset that a material name found in a mesh refers to. Names with the material
+    /// file extension refer to a [`MaterialDefinition`] in the materials group, all other names
+    /// refer to a diffuse texture in the textures group.
+    pub fn asset_id(name: &str) -> AssetId {
+        if Path::new(name).extension().and_then(|ext| ext.to_str()) == Some(MATERIAL_FILE_EXTENSION) {
+            AssetId::new(MATERIAL_ASSET_GROUP, name)
+        } else {
+            AssetId::new(TEXTURE_ASSET_GROUP, name)
+        }
+    }
+
+    /// Creates an untextured material of a single color given in linear RGBA, using the default
+    /// material parameters
+    pub fn with_color(label: Option<String>, color: [f32; 4]) -> Self {
+        let defaults = MaterialDefinition::default();
+        CpuMaterial {
+            diffuse_texture: CpuTexture::with_color(label.clone(), color),
+            label,
+            normal_texture: None,
+            specular_texture: None,
+            ambient_reflectivity: defaults.ambient_reflectivity,
+            diffuse_reflectivity: defaults.diffuse_reflectivity,
+            specular_reflectivity: defaults.specular_reflectivity,
+            smoothness: defaults.smoothness,
+            blend_mode: defaults.blend_mode,
+            alpha_cutoff: defaults.alpha_cutoff,
+            opacity: defaults.opacity,
+            sampler: defaults.sampler,
+        }
+    }
+
+    #[tracing::instrument(skip(res))]
+    async fn with_definition(res: &Resources, path: &Path) -> anyhow::Result<Self> {
+        let label = path.file_stem().and_then(|n| n.to_str()).map(|n| n.to_owned());
+        let definition = MaterialDefinition::with_path(path)?;
+
+        let diffuse_texture = Self::load_texture(res, &definition.diffuse_texture).await?;
+        let normal_texture = match definition.normal_texture {
+            Some(ref name) => Some(Self::load_texture(res, name).await?),
+            None => None,
+        };
+        let specular_texture = match definition.specular_texture {
+            Some(ref name) => Some(Self::load_texture(res, name).await?),
+            None => None,
+        };
+
+        Ok(CpuMaterial {
+            label,
+            diffuse_texture,
+            normal_texture,
+            specular_texture,
+            ambient_reflectivity: definition.ambient_reflectivity,
+            diffuse_reflectivity: definition.diffuse_reflectivity,
+            specular_reflectivity: definition.specular_reflectivity,
+            smoothness: definition.smoothness,
+            blend_mode: definition.blend_mode,
+            alpha_cutoff: definition.alpha_cutoff,
+            opacity: definition.opacity,
+            sampler: definition.sampler,
+        })
+    }
 
     #[tracing::instrument(skip(res))]
-    async fn with_path(res: &ecs::Resources, path: &std::path::Path) -> anyhow::Result<Self::Output> {
+    async fn with_texture(res: &Resources, path: &Path) -> anyhow::Result<Self> {
         let label = path.file_stem().and_then(|n| n.to_str()).map(|n| n.to_owned());
-        let texture = CpuTexture::with_path(res, path)
+        let diffuse_texture = CpuTexture::with_path(res, path)
             .await
             .with_context(|| format!("Loading CpuTexture at path {}", path.display()))?;
 
-        warn!("Use of hard-coded material properties in CpuMaterial");
+        warn!("Use of default material properties in CpuMaterial, consider adding a material definition");
+        let defaults = MaterialDefinition::default();
         Ok(CpuMaterial {
             label,
-            texture,
+            diffuse_texture,
+            normal_texture: None,
+            specular_texture: None,
+            ambient_reflectivity: defaults.ambient_reflectivity,
+            diffuse_reflectivity: defaults.diffuse_reflectivity,
+            specular_reflectivity: defaults.specular_reflectivity,
+            smoothness: defaults.smoothness,
+            blend_mode: defaults.blend_mode,
+            alpha_cutoff: defaults.alpha_cutoff,
+            opacity: defaults.opacity,
+            sampler: defaults.sampler,
+        })
+    }
+
+    async fn load_texture(res: &Resources, name: &str) -> anyhow::Result<CpuTexture> {
+        res.read::<AssetDatabase>()
+            .load_asset::<CpuTexture, _>(res, TEXTURE_ASSET_GROUP, name)
+            .await
+            .with_context(|| {
+                format!(
+                    "Loading a CpuTexture from group {} and name {}",
+                    TEXTURE_ASSET_GROUP, name
+                )
+            })
+    }
+}
+
+impl LoadAsset for CpuMaterial {
+    type Output = Self;
+
+    #[tracing::instrument(skip(res))]
+    async fn with_path(res: &Resources, path: &Path) -> anyhow::Result<Self::Output> {
+        if path.extension().and_then(|ext| ext.to_str()) == Some(MATERIAL_FILE_EXTENSION) {
+            Self::with_definition(res, path).await
+        } else {
+            Self::with_texture(res, path).await
+        }
+    }
+}
+
+impl AssetDependencies for CpuMaterial {
+    async fn dependencies(_asset: &AssetId, path: &Path) -> anyhow::Result<Vec<AssetId>> {
+        if path.extension().and_then(|ext| ext.to_str()) != Some(MATERIAL_FILE_EXTENSION) {
+            return Ok(Vec::new());
+        }
+
+        let definition = MaterialDefinition::with_path(path)?;
+        Ok(std::iter::once(&definition.diffuse_texture)
+            .chain(definition.normal_texture.iter())
+            .chain(definition.specular_texture.iter())
+            .map(|name| AssetId::new(TEXTURE_ASSET_GROUP, name))
+            .collect())
+    }
+}
+
+/// On-disk representation of a material. Textures are referenced by name from the textures
+/// group, and omitted parameters assume their default values.
+///
+/// ```toml
+/// diffuse_texture = "cube-diffuse.jpg"
+/// normal_texture = "cube-normal.png"
+/// specular_reflectivity = 0.5
+/// smoothness = 16.0
+/// blend_mode = "alpha-blend"
+/// opacity = 0.75
+///
+/// [sampler]
+/// address_mode_u = "repeat"
+/// anisotropy = 8
+/// ```
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct MaterialDefinition {
+    pub diffuse_texture: String,
+    pub normal_texture: Option<String>,
+    pub specular_texture: Option<String>,
+    pub ambient_reflectivity: f32,
+    pub diffuse_reflectivity: f32,
+    pub specular_reflectivity: f32,
+    pub smoothness: f32,
+    pub blend_mode: BlendMode,
+    pub alpha_cutoff: f32,
+    pub opacity: f32,
+    pub sampler: SamplerSettings,
+}
+
+impl MaterialDefinition {
+    pub fn with_path(path: &Path) -> anyhow::Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading the material definition '{}'", path.display()))?;
+        let definition: Self =
+            toml::from_str(&source).with_context(|| format!("Parsing the material definition '{}'", path.display()))?;
+
+        if definition.diffuse_texture.is_empty() {
+            return Err(Error::NoDiffuseTexture(path.to_path_buf()).into());
+        }
+
+        Ok(definition)
+    }
+}
+
+impl Default for MaterialDefinition {
+    fn default() -> Self {
+        MaterialDefinition {
+            diffuse_texture: String::new(),
+            normal_texture: None,
+            specular_texture: None,
             ambient_reflectivity: 1.0,
             diffuse_reflectivity: 1.0,
             specular_reflectivity: 1.0,
             smoothness: 32.0,
-        })
+            blend_mode: BlendMode::Opaque,
+            alpha_cutoff: 0.5,
+            opacity: 1.0,
+            sampler: SamplerSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("The material definition does not specify a diffuse texture: {}", .0.display())]
+    NoDiffuseTexture(std::path::PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn material_definition_uses_defaults_for_omitted_parameters() {
+        let definition: MaterialDefinition = toml::from_str(
+            r#"
+            diffuse_texture = "cube-diffuse.jpg"
+            smoothness = 8.0
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(definition.diffuse_texture, "cube-diffuse.jpg");
+        assert_eq!(definition.normal_texture, None);
+        assert_eq!(definition.diffuse_reflectivity, 1.0);
+        assert_eq!(definition.smoothness, 8.0);
+        assert_eq!(definition.blend_mode, BlendMode::Opaque);
+        assert_eq!(definition.opacity, 1.0);
+    }
+
+    #[test]
+    fn material_definition_parses_blend_modes() {
+        let definition: MaterialDefinition = toml::from_str(
+            r#"
+            diffuse_texture = "glass.png"
+            blend_mode = "alpha-test"
+            alpha_cutoff = 0.25
+            opacity = 0.5
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(definition.blend_mode, BlendMode::AlphaTest);
+        assert_eq!(definition.alpha_cutoff, 0.25);
+        assert_eq!(definition.opacity, 0.5);
+    }
+
+    #[test]
+    fn material_definition_parses_sampler_settings() {
+        let definition: MaterialDefinition = toml::from_str(
+            r#"
+            diffuse_texture = "floor.ktx2"
+
+            [sampler]
+            address_mode_u = "repeat"
+            mag_filter = "nearest"
+            anisotropy = 8
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(definition.sampler.address_mode_u, wgpu::AddressMode::Repeat);
+        assert_eq!(definition.sampler.address_mode_v, wgpu::AddressMode::ClampToEdge);
+        assert_eq!(definition.sampler.mag_filter, wgpu::FilterMode::Nearest);
+        assert_eq!(definition.sampler.anisotropy, 8);
+    }
+
+    #[test]
+    fn asset_id_distinguishes_materials_from_textures() {
+        assert_eq!(
+            CpuMaterial::asset_id("cube.toml"),
+            AssetId::new(MATERIAL_ASSET_GROUP, "cube.toml")
+        );
+        assert_eq!(
+            CpuMaterial::asset_id("cube-diffuse.jpg"),
+            AssetId::new(TEXTURE_ASSET_GROUP, "cube-diffuse.jpg")
+        );
     }
 }
@@ -1,15 +1,18 @@
 use std::collections::HashMap;
+use std::ops::Range;
 
 use anyhow::Context;
 
+use super::mesh_processing::{self, BoundingBox};
+use crate::base::skinned_vertex::{SkinnedVertex, VertexSkin};
 use crate::base::vertex::Vertex;
 use assam::LoadAsset;
 use plyers::{
     load_ply,
     types::{
-        AsSlice, FACE_ELEMENT, NX_PROPERTY, NY_PROPERTY, NZ_PROPERTY, Ply, Primitive, PropertyDescriptor, S_PROPERTY,
-        T_PROPERTY, TEXTURE_U_PROPERTY, TEXTURE_V_PROPERTY, U_PROPERTY, V_PROPERTY, VERTEX_ELEMENT,
-        VERTEX_INDICES_LIST_PROPERTY, X_PROPERTY, Y_PROPERTY, Z_PROPERTY,
+        AsSlice, FACE_ELEMENT, MATERIAL_INDEX_PROPERTY, NX_PROPERTY, NY_PROPERTY, NZ_PROPERTY, Ply, Primitive,
+        PropertyDescriptor, PropertyId, S_PROPERTY, T_PROPERTY, TEXTURE_U_PROPERTY, TEXTURE_V_PROPERTY, U_PROPERTY,
+        V_PROPERTY, VERTEX_ELEMENT, VERTEX_INDICES_LIST_PROPERTY, Values, X_PROPERTY, Y_PROPERTY, Z_PROPERTY,
     },
 };
 
@@ -19,6 +22,56 @@ pub struct CpuMesh {
     pub texture_names: Vec<String>,
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
+    pub submeshes: Vec<Submesh>,
+    pub bounds: BoundingBox,
+    /// The joints and weights of each vertex, or empty if the mesh is not skinned
+    #[serde(default)]
+    pub skin: Vec<VertexSkin>,
+}
+
+/// Method of generating vertex normals from the geometry of a mesh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalMode {
+    /// Shares normals between adjacent triangles
+    Smooth,
+    /// Gives every triangle its own face normal
+    Flat,
+}
+
+/// Contiguous range of indices within a mesh that is drawn with a single material
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Submesh {
+    pub indices: Range<u32>,
+    /// Index into the materials of the model that owns the mesh
+    pub material: usize,
+}
+
+impl Submesh {
+    /// Reorders the triangles in `indices` such that all triangles sharing a material are
+    /// contiguous, and returns one submesh per material in ascending material order.
+    /// `face_materials` must contain one material index per triangle.
+    pub fn group_by_material(indices: &[u32], face_materials: &[usize]) -> (Vec<u32>, Vec<Submesh>) {
+        let mut faces: Vec<(usize, &[u32])> = face_materials.iter().copied().zip(indices.chunks_exact(3)).collect();
+        faces.sort_by_key(|(material, _)| *material);
+
+        let mut sorted_indices = Vec::with_capacity(indices.len());
+        let mut submeshes: Vec<Submesh> = Vec::new();
+        for (material, face) in faces {
+            let start = sorted_indices.len() as u32;
+            sorted_indices.extend_from_slice(face);
+            let end = sorted_indices.len() as u32;
+
+            match submeshes.last_mut() {
+                Some(last) if last.material == material => last.indices.end = end,
+                _ => submeshes.push(Submesh {
+                    indices: start..end,
+                    material,
+                }),
+            }
+        }
+
+        (sorted_indices, submeshes)
+    }
 }
 
 impl LoadAsset for CpuMesh {
@@ -97,10 +150,6 @@ impl CpuMesh {
 
         tracing::trace!("Located vertex indices property {}", vertex_indices_id);
 
-        if ply.primitive() != Some(Primitive::Triangles) {
-            return Err(Error::NoTriangleFaces.into());
-        }
-
         let mut vertices = vec![Vertex::default(); num_vertices];
 
         let vertex_data = &ply.data;
@@ -136,44 +185,201 @@ impl CpuMesh {
             if let Some(p_idx) = v_p_index.get(TEXTURE_V_PROPERTY) {
                 vertex.tex_coords[1] = vertex_data[p_idx].1.as_slice().unwrap()[i];
             }
-
-            #[cfg(feature = "renormalize")]
-            {
-                // Normalize the normal vector
-                let norm_sq = vertex.normal[0].powi(2) + vertex.normal[1].powi(2) + vertex.normal[2].powi(2);
-                if norm_sq > 0.0 {
-                    let norm = norm_sq.sqrt();
-                    vertex.normal[0] /= norm;
-                    vertex.normal[1] /= norm;
-                    vertex.normal[2] /= norm;
-                }
-            }
         }
 
-        let indices: Vec<u32> = ply.data[&vertex_indices_id]
-            .1
-            .as_slice()
-            .map(|inner: &[u32]| inner)
-            .iter()
-            .flat_map(|inner| inner.iter())
-            .copied()
-            .collect();
+        let polygons: Vec<u32> = Self::integer_values(&ply.data[&vertex_indices_id].1).ok_or(Error::NoVertexIndices)?;
+        let face_lengths = Self::face_lengths(ply, vertex_indices_id, polygons.len())?;
+        let mut indices = mesh_processing::triangulate(face_lengths.iter().scan(0, |start, &len| {
+            let face = &polygons[*start..*start + len];
+            *start += len;
+            Some(face)
+        }));
 
         tracing::trace!("Loaded {} vertices and {} indices", vertex_data.len(), indices.len());
 
+        let material_index_id = ply.descriptor.elements[&f_e_id]
+            .properties
+            .iter()
+            .find_map(|(&p_id, p)| match p {
+                PropertyDescriptor::Scalar { name, .. } if name == MATERIAL_INDEX_PROPERTY => Some(p_id),
+                _ => None,
+            });
+
+        let submeshes = match material_index_id {
+            Some(p_id) => {
+                let face_materials: Vec<usize> =
+                    Self::integer_values(&ply.data[&p_id].1).ok_or(Error::InvalidMaterialIndex)?;
+                if face_materials.len() != face_lengths.len() {
+                    return Err(Error::InvalidMaterialIndex.into());
+                }
+
+                // Each face was split into `length - 2` triangles
+                let face_materials: Vec<usize> = face_materials
+                    .into_iter()
+                    .zip(&face_lengths)
+                    .flat_map(|(m, &len)| std::iter::repeat_n(m, len.saturating_sub(2)))
+                    .collect();
+
+                let (sorted_indices, submeshes) = Submesh::group_by_material(&indices, &face_materials);
+                indices = sorted_indices;
+                submeshes
+            }
+            None => vec![Submesh {
+                indices: 0..indices.len() as u32,
+                material: 0,
+            }],
+        };
+
+        tracing::trace!("Located {} submeshes", submeshes.len());
+
         let texture_names: Vec<_> = Self::find_texture_names(ply).map(|n| n.to_owned()).collect();
 
         tracing::trace!("Located the following texture names: {}", texture_names.join(", "));
 
-        Ok(CpuMesh {
+        let mut mesh = CpuMesh {
             label,
             texture_names,
             vertices,
             indices,
-        })
+            submeshes,
+            bounds: BoundingBox::default(),
+            skin: Vec::new(),
+        };
+        mesh.finalize();
+
+        Ok(mesh)
+    }
+
+    /// Prepares an imported mesh for rendering: normalizes the normals or computes smooth normals
+    /// if there are none, generates missing tangents, merges duplicate vertices and updates the
+    /// bounding box. The order of indices, and thus the submeshes, are preserved.
+    pub fn finalize(&mut self) {
+        if self.is_skinned() && self.skin.len() != self.vertices.len() {
+            tracing::warn!(
+                "The mesh {:?} has {} vertices but {} vertex skins, ignoring the skin",
+                self.label,
+                self.vertices.len(),
+                self.skin.len()
+            );
+            self.skin.clear();
+        }
+
+        if mesh_processing::has_normals(&self.vertices) {
+            mesh_processing::normalize_normals(&mut self.vertices);
+        } else {
+            mesh_processing::compute_smooth_normals(&mut self.vertices, &self.indices);
+        }
+
+        let has_tex_coords = self.vertices.iter().any(|v| v.tex_coords != [0.0; 2]);
+        if has_tex_coords && !mesh_processing::has_tangents(&self.vertices) {
+            match mesh_processing::compute_tangents(&self.vertices, &self.indices) {
+                Some((vertices, indices)) => {
+                    self.unweld_skin();
+                    (self.vertices, self.indices) = (vertices, indices)
+                }
+                None => tracing::warn!("Unable to generate tangents for the mesh {:?}", self.label),
+            }
+        }
+
+        if self.is_skinned() {
+            let (vertices, indices) = mesh_processing::weld_vertices(&self.skinned_vertices(), &self.indices);
+            (self.vertices, self.skin) = vertices.into_iter().map(|v| (v.vertex, v.skin)).unzip();
+            self.indices = indices;
+        } else {
+            (self.vertices, self.indices) = mesh_processing::weld_vertices(&self.vertices, &self.indices);
+        }
+        self.bounds = BoundingBox::from_vertices(&self.vertices);
     }
 
-    fn find_texture_names(ply: &Ply) -> impl Iterator<Item = &str> {
+    /// Replaces the normals of the mesh and regenerates its tangents
+    pub fn recompute_normals(&mut self, mode: NormalMode) {
+        match mode {
+            NormalMode::Smooth => mesh_processing::compute_smooth_normals(&mut self.vertices, &self.indices),
+            NormalMode::Flat => {
+                self.unweld_skin();
+                (self.vertices, self.indices) = mesh_processing::compute_flat_normals(&self.vertices, &self.indices)
+            }
+        }
+
+        for v in &mut self.vertices {
+            v.tangent = [0.0; 4];
+        }
+
+        self.finalize();
+    }
+
+    /// Returns `true` if the vertices of the mesh are deformed by a skeleton
+    pub fn is_skinned(&self) -> bool {
+        !self.skin.is_empty()
+    }
+
+    /// Pairs every vertex with its skin. Returns an empty vector if the mesh is not skinned.
+    pub fn skinned_vertices(&self) -> Vec<SkinnedVertex> {
+        self.vertices
+            .iter()
+            .zip(&self.skin)
+            .map(|(&vertex, &skin)| SkinnedVertex { vertex, skin })
+            .collect()
+    }
+
+    /// Gives each index its own vertex skin, ahead of unwelding the vertices themselves
+    fn unweld_skin(&mut self) {
+        if self.is_skinned() {
+            self.skin = mesh_processing::unweld_vertices(&self.skin, &self.indices).0;
+        }
+    }
+
+    /// Returns the number of vertices of each face. Faces of uniform length are split by their
+    /// primitive, faces of varying length by the list lengths recorded by the parser.
+    fn face_lengths(ply: &Ply, vertex_indices_id: PropertyId, num_indices: usize) -> Result<Vec<usize>, Error> {
+        let face_lengths = match ply.data[&vertex_indices_id].0 {
+            Primitive::Mixed => ply
+                .list_lengths
+                .get(&vertex_indices_id)
+                .cloned()
+                .ok_or(Error::UnknownFaceLengths)?,
+            primitive => {
+                let length = usize::try_from(primitive).map_err(|_| Error::UnknownFaceLengths)?;
+                vec![length; num_indices / length]
+            }
+        };
+
+        if face_lengths.iter().sum::<usize>() != num_indices {
+            return Err(Error::UnknownFaceLengths);
+        }
+
+        Ok(face_lengths)
+    }
+
+    fn integer_values<U>(values: &Values) -> Option<Vec<U>>
+    where
+        U: TryFrom<u8>
+            + TryFrom<i8>
+            + TryFrom<u16>
+            + TryFrom<i16>
+            + TryFrom<u32>
+            + TryFrom<i32>
+            + TryFrom<u64>
+            + TryFrom<i64>,
+    {
+        fn convert<T: Copy, U: TryFrom<T>>(v: &[T]) -> Option<Vec<U>> {
+            v.iter().map(|&i| U::try_from(i).ok()).collect()
+        }
+
+        match values {
+            Values::U8(v) => convert(v),
+            Values::I8(v) => convert(v),
+            Values::U16(v) => convert(v),
+            Values::I16(v) => convert(v),
+            Values::U32(v) => convert(v),
+            Values::I32(v) => convert(v),
+            Values::U64(v) => convert(v),
+            Values::I64(v) => convert(v),
+            Values::F32(_) | Values::F64(_) => None,
+        }
+    }
+
+    pub(crate) fn find_texture_names(ply: &Ply) -> impl Iterator<Item = &str> {
         ply.descriptor
             .comments
             .iter()
@@ -213,8 +419,161 @@ pub enum Error {
     NoFaceElement,
     #[error("The element named 'face' contains no property 'vertex_indices' with triangle indices")]
     NoVertexIndices,
-    #[error("The mesh does not use triangles as face primitive")]
-    NoTriangleFaces,
+    #[error("The number of vertices of each face of the mesh is unknown")]
+    UnknownFaceLengths,
+    #[error("The property 'material_index' of the element 'face' must hold one non-negative integer per face")]
+    InvalidMaterialIndex,
     #[error(transparent)]
     Ply(#[from] plyers::PlyError),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_by_material_makes_faces_with_the_same_material_contiguous() {
+        let indices = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+        let face_materials = [1, 0, 1, 2];
+
+        let (sorted, submeshes) = Submesh::group_by_material(&indices, &face_materials);
+
+        assert_eq!(sorted, vec![3, 4, 5, 0, 1, 2, 6, 7, 8, 9, 10, 11]);
+        assert_eq!(
+            submeshes,
+            vec![
+                Submesh {
+                    indices: 0..3,
+                    material: 0
+                },
+                Submesh {
+                    indices: 3..9,
+                    material: 1
+                },
+                Submesh {
+                    indices: 9..12,
+                    material: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finalize_keeps_vertex_skins_with_their_vertices() {
+        let corner = |x: f32, y: f32| Vertex {
+            position: [x, y, 0.0],
+            tex_coords: [x, y],
+            ..Default::default()
+        };
+        let skin = |joint: u32| VertexSkin {
+            joints: [joint, 0, 0, 0],
+            weights: [1.0, 0.0, 0.0, 0.0],
+        };
+        let mut mesh = CpuMesh {
+            label: None,
+            texture_names: Vec::new(),
+            vertices: vec![corner(0.0, 0.0), corner(1.0, 0.0), corner(1.0, 1.0), corner(0.0, 1.0)],
+            indices: vec![0, 1, 2, 0, 2, 3],
+            submeshes: Vec::new(),
+            bounds: BoundingBox::default(),
+            skin: (0..4).map(skin).collect(),
+        };
+
+        mesh.finalize();
+
+        assert_eq!(mesh.skin.len(), mesh.vertices.len());
+        for &i in &mesh.indices {
+            let v = &mesh.vertices[i as usize];
+            let expected = match v.position {
+                [0.0, 0.0, _] => 0,
+                [1.0, 0.0, _] => 1,
+                [1.0, 1.0, _] => 2,
+                _ => 3,
+            };
+            assert_eq!(mesh.skin[i as usize], skin(expected));
+        }
+    }
+
+    #[test]
+    fn with_ply_triangulates_pentagons() {
+        let ply = plyers::parse_ply(
+            b"ply
+format ascii 1.0
+element vertex 5
+property float x
+property float y
+property float z
+element face 1
+property list uchar uint vertex_indices
+end_header
+0 0 0
+1 0 0
+1.5 1 0
+0.5 1.5 0
+-0.5 1 0
+5 0 1 2 3 4
+",
+        )
+        .unwrap();
+
+        let mesh = CpuMesh::with_ply(&ply, None).unwrap();
+
+        assert_eq!(mesh.indices.len(), 9);
+        assert_eq!(
+            mesh.submeshes,
+            vec![Submesh {
+                indices: 0..9,
+                material: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn with_ply_triangulates_mixed_triangles_and_quads() {
+        let ply = plyers::parse_ply(
+            b"ply
+format ascii 1.0
+element vertex 6
+property float x
+property float y
+property float z
+element face 2
+property list uchar uint vertex_indices
+property uchar material_index
+end_header
+0 0 0
+1 0 0
+1 1 0
+0 1 0
+2 0 0
+2 1 0
+4 0 1 2 3 1
+3 1 4 5 0
+",
+        )
+        .unwrap();
+
+        let mesh = CpuMesh::with_ply(&ply, None).unwrap();
+
+        let positions: Vec<[f32; 3]> = mesh
+            .indices
+            .iter()
+            .map(|&i| mesh.vertices[i as usize].position)
+            .collect();
+        assert_eq!(positions.len(), 9);
+        assert_eq!(&positions[..3], &[[1.0, 0.0, 0.0], [2.0, 0.0, 0.0], [2.0, 1.0, 0.0]]);
+        assert_eq!(
+            mesh.submeshes,
+            vec![
+                Submesh {
+                    indices: 0..3,
+                    material: 0
+                },
+                Submesh {
+                    indices: 3..9,
+                    material: 1
+                },
+            ]
+        );
+    }
+}
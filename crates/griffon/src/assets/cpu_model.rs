@@ -1,12 +1,18 @@
 use std::path::Path;
 
+use super::gltf_import::{GLB_FILE_EXTENSION, GLTF_FILE_EXTENSION, gltf_dependencies, load_gltf, load_gltf_meshes};
+use super::obj_import::{OBJ_FILE_EXTENSION, load_obj, obj_dependencies};
 use super::{cpu_material::CpuMaterial, cpu_mesh::CpuMesh};
 use anyhow::Context;
-use assam::{AssetDatabase, LoadAsset};
+use assam::{AssetDatabase, AssetDependencies, AssetId, LoadAsset};
 use ecs::Resources;
-use tracing::trace;
+use plyers::load_ply;
+use tracing::{trace, warn};
 
-pub const MATERIAL_ASSET_GROUP: &str = "textures";
+pub const MODEL_ASSET_GROUP: &str = "models";
+/// File extensions of the model formats supported by [`CpuModel`]. Other files in the models
+/// group, such as material libraries or buffers, are companions of a model.
+pub const MODEL_FILE_EXTENSIONS: &[&str] = &["ply", OBJ_FILE_EXTENSION, GLTF_FILE_EXTENSION, GLB_FILE_EXTENSION];
 
 #[derive(Debug)]
 pub struct CpuModel {
@@ -14,10 +20,8 @@ pub struct CpuModel {
     pub materials: Vec<CpuMaterial>,
 }
 
-impl LoadAsset for CpuModel {
-    type Output = Self;
-
-    async fn with_path(res: &Resources, path: &Path) -> anyhow::Result<Self::Output> {
+impl CpuModel {
+    async fn with_ply(res: &Resources, path: &Path) -> anyhow::Result<Self> {
         let mesh = CpuMesh::with_path(res, path)
             .await
             .with_context(|| format!("Loading a CpuMesh from '{}'", path.display()))?;
@@ -25,21 +29,75 @@ impl LoadAsset for CpuModel {
 
         let mut materials = Vec::new();
         for name in &mesh.texture_names {
+            let asset_id = CpuMaterial::asset_id(name);
             let cpu_mat = res
                 .read::<AssetDatabase>()
-                .load_asset::<CpuMaterial, _>(res, MATERIAL_ASSET_GROUP, name)
+                .load_asset::<CpuMaterial, _>(res, &asset_id.group, &asset_id.name)
                 .await
-                .with_context(|| {
-                    format!(
-                        "Loading a CpuMaterial from group {} and name {}",
-                        MATERIAL_ASSET_GROUP, name
-                    )
-                })?;
+                .with_context(|| format!("Loading a CpuMaterial from {}", asset_id))?;
             trace!("Loaded CpuMaterial with size {} bytes", size_of_val(&cpu_mat));
 
             materials.push(cpu_mat);
         }
 
+        if let Some(submesh) = mesh.submeshes.iter().find(|sm| sm.material >= materials.len()) {
+            warn!(
+                "The mesh at '{}' refers to material {}, but only {} materials are defined",
+                path.display(),
+                submesh.material,
+                materials.len()
+            );
+        }
+
         Ok(CpuModel { mesh, materials })
     }
 }
+
+impl LoadAsset for CpuModel {
+    type Output = Self;
+
+    async fn with_path(res: &Resources, path: &Path) -> anyhow::Result<Self::Output> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ply") => Self::with_ply(res, path).await,
+            Some(OBJ_FILE_EXTENSION) => load_obj(res, path).await,
+            Some(GLTF_FILE_EXTENSION | GLB_FILE_EXTENSION) => load_gltf(path),
+            _ => Err(assam::Error::UnsupportedFileFormat.into()),
+        }
+    }
+}
+
+impl AssetDependencies for CpuModel {
+    async fn dependencies(asset: &AssetId, path: &Path) -> anyhow::Result<Vec<AssetId>> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ply") => {
+                let ply =
+                    load_ply(path).with_context(|| format!("Loading a Stanford Ply file from '{}'", path.display()))?;
+                Ok(CpuMesh::find_texture_names(&ply).map(CpuMaterial::asset_id).collect())
+            }
+            Some(OBJ_FILE_EXTENSION) => obj_dependencies(asset, path),
+            Some(GLTF_FILE_EXTENSION | GLB_FILE_EXTENSION) => gltf_dependencies(asset, path),
+            _ => Err(assam::Error::UnsupportedFileFormat.into()),
+        }
+    }
+}
+
+/// The meshes of a model file that contains several, such as a glTF document, each in its local
+/// coordinate system with its own materials. The models are indexed like
+/// [`CpuNode::mesh`](super::gltf_import::CpuNode::mesh).
+#[derive(Debug)]
+pub struct CpuModelSet {
+    pub models: Vec<CpuModel>,
+}
+
+impl LoadAsset for CpuModelSet {
+    type Output = Self;
+
+    async fn with_path(_res: &Resources, path: &Path) -> anyhow::Result<Self::Output> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(GLTF_FILE_EXTENSION | GLB_FILE_EXTENSION) => Ok(CpuModelSet {
+                models: load_gltf_meshes(path)?,
+            }),
+            _ => Err(assam::Error::UnsupportedFileFormat.into()),
+        }
+    }
+}
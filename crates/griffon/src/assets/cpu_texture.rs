@@ -1,17 +1,123 @@
+use anyhow::{Context, anyhow, bail};
 use assam::{Error, LoadAsset};
 
+use crate::resources::Graphics;
+
+pub const KTX2_FILE_EXTENSION: &str = "ktx2";
+
 #[derive(Debug)]
 pub struct CpuTexture {
     pub label: Option<String>,
-    pub image: image::DynamicImage,
+    pub data: TextureData,
+}
+
+#[derive(Debug)]
+pub enum TextureData {
+    /// An image that is converted to RGBA8 and mipmapped on upload
+    Image(image::DynamicImage),
+    /// A block-compressed mip chain that is uploaded as is
+    Compressed(CompressedImage),
+}
+
+/// The mip levels of a block-compressed texture, as stored in a KTX2 container
+#[derive(Debug)]
+pub struct CompressedImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+    /// The data of each mip level, starting with the full-size image
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl CompressedImage {
+    /// Parses a KTX2 container with a single 2D image in BC7 format, without supercompression
+    pub fn with_ktx2(bytes: &[u8]) -> anyhow::Result<Self> {
+        let reader = ktx2::Reader::new(bytes).map_err(|e| anyhow!("Parsing the KTX2 container: {e}"))?;
+        let header = reader.header();
+
+        let format = match header.format {
+            Some(ktx2::Format::BC7_UNORM_BLOCK) => wgpu::TextureFormat::Bc7RgbaUnorm,
+            Some(ktx2::Format::BC7_SRGB_BLOCK) => wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+            f => bail!("The KTX2 texture format {f:?} is not supported, only BC7 is"),
+        };
+        if let Some(scheme) = header.supercompression_scheme {
+            bail!("The KTX2 supercompression scheme {scheme:?} is not supported");
+        }
+        if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+            bail!("Only KTX2 containers with a single 2D image are supported");
+        }
+        let (block_width, block_height) = format.block_dimensions();
+        if header.pixel_width % block_width != 0 || header.pixel_height % block_height != 0 {
+            bail!(
+                "The dimensions {}x{} of the KTX2 texture are not a multiple of the block size",
+                header.pixel_width,
+                header.pixel_height
+            );
+        }
+
+        Ok(CompressedImage {
+            width: header.pixel_width,
+            height: header.pixel_height.max(1),
+            format,
+            levels: reader.levels().map(|l| l.data.to_vec()).collect(),
+        })
+    }
+}
+
+impl CpuTexture {
+    /// Creates a single-pixel texture from a color given in linear RGBA. The color channels are
+    /// encoded as sRGB to match the format used for color textures.
+    pub fn with_color(label: Option<String>, color: [f32; 4]) -> Self {
+        fn encode(c: f32) -> u8 {
+            let c = c.clamp(0.0, 1.0);
+            let srgb = if c <= 0.003_130_8 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+            (srgb * 255.0).round() as u8
+        }
+
+        let pixel = image::Rgba([
+            encode(color[0]),
+            encode(color[1]),
+            encode(color[2]),
+            (color[3].clamp(0.0, 1.0) * 255.0).round() as u8,
+        ]);
+
+        let image = image::RgbaImage::from_pixel(1, 1, pixel);
+        CpuTexture {
+            label,
+            data: TextureData::Image(image::DynamicImage::ImageRgba8(image)),
+        }
+    }
 }
 
 impl LoadAsset for CpuTexture {
     type Output = Self;
 
-    async fn with_path(_res: &ecs::Resources, path: &std::path::Path) -> anyhow::Result<Self::Output> {
+    async fn with_path(res: &ecs::Resources, path: &std::path::Path) -> anyhow::Result<Self::Output> {
         let label = path.file_stem().and_then(|n| n.to_str()).map(|n| n.to_owned());
 
+        if path.extension().and_then(|ext| ext.to_str()) == Some(KTX2_FILE_EXTENSION) {
+            let bytes = std::fs::read(path)?;
+            let image = CompressedImage::with_ktx2(&bytes)
+                .with_context(|| format!("Loading a compressed texture from '{}'", path.display()))?;
+            // Without graphics, such as during asset validation, the texture is only parsed
+            if res.contains::<Graphics>() && !res.read::<Graphics>().supports_texture_format(image.format) {
+                bail!(
+                    "The graphics adapter does not support the format {:?} of the texture '{}'",
+                    image.format,
+                    path.display()
+                );
+            }
+
+            return Ok(CpuTexture {
+                label,
+                data: TextureData::Compressed(image),
+            });
+        }
+
         let image_format = path
             .extension()
             .and_then(|ext| ext.to_str())
@@ -22,6 +128,9 @@ impl LoadAsset for CpuTexture {
         let buf = std::io::BufReader::new(f);
         let image = image::load(buf, image_format)?;
 
-        Ok(CpuTexture { label, image })
+        Ok(CpuTexture {
+            label,
+            data: TextureData::Image(image),
+        })
     }
 }
@@ -0,0 +1,773 @@
+use std::path::Path;
+
+use anyhow::{Context, anyhow, bail};
+use assam::{AssetId, LoadAsset};
+use ecs::Resources;
+use tracing::warn;
+
+use super::animation_clip::{AnimationChannel, AnimationClip, Interpolation, Keyframes};
+use super::cpu_material::{CpuMaterial, MaterialDefinition};
+use super::cpu_mesh::{CpuMesh, Submesh};
+use super::cpu_model::CpuModel;
+use super::cpu_texture::{CpuTexture, TextureData};
+use super::mesh_processing::BoundingBox;
+use crate::base::blend_mode::BlendMode;
+use crate::base::sampler_builder::SamplerSettings;
+use crate::base::skinned_vertex::VertexSkin;
+use crate::base::vertex::Vertex;
+
+pub const GLTF_FILE_EXTENSION: &str = "gltf";
+pub const GLB_FILE_EXTENSION: &str = "glb";
+
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Node of a glTF scene with its local transform
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuNode {
+    pub name: Option<String>,
+    /// Index of the parent node within the [`CpuNodeTree`]
+    pub parent: Option<usize>,
+    pub translation: [f32; 3],
+    /// Rotation quaternion in the order x, y, z, w
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+    /// Index of the mesh within the glTF document, see [`CpuModelSet`](super::cpu_model::CpuModelSet)
+    pub mesh: Option<usize>,
+    /// Index of the skin within the [`CpuNodeTree`] that deforms the mesh of the node
+    pub skin: Option<usize>,
+}
+
+/// Joints that deform a skinned mesh
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuSkin {
+    /// Index of each joint node within the [`CpuNodeTree`], in the order referenced by
+    /// [`VertexSkin::joints`]
+    pub joints: Vec<usize>,
+    /// Transforms model space positions into the local space of each joint in its bind pose,
+    /// column-major
+    pub inverse_bind_matrices: Vec<[[f32; 4]; 4]>,
+}
+
+/// Node hierarchy of the default scene of a glTF document. Parents always precede their
+/// children.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpuNodeTree {
+    pub label: Option<String>,
+    pub nodes: Vec<CpuNode>,
+    pub skins: Vec<CpuSkin>,
+    pub animations: Vec<AnimationClip>,
+}
+
+impl CpuNodeTree {
+    /// Skins and animations are read from `buffers`, and skipped if the data they refer to is
+    /// missing. Animated nodes outside of the default scene are dropped. Fails if a skin refers
+    /// to a joint outside of the default scene, or lacks an inverse bind matrix for a joint.
+    fn with_document(
+        label: Option<String>,
+        document: &gltf::Document,
+        buffers: &[gltf::buffer::Data],
+    ) -> anyhow::Result<Self> {
+        fn visit(node: gltf::Node, parent: Option<usize>, nodes: &mut Vec<CpuNode>, lookup: &mut [Option<usize>]) {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            let index = nodes.len();
+            lookup[node.index()] = Some(index);
+            nodes.push(CpuNode {
+                name: node.name().map(|n| n.to_owned()),
+                parent,
+                translation,
+                rotation,
+                scale,
+                mesh: node.mesh().map(|m| m.index()),
+                skin: node.skin().map(|s| s.index()),
+            });
+
+            for child in node.children() {
+                visit(child, Some(index), nodes, lookup);
+            }
+        }
+
+        let mut nodes = Vec::new();
+        let mut lookup = vec![None; document.nodes().len()];
+        if let Some(scene) = default_scene(document) {
+            for node in scene.nodes() {
+                visit(node, None, &mut nodes, &mut lookup);
+            }
+        }
+
+        let get_buffer_data = |b: gltf::Buffer| buffers.get(b.index()).map(|d| &d.0[..]);
+        let skins = document
+            .skins()
+            .map(|skin| {
+                // The joint indices of the vertices refer to the joints by position, so a skin
+                // cannot drop joints without deforming the mesh incorrectly
+                let joints = skin
+                    .joints()
+                    .map(|j| {
+                        lookup[j.index()].ok_or_else(|| {
+                            anyhow!(
+                                "Skin {} refers to the joint node {} outside of the default scene",
+                                skin.index(),
+                                j.index()
+                            )
+                        })
+                    })
+                    .collect::<anyhow::Result<Vec<usize>>>()?;
+                let inverse_bind_matrices: Vec<_> = match skin.reader(get_buffer_data).read_inverse_bind_matrices() {
+                    Some(matrices) => matrices.take(joints.len()).collect(),
+                    None => vec![IDENTITY; joints.len()],
+                };
+                if inverse_bind_matrices.len() != joints.len() {
+                    bail!(
+                        "Skin {} has {} joints but {} inverse bind matrices",
+                        skin.index(),
+                        joints.len(),
+                        inverse_bind_matrices.len()
+                    );
+                }
+
+                Ok(CpuSkin {
+                    joints,
+                    inverse_bind_matrices,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let animations = document
+            .animations()
+            .map(|animation| animation_from_gltf(&animation, &lookup, get_buffer_data))
+            .collect();
+
+        Ok(CpuNodeTree {
+            label,
+            nodes,
+            skins,
+            animations,
+        })
+    }
+}
+
+impl LoadAsset for CpuNodeTree {
+    type Output = Self;
+
+    async fn with_path(_res: &Resources, path: &Path) -> anyhow::Result<Self::Output> {
+        let label = path.file_stem().and_then(|n| n.to_str()).map(|n| n.to_owned());
+        let gltf =
+            gltf::Gltf::open(path).with_context(|| format!("Loading a glTF document from '{}'", path.display()))?;
+        let buffers = gltf::import_buffers(&gltf.document, path.parent(), gltf.blob.clone())
+            .with_context(|| format!("Loading the buffers of the glTF document '{}'", path.display()))?;
+
+        Self::with_document(label, &gltf, &buffers)
+            .with_context(|| format!("Loading the node tree of the glTF document '{}'", path.display()))
+    }
+}
+
+/// Collects the translation, rotation and scale channels of an animation per node. Cubic spline
+/// keyframes are approximated by interpolating their values linearly, morph target weights are
+/// not supported.
+fn animation_from_gltf<'a, 's, F>(
+    animation: &gltf::Animation<'a>,
+    lookup: &[Option<usize>],
+    get_buffer_data: F,
+) -> AnimationClip
+where
+    F: Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>,
+{
+    use gltf::animation::util::ReadOutputs;
+
+    let mut channels: Vec<AnimationChannel> = Vec::new();
+    for channel in animation.channels() {
+        let Some(node) = lookup[channel.target().node().index()] else {
+            continue;
+        };
+        let reader = channel.reader(get_buffer_data.clone());
+        let (Some(times), Some(outputs)) = (reader.read_inputs(), reader.read_outputs()) else {
+            warn!(
+                "Skipping a channel of animation {} without keyframes",
+                animation.index()
+            );
+            continue;
+        };
+
+        let times: Vec<f32> = times.collect();
+        let interpolation = channel.sampler().interpolation();
+
+        let index = match channels.iter().position(|c| c.node == node) {
+            Some(index) => index,
+            None => {
+                channels.push(AnimationChannel {
+                    node,
+                    translation: None,
+                    rotation: None,
+                    scale: None,
+                });
+                channels.len() - 1
+            }
+        };
+        let target = &mut channels[index];
+        match outputs {
+            ReadOutputs::Translations(t) => target.translation = Some(keyframes_from_gltf(&times, t, interpolation)),
+            ReadOutputs::Rotations(r) => {
+                target.rotation = Some(keyframes_from_gltf(&times, r.into_f32(), interpolation))
+            }
+            ReadOutputs::Scales(s) => target.scale = Some(keyframes_from_gltf(&times, s, interpolation)),
+            ReadOutputs::MorphTargetWeights(_) => {
+                warn!("Skipping morph target weights of animation {}", animation.index())
+            }
+        }
+    }
+
+    AnimationClip::new(animation.name().map(|n| n.to_owned()), channels)
+}
+
+/// Loads a glTF model with all meshes of the default scene merged and their node transforms
+/// applied. External buffers and images are resolved relative to the glTF file.
+#[tracing::instrument]
+pub(crate) fn load_gltf(path: &Path) -> anyhow::Result<CpuModel> {
+    fn visit(node: gltf::Node, parent: &[[f32; 4]; 4], buffers: &[gltf::buffer::Data], b: &mut MeshBuilder) {
+        let world = mat_mul(parent, &node.transform().matrix());
+        if let Some(mesh) = node.mesh() {
+            b.add_mesh(&mesh, buffers, &world);
+        }
+
+        for child in node.children() {
+            visit(child, &world, buffers, b);
+        }
+    }
+
+    let label = path.file_stem().and_then(|n| n.to_str()).map(|n| n.to_owned());
+    let (document, buffers, images) =
+        gltf::import(path).with_context(|| format!("Loading a glTF model from '{}'", path.display()))?;
+
+    let mut builder = MeshBuilder::default();
+    if let Some(scene) = default_scene(&document) {
+        for node in scene.nodes() {
+            visit(node, &IDENTITY, &buffers, &mut builder);
+        }
+    }
+
+    builder.build(label, &document, &images)
+}
+
+/// Loads every mesh of a glTF document as a separate model in its local coordinate system,
+/// parsing the document once. External buffers and images are resolved relative to the glTF
+/// file.
+#[tracing::instrument]
+pub(crate) fn load_gltf_meshes(path: &Path) -> anyhow::Result<Vec<CpuModel>> {
+    let label = path.file_stem().and_then(|n| n.to_str()).map(|n| n.to_owned());
+    let (document, buffers, images) =
+        gltf::import(path).with_context(|| format!("Loading a glTF model from '{}'", path.display()))?;
+
+    models_from_document(label, &document, &buffers, &images)
+}
+
+/// Builds one model per mesh of a glTF document, in the order of the document
+fn models_from_document(
+    label: Option<String>,
+    document: &gltf::Document,
+    buffers: &[gltf::buffer::Data],
+    images: &[gltf::image::Data],
+) -> anyhow::Result<Vec<CpuModel>> {
+    document
+        .meshes()
+        .map(|mesh| {
+            // Only meshes in their local coordinate system can be deformed by their skeleton
+            let mut builder = MeshBuilder {
+                read_skin: true,
+                ..Default::default()
+            };
+            builder.add_mesh(&mesh, buffers, &IDENTITY);
+            builder.build(label.clone(), document, images)
+        })
+        .collect()
+}
+
+/// Returns the external buffers and images referenced by a glTF document as assets relative to
+/// the document
+pub(crate) fn gltf_dependencies(asset: &AssetId, path: &Path) -> anyhow::Result<Vec<AssetId>> {
+    let gltf = gltf::Gltf::open(path).with_context(|| format!("Loading a glTF document from '{}'", path.display()))?;
+
+    let buffer_uris = gltf.buffers().filter_map(|b| match b.source() {
+        gltf::buffer::Source::Uri(uri) => Some(uri),
+        gltf::buffer::Source::Bin => None,
+    });
+    let image_uris = gltf.images().filter_map(|i| match i.source() {
+        gltf::image::Source::Uri { uri, .. } => Some(uri),
+        gltf::image::Source::View { .. } => None,
+    });
+
+    Ok(buffer_uris
+        .chain(image_uris)
+        .filter(|uri| !uri.starts_with("data:"))
+        .map(|uri| asset.sibling(uri))
+        .collect())
+}
+
+fn default_scene(document: &gltf::Document) -> Option<gltf::Scene<'_>> {
+    document.default_scene().or_else(|| document.scenes().next())
+}
+
+#[derive(Debug, Default)]
+struct MeshBuilder {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    face_materials: Vec<usize>,
+    /// glTF material index of each material slot, where `None` refers to the default material
+    material_slots: Vec<Option<usize>>,
+    read_skin: bool,
+    /// Either empty or one skin per vertex
+    skin: Vec<VertexSkin>,
+}
+
+impl MeshBuilder {
+    fn add_mesh(&mut self, mesh: &gltf::Mesh, buffers: &[gltf::buffer::Data], transform: &[[f32; 4]; 4]) {
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                warn!(
+                    "Skipping a primitive of mesh {} with unsupported mode {:?}",
+                    mesh.index(),
+                    primitive.mode()
+                );
+                continue;
+            }
+
+            let reader = primitive.reader(|b| buffers.get(b.index()).map(|d| &d.0[..]));
+            let Some(positions) = reader.read_positions() else {
+                warn!("Skipping a primitive of mesh {} without positions", mesh.index());
+                continue;
+            };
+
+            let base_index = self.vertices.len() as u32;
+            self.vertices.extend(positions.map(|p| Vertex {
+                position: transform_point(transform, p),
+                ..Default::default()
+            }));
+            let vertices = &mut self.vertices[base_index as usize..];
+
+            if let Some(normals) = reader.read_normals() {
+                for (v, n) in vertices.iter_mut().zip(normals) {
+                    v.normal = transform_normal(transform, n);
+                }
+            }
+
+            if let Some(tangents) = reader.read_tangents() {
+                for (v, t) in vertices.iter_mut().zip(tangents) {
+                    let [x, y, z] = transform_direction(transform, [t[0], t[1], t[2]]);
+                    v.tangent = [x, y, z, t[3]];
+                }
+            }
+
+            if let Some(tex_coords) = reader.read_tex_coords(0) {
+                for (v, t) in vertices.iter_mut().zip(tex_coords.into_f32()) {
+                    v.tex_coords = t;
+                }
+            }
+
+            if let (true, Some(joints), Some(weights)) = (self.read_skin, reader.read_joints(0), reader.read_weights(0))
+            {
+                // Vertices of earlier primitives without joints are not deformed
+                self.skin.resize(base_index as usize, VertexSkin::default());
+                self.skin
+                    .extend(joints.into_u16().zip(weights.into_f32()).map(|(j, w)| {
+                        let total: f32 = w.iter().sum();
+                        VertexSkin {
+                            joints: j.map(u32::from),
+                            weights: if total > 0.0 { w.map(|w| w / total) } else { w },
+                        }
+                    }));
+            }
+            if !self.skin.is_empty() {
+                self.skin.resize(self.vertices.len(), VertexSkin::default());
+            }
+
+            let num_indices_before = self.indices.len();
+            match reader.read_indices() {
+                Some(indices) => self.indices.extend(indices.into_u32().map(|i| base_index + i)),
+                None => self.indices.extend(base_index..self.vertices.len() as u32),
+            }
+
+            let material = primitive.material().index();
+            let slot = match self.material_slots.iter().position(|&s| s == material) {
+                Some(slot) => slot,
+                None => {
+                    self.material_slots.push(material);
+                    self.material_slots.len() - 1
+                }
+            };
+            let num_faces = (self.indices.len() - num_indices_before) / 3;
+            self.face_materials.extend(std::iter::repeat_n(slot, num_faces));
+        }
+    }
+
+    /// Creates the model from the added meshes with one submesh per material slot
+    fn build(
+        self,
+        label: Option<String>,
+        document: &gltf::Document,
+        images: &[gltf::image::Data],
+    ) -> anyhow::Result<CpuModel> {
+        let materials = self
+            .material_slots
+            .iter()
+            .map(|slot| match slot.and_then(|i| document.materials().nth(i)) {
+                Some(material) => material_from_gltf(&material, images),
+                None => Ok(CpuMaterial::with_color(None, [1.0; 4])),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let (indices, submeshes) = Submesh::group_by_material(&self.indices, &self.face_materials);
+
+        let mut mesh = CpuMesh {
+            label,
+            texture_names: Vec::new(),
+            vertices: self.vertices,
+            indices,
+            submeshes,
+            bounds: BoundingBox::default(),
+            skin: self.skin,
+        };
+        mesh.finalize();
+
+        Ok(CpuModel { mesh, materials })
+    }
+}
+
+fn keyframes_from_gltf<T>(
+    times: &[f32],
+    values: impl Iterator<Item = T>,
+    interpolation: gltf::animation::Interpolation,
+) -> Keyframes<T> {
+    let (values, interpolation) = match interpolation {
+        gltf::animation::Interpolation::Step => (values.collect(), Interpolation::Step),
+        gltf::animation::Interpolation::Linear => (values.collect(), Interpolation::Linear),
+        // Every keyframe consists of an in-tangent, a value and an out-tangent
+        gltf::animation::Interpolation::CubicSpline => (values.skip(1).step_by(3).collect(), Interpolation::Linear),
+    };
+
+    Keyframes {
+        times: times.to_vec(),
+        values,
+        interpolation,
+    }
+}
+
+fn material_from_gltf(material: &gltf::Material, images: &[gltf::image::Data]) -> anyhow::Result<CpuMaterial> {
+    let label = material.name().map(|n| n.to_owned());
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, opacity] = pbr.base_color_factor();
+
+    // The alpha of the base color factor applies to textured materials as well, hence it becomes
+    // the opacity of the material
+    let diffuse_texture = match pbr.base_color_texture() {
+        Some(info) => texture_from_gltf(label.clone(), &images[info.texture().source().index()])?,
+        None => CpuTexture::with_color(label.clone(), [r, g, b, 1.0]),
+    };
+    let normal_texture = material
+        .normal_texture()
+        .map(|info| texture_from_gltf(label.clone(), &images[info.texture().source().index()]))
+        .transpose()?;
+
+    // Approximate the Blinn-Phong parameters from the metallic-roughness model
+    let roughness = pbr.roughness_factor().clamp(0.05, 1.0);
+    let defaults = MaterialDefinition::default();
+    let blend_mode = match material.alpha_mode() {
+        gltf::material::AlphaMode::Opaque => BlendMode::Opaque,
+        gltf::material::AlphaMode::Mask => BlendMode::AlphaTest,
+        gltf::material::AlphaMode::Blend => BlendMode::AlphaBlend,
+    };
+    let sampler = pbr
+        .base_color_texture()
+        .map_or(defaults.sampler, |info| sampler_from_gltf(&info.texture().sampler()));
+    Ok(CpuMaterial {
+        label,
+        diffuse_texture,
+        normal_texture,
+        specular_texture: None,
+        ambient_reflectivity: defaults.ambient_reflectivity,
+        diffuse_reflectivity: defaults.diffuse_reflectivity,
+        specular_reflectivity: 1.0 - roughness,
+        smoothness: (2.0 / roughness.powi(4) - 2.0).clamp(1.0, 256.0),
+        blend_mode,
+        alpha_cutoff: material.alpha_cutoff().unwrap_or(defaults.alpha_cutoff),
+        opacity,
+        sampler,
+    })
+}
+
+/// Translates the sampler of a glTF texture, where unspecified filters fall back to the defaults
+fn sampler_from_gltf(sampler: &gltf::texture::Sampler) -> SamplerSettings {
+    use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+    let address_mode = |mode| match mode {
+        WrappingMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => wgpu::AddressMode::MirrorRepeat,
+        WrappingMode::Repeat => wgpu::AddressMode::Repeat,
+    };
+    let defaults = SamplerSettings::default();
+    let (min_filter, mipmap_filter) = match sampler.min_filter() {
+        Some(MinFilter::Nearest | MinFilter::NearestMipmapNearest) => {
+            (wgpu::FilterMode::Nearest, wgpu::FilterMode::Nearest)
+        }
+        Some(MinFilter::Linear | MinFilter::LinearMipmapNearest) => {
+            (wgpu::FilterMode::Linear, wgpu::FilterMode::Nearest)
+        }
+        Some(MinFilter::NearestMipmapLinear) => (wgpu::FilterMode::Nearest, wgpu::FilterMode::Linear),
+        Some(MinFilter::LinearMipmapLinear) | None => (defaults.min_filter, defaults.mipmap_filter),
+    };
+
+    SamplerSettings {
+        address_mode_u: address_mode(sampler.wrap_s()),
+        address_mode_v: address_mode(sampler.wrap_t()),
+        mag_filter: match sampler.mag_filter() {
+            Some(MagFilter::Nearest) => wgpu::FilterMode::Nearest,
+            Some(MagFilter::Linear) | None => defaults.mag_filter,
+        },
+        min_filter,
+        mipmap_filter,
+        anisotropy: defaults.anisotropy,
+    }
+}
+
+fn texture_from_gltf(label: Option<String>, data: &gltf::image::Data) -> anyhow::Result<CpuTexture> {
+    use gltf::image::Format;
+    use image::{DynamicImage, ImageBuffer};
+
+    let (w, h, pixels) = (data.width, data.height, data.pixels.clone());
+    let image = match data.format {
+        Format::R8 => ImageBuffer::from_raw(w, h, pixels).map(DynamicImage::ImageLuma8),
+        Format::R8G8 => ImageBuffer::from_raw(w, h, pixels).map(DynamicImage::ImageLumaA8),
+        Format::R8G8B8 => ImageBuffer::from_raw(w, h, pixels).map(DynamicImage::ImageRgb8),
+        Format::R8G8B8A8 => ImageBuffer::from_raw(w, h, pixels).map(DynamicImage::ImageRgba8),
+        format => return Err(Error::UnsupportedImageFormat(format).into()),
+    };
+
+    Ok(CpuTexture {
+        label,
+        data: TextureData::Image(image.ok_or(Error::InvalidImageData)?),
+    })
+}
+
+/// Multiplies two column-major matrices
+fn mat_mul(a: &[[f32; 4]; 4], b: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut m = [[0.0; 4]; 4];
+    for (c, column) in m.iter_mut().enumerate() {
+        for (r, value) in column.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][r] * b[c][k]).sum();
+        }
+    }
+    m
+}
+
+fn transform_point(m: &[[f32; 4]; 4], p: [f32; 3]) -> [f32; 3] {
+    let mut q = [0.0; 3];
+    for (r, value) in q.iter_mut().enumerate() {
+        *value = m[0][r] * p[0] + m[1][r] * p[1] + m[2][r] * p[2] + m[3][r];
+    }
+    q
+}
+
+fn transform_direction(m: &[[f32; 4]; 4], d: [f32; 3]) -> [f32; 3] {
+    let mut t = [0.0; 3];
+    for (r, value) in t.iter_mut().enumerate() {
+        *value = m[0][r] * d[0] + m[1][r] * d[1] + m[2][r] * d[2];
+    }
+
+    let norm = (t[0].powi(2) + t[1].powi(2) + t[2].powi(2)).sqrt();
+    if norm > 0.0 {
+        [t[0] / norm, t[1] / norm, t[2] / norm]
+    } else {
+        t
+    }
+}
+
+/// Transforms a normal with the inverse transpose of the upper 3x3 block of the matrix, which is
+/// computed from the cofactors of that block
+fn transform_normal(m: &[[f32; 4]; 4], n: [f32; 3]) -> [f32; 3] {
+    fn cross(a: &[f32; 4], b: &[f32; 4]) -> [f32; 3] {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    }
+
+    let cofactors = [cross(&m[1], &m[2]), cross(&m[2], &m[0]), cross(&m[0], &m[1])];
+    let det = cofactors[0][0] * m[0][0] + cofactors[0][1] * m[0][1] + cofactors[0][2] * m[0][2];
+
+    let mut t = [0.0; 3];
+    for (r, value) in t.iter_mut().enumerate() {
+        *value = (cofactors[0][r] * n[0] + cofactors[1][r] * n[1] + cofactors[2][r] * n[2]) * det.signum();
+    }
+
+    let norm = (t[0].powi(2) + t[1].powi(2) + t[2].powi(2)).sqrt();
+    if norm > 0.0 {
+        [t[0] / norm, t[1] / norm, t[2] / norm]
+    } else {
+        t
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("The glTF document contains no mesh with index {}", .0)]
+    NoSuchMesh(usize),
+    #[error("The glTF image format {:?} is not supported", .0)]
+    UnsupportedImageFormat(gltf::image::Format),
+    #[error("The glTF image data does not match its dimensions")]
+    InvalidImageData,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_tree_lists_parents_before_children() {
+        let gltf = gltf::Gltf::from_slice(
+            br#"{
+                "asset": {"version": "2.0"},
+                "scene": 0,
+                "scenes": [{"nodes": [0]}],
+                "nodes": [
+                    {"name": "root", "children": [1], "translation": [1.0, 2.0, 3.0]},
+                    {"name": "child", "scale": [2.0, 2.0, 2.0]}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let tree = CpuNodeTree::with_document(None, &gltf, &[]).unwrap();
+
+        assert_eq!(tree.nodes.len(), 2);
+        assert_eq!(tree.nodes[0].name.as_deref(), Some("root"));
+        assert_eq!(tree.nodes[0].parent, None);
+        assert_eq!(tree.nodes[0].translation, [1.0, 2.0, 3.0]);
+        assert_eq!(tree.nodes[1].parent, Some(0));
+        assert_eq!(tree.nodes[1].scale, [2.0, 2.0, 2.0]);
+        assert_eq!(tree.nodes[1].mesh, None);
+    }
+
+    #[test]
+    fn node_tree_maps_skins_and_animations_to_tree_indices() {
+        let gltf = gltf::Gltf::from_slice(
+            br#"{
+                "asset": {"version": "2.0"},
+                "scenes": [{"nodes": [2]}],
+                "nodes": [
+                    {"name": "upper"},
+                    {"name": "lower"},
+                    {"name": "body", "children": [0, 1], "skin": 0}
+                ],
+                "skins": [{"joints": [1, 0]}],
+                "buffers": [{"byteLength": 32}],
+                "bufferViews": [{"buffer": 0, "byteLength": 32}],
+                "accessors": [
+                    {"bufferView": 0, "componentType": 5126, "count": 2, "type": "SCALAR", "min": [0.0], "max": [2.0]},
+                    {"bufferView": 0, "byteOffset": 8, "componentType": 5126, "count": 2, "type": "VEC3"}
+                ],
+                "animations": [{
+                    "name": "wave",
+                    "samplers": [{"input": 0, "output": 1, "interpolation": "STEP"}],
+                    "channels": [{"sampler": 0, "target": {"node": 1, "path": "translation"}}]
+                }]
+            }"#,
+        )
+        .unwrap();
+        let data: Vec<f32> = vec![0.0, 2.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let buffers = [gltf::buffer::Data(bytemuck::cast_slice(&data).to_vec())];
+
+        let tree = CpuNodeTree::with_document(None, &gltf, &buffers).unwrap();
+
+        assert_eq!(tree.nodes[0].skin, Some(0));
+        assert_eq!(tree.skins[0].joints, vec![2, 1]);
+        assert_eq!(tree.skins[0].inverse_bind_matrices, vec![IDENTITY; 2]);
+
+        let clip = &tree.animations[0];
+        assert_eq!(clip.name.as_deref(), Some("wave"));
+        assert_eq!(clip.duration, 2.0);
+        assert_eq!(clip.channels[0].node, 2);
+        let translation = clip.channels[0].translation.as_ref().unwrap();
+        assert_eq!(translation.interpolation, Interpolation::Step);
+        assert_eq!(translation.sample(1.0), Some([1.0, 2.0, 3.0]));
+    }
+
+    #[test]
+    fn skins_with_joints_outside_of_the_default_scene_are_rejected() {
+        let gltf = gltf::Gltf::from_slice(
+            br#"{
+                "asset": {"version": "2.0"},
+                "scene": 0,
+                "scenes": [{"nodes": [2]}, {"nodes": [3]}],
+                "nodes": [
+                    {"name": "upper"},
+                    {"name": "lower"},
+                    {"name": "body", "children": [0], "skin": 0},
+                    {"name": "other", "children": [1]}
+                ],
+                "skins": [{"joints": [1, 0]}]
+            }"#,
+        )
+        .unwrap();
+
+        let error = CpuNodeTree::with_document(None, &gltf, &[]).unwrap_err();
+
+        assert!(error.to_string().contains("joint node 1"), "{error}");
+    }
+
+    #[test]
+    fn models_from_document_loads_each_mesh_in_its_local_coordinate_system() {
+        let (document, buffers, images) = gltf::import_slice(
+            br#"{
+                "asset": {"version": "2.0"},
+                "scene": 0,
+                "scenes": [{"nodes": [0, 1]}],
+                "nodes": [{"mesh": 0}, {"mesh": 1, "translation": [5.0, 0.0, 0.0]}],
+                "meshes": [
+                    {"primitives": [{"attributes": {"POSITION": 0}}]},
+                    {"primitives": [{"attributes": {"POSITION": 0}, "material": 0}]}
+                ],
+                "materials": [{"pbrMetallicRoughness": {"baseColorFactor": [1.0, 0.0, 0.0, 0.5]}}],
+                "accessors": [{
+                    "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+                }],
+                "bufferViews": [{"buffer": 0, "byteLength": 36}],
+                "buffers": [{
+                    "byteLength": 36,
+                    "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        let models = models_from_document(None, &document, &buffers, &images).unwrap();
+
+        assert_eq!(models.len(), 2);
+        for model in &models {
+            assert_eq!(model.mesh.indices.len(), 3);
+            assert_eq!(model.mesh.bounds.max[0], 1.0);
+        }
+        assert_eq!(models[0].materials[0].opacity, 1.0);
+        assert_eq!(models[1].materials[0].opacity, 0.5);
+    }
+
+    #[test]
+    fn transform_normal_preserves_direction_under_non_uniform_scale() {
+        let scale = [
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let n = transform_normal(&scale, [1.0, 1.0, 0.0]);
+
+        let expected = [1.0 / 5.0f32.sqrt(), 2.0 / 5.0f32.sqrt(), 0.0];
+        for (a, b) in n.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+}
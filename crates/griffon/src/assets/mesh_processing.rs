@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use crate::base::vertex::Vertex;
+
+/// Axis-aligned bounding box in the local coordinate system of a mesh
+#[derive(Debug, Default, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BoundingBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl BoundingBox {
+    /// Returns the smallest box enclosing all vertices, or an empty box at the origin if there are
+    /// no vertices
+    pub fn from_vertices(vertices: &[Vertex]) -> Self {
+        let Some(first) = vertices.first() else {
+            return BoundingBox::default();
+        };
+
+        vertices.iter().fold(
+            BoundingBox {
+                min: first.position,
+                max: first.position,
+            },
+            |mut bb, v| {
+                for i in 0..3 {
+                    bb.min[i] = bb.min[i].min(v.position[i]);
+                    bb.max[i] = bb.max[i].max(v.position[i]);
+                }
+                bb
+            },
+        )
+    }
+
+    pub fn center(&self) -> [f32; 3] {
+        [
+            (self.min[0] + self.max[0]) / 2.0,
+            (self.min[1] + self.max[1]) / 2.0,
+            (self.min[2] + self.max[2]) / 2.0,
+        ]
+    }
+
+    /// Returns the half-lengths of the box along each axis
+    pub fn half_extents(&self) -> [f32; 3] {
+        [
+            (self.max[0] - self.min[0]) / 2.0,
+            (self.max[1] - self.min[1]) / 2.0,
+            (self.max[2] - self.min[2]) / 2.0,
+        ]
+    }
+}
+
+/// Splits polygons into triangles by fanning out from their first vertex. This is exact for
+/// convex polygons. Polygons with fewer than three vertices are dropped.
+pub fn triangulate<'a, I: IntoIterator<Item = &'a [u32]>>(polygons: I) -> Vec<u32> {
+    let mut indices = Vec::new();
+    for polygon in polygons {
+        for i in 1..polygon.len().saturating_sub(1) {
+            indices.extend_from_slice(&[polygon[0], polygon[i], polygon[i + 1]]);
+        }
+    }
+
+    indices
+}
+
+/// Returns `true` if at least one vertex has a non-zero normal
+pub fn has_normals(vertices: &[Vertex]) -> bool {
+    vertices.iter().any(|v| v.normal != [0.0; 3])
+}
+
+/// Returns `true` if at least one vertex has a non-zero tangent
+pub fn has_tangents(vertices: &[Vertex]) -> bool {
+    vertices.iter().any(|v| v.tangent != [0.0; 4])
+}
+
+/// Normalizes the normal of every vertex. Zero normals are left unchanged.
+pub fn normalize_normals(vertices: &mut [Vertex]) {
+    for v in vertices {
+        v.normal = normalize(v.normal);
+    }
+}
+
+/// Assigns every vertex the area-weighted average of the normals of the triangles sharing it
+pub fn compute_smooth_normals(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut normals = vec![[0.0f32; 3]; vertices.len()];
+    for tri in indices.chunks_exact(3) {
+        let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| vertices[i as usize].position);
+        // The length of the cross product is twice the area of the triangle
+        let n = cross(sub(b, a), sub(c, a));
+        for &i in tri {
+            let acc = &mut normals[i as usize];
+            for k in 0..3 {
+                acc[k] += n[k];
+            }
+        }
+    }
+
+    for (v, n) in vertices.iter_mut().zip(normals) {
+        v.normal = normalize(n);
+    }
+}
+
+/// Assigns every triangle its face normal. Since vertices can no longer be shared between
+/// triangles, each index receives its own vertex. The order of indices is preserved.
+pub fn compute_flat_normals(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    let (mut vertices, indices) = unweld_vertices(vertices, indices);
+    for tri in vertices.chunks_exact_mut(3) {
+        let n = normalize(cross(
+            sub(tri[1].position, tri[0].position),
+            sub(tri[2].position, tri[0].position),
+        ));
+        for v in tri {
+            v.normal = n;
+        }
+    }
+
+    (vertices, indices)
+}
+
+/// Computes MikkTSpace tangents, with the handedness of the bitangent in the fourth component.
+/// Requires normals and texture coordinates. Since tangents are generated per triangle corner,
+/// each index receives its own vertex; use [`weld_vertices`] to share vertices again. Returns
+/// `None` if the geometry is unsuitable for tangent generation.
+pub fn compute_tangents(vertices: &[Vertex], indices: &[u32]) -> Option<(Vec<Vertex>, Vec<u32>)> {
+    struct Corners(Vec<Vertex>);
+
+    impl bevy_mikktspace::Geometry for Corners {
+        fn num_faces(&self) -> usize {
+            self.0.len() / 3
+        }
+
+        fn num_vertices_of_face(&self, _face: usize) -> usize {
+            3
+        }
+
+        fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+            self.0[face * 3 + vert].position
+        }
+
+        fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+            self.0[face * 3 + vert].normal
+        }
+
+        fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+            self.0[face * 3 + vert].tex_coords
+        }
+
+        fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+            self.0[face * 3 + vert].tangent = tangent;
+        }
+    }
+
+    let (vertices, indices) = unweld_vertices(vertices, indices);
+    let mut corners = Corners(vertices);
+    if !bevy_mikktspace::generate_tangents(&mut corners) {
+        return None;
+    }
+
+    Some((corners.0, indices))
+}
+
+/// Merges vertices whose attributes are bitwise identical and remaps the indices accordingly.
+/// The order of indices is preserved.
+pub fn weld_vertices<V: bytemuck::Pod>(vertices: &[V], indices: &[u32]) -> (Vec<V>, Vec<u32>) {
+    let mut welded: Vec<V> = Vec::with_capacity(vertices.len());
+    let mut lookup: HashMap<&[u8], u32> = HashMap::with_capacity(vertices.len());
+    let mut remap = Vec::with_capacity(vertices.len());
+
+    for v in vertices {
+        let index = *lookup.entry(bytemuck::bytes_of(v)).or_insert_with(|| {
+            welded.push(*v);
+            welded.len() as u32 - 1
+        });
+        remap.push(index);
+    }
+
+    (welded, indices.iter().map(|&i| remap[i as usize]).collect())
+}
+
+/// Gives each index its own copy of the vertex it refers to
+pub fn unweld_vertices<V: Copy>(vertices: &[V], indices: &[u32]) -> (Vec<V>, Vec<u32>) {
+    (
+        indices.iter().map(|&i| vertices[i as usize]).collect(),
+        (0..indices.len() as u32).collect(),
+    )
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let norm = (v[0].powi(2) + v[1].powi(2) + v[2].powi(2)).sqrt();
+    if norm > 0.0 {
+        [v[0] / norm, v[1] / norm, v[2] / norm]
+    } else {
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(position: [f32; 3], tex_coords: [f32; 2]) -> Vertex {
+        Vertex {
+            position,
+            tex_coords,
+            ..Default::default()
+        }
+    }
+
+    fn quad() -> (Vec<Vertex>, Vec<u32>) {
+        let vertices = vec![
+            vertex([0.0, 0.0, 0.0], [0.0, 1.0]),
+            vertex([1.0, 0.0, 0.0], [1.0, 1.0]),
+            vertex([1.0, 1.0, 0.0], [1.0, 0.0]),
+            vertex([0.0, 1.0, 0.0], [0.0, 0.0]),
+        ];
+        (vertices, triangulate([&[0, 1, 2, 3][..]]))
+    }
+
+    #[test]
+    fn triangulate_fans_out_polygons_and_drops_degenerate_ones() {
+        let polygons: [&[u32]; 3] = [&[0, 1, 2, 3, 4], &[5, 6], &[7, 8, 9]];
+        assert_eq!(triangulate(polygons), vec![0, 1, 2, 0, 2, 3, 0, 3, 4, 7, 8, 9]);
+    }
+
+    #[test]
+    fn smooth_and_flat_normals_of_a_planar_quad_point_along_z() {
+        let (mut vertices, indices) = quad();
+        compute_smooth_normals(&mut vertices, &indices);
+        assert!(vertices.iter().all(|v| v.normal == [0.0, 0.0, 1.0]));
+
+        let (flat, flat_indices) = compute_flat_normals(&vertices, &indices);
+        assert_eq!(flat.len(), 6);
+        assert_eq!(flat_indices, vec![0, 1, 2, 3, 4, 5]);
+        assert!(flat.iter().all(|v| v.normal == [0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn tangents_follow_the_u_direction_and_welding_restores_shared_vertices() {
+        let (mut vertices, indices) = quad();
+        compute_smooth_normals(&mut vertices, &indices);
+
+        let (corners, corner_indices) = compute_tangents(&vertices, &indices).unwrap();
+        assert!(corners.iter().all(|v| (v.tangent[0] - 1.0).abs() < 1e-6));
+
+        let (welded, welded_indices) = weld_vertices(&corners, &corner_indices);
+        assert_eq!(welded.len(), 4);
+        assert_eq!(welded_indices, vec![0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn bounding_box_encloses_all_vertices() {
+        let (vertices, _) = quad();
+        let bb = BoundingBox::from_vertices(&vertices);
+        assert_eq!(bb.min, [0.0, 0.0, 0.0]);
+        assert_eq!(bb.max, [1.0, 1.0, 0.0]);
+        assert_eq!(bb.center(), [0.5, 0.5, 0.0]);
+        assert_eq!(bb.half_extents(), [0.5, 0.5, 0.0]);
+    }
+}
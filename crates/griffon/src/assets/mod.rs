@@ -1,4 +1,10 @@
+pub mod animation_clip;
 pub mod cpu_material;
 pub mod cpu_mesh;
 pub mod cpu_model;
 pub mod cpu_texture;
+pub mod gltf_import;
+pub mod mesh_processing;
+pub mod obj_import;
+pub mod primitives;
+pub mod shader_source;
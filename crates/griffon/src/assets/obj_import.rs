@@ -0,0 +1,265 @@
+use std::path::Path;
+
+use anyhow::Context;
+use assam::{AssetDatabase, AssetId};
+use ecs::Resources;
+
+use super::cpu_material::{CpuMaterial, MaterialDefinition};
+use super::cpu_mesh::{CpuMesh, Submesh};
+use super::cpu_model::CpuModel;
+use super::cpu_texture::CpuTexture;
+use super::mesh_processing::BoundingBox;
+use crate::base::blend_mode::BlendMode;
+use crate::base::vertex::Vertex;
+
+pub const OBJ_FILE_EXTENSION: &str = "obj";
+
+fn load_options() -> tobj::LoadOptions {
+    tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ignore_points: true,
+        ignore_lines: true,
+    }
+}
+
+/// Loads a Wavefront OBJ model. Material libraries are resolved relative to the OBJ file, and
+/// the textures they reference are loaded from the [`AssetDatabase`] relative to the OBJ asset.
+#[tracing::instrument(skip(res))]
+pub(crate) async fn load_obj(res: &Resources, path: &Path) -> anyhow::Result<CpuModel> {
+    let label = path.file_stem().and_then(|n| n.to_str()).map(|n| n.to_owned());
+    let (group, name) = res.read::<AssetDatabase>().find_asset_name(path)?;
+    let asset = AssetId::new(group, name);
+    let (models, obj_materials) = tobj::load_obj(path, &load_options())
+        .with_context(|| format!("Loading a Wavefront OBJ file from '{}'", path.display()))?;
+    let obj_materials =
+        obj_materials.with_context(|| format!("Loading the material libraries of '{}'", path.display()))?;
+
+    let mesh = mesh_from_obj_models(label, &models);
+
+    let mut materials = Vec::with_capacity(obj_materials.len().max(1));
+    for obj_material in &obj_materials {
+        materials.push(material_from_mtl(res, &asset, obj_material).await?);
+    }
+
+    if materials.is_empty() {
+        materials.push(CpuMaterial::with_color(None, [1.0; 4]));
+    }
+
+    Ok(CpuModel { mesh, materials })
+}
+
+/// Returns the material libraries referenced by an OBJ file, and the textures referenced by
+/// those libraries, as assets relative to the OBJ file
+pub(crate) fn obj_dependencies(asset: &AssetId, path: &Path) -> anyhow::Result<Vec<AssetId>> {
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Reading the Wavefront OBJ file '{}'", path.display()))?;
+
+    let mut dependencies = Vec::new();
+    for library in source
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("mtllib "))
+        .flat_map(|l| l.split_whitespace())
+    {
+        dependencies.push(asset.sibling(library));
+
+        // Missing libraries are reported by the validation itself
+        let Ok((obj_materials, _)) = tobj::load_mtl(base_dir.join(library)) else {
+            continue;
+        };
+
+        for m in &obj_materials {
+            dependencies.extend(
+                [&m.diffuse_texture, &m.normal_texture, &m.specular_texture]
+                    .into_iter()
+                    .filter(|t| !t.is_empty())
+                    .map(|t| asset.sibling(t)),
+            );
+        }
+    }
+
+    Ok(dependencies)
+}
+
+/// Merges all objects of an OBJ file into a single mesh with one submesh per material
+fn mesh_from_obj_models(label: Option<String>, models: &[tobj::Model]) -> CpuMesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut face_materials = Vec::new();
+
+    for model in models {
+        let m = &model.mesh;
+        let base_index = vertices.len() as u32;
+
+        vertices.extend((0..m.positions.len() / 3).map(|i| {
+            let mut vertex = Vertex {
+                position: [m.positions[i * 3], m.positions[i * 3 + 1], m.positions[i * 3 + 2]],
+                ..Default::default()
+            };
+
+            if !m.normals.is_empty() {
+                vertex.normal = [m.normals[i * 3], m.normals[i * 3 + 1], m.normals[i * 3 + 2]];
+            }
+
+            if !m.texcoords.is_empty() {
+                // OBJ texture coordinates have their origin at the bottom left
+                vertex.tex_coords = [m.texcoords[i * 2], 1.0 - m.texcoords[i * 2 + 1]];
+            }
+
+            vertex
+        }));
+
+        indices.extend(m.indices.iter().map(|&i| base_index + i));
+        face_materials.extend(std::iter::repeat_n(m.material_id.unwrap_or(0), m.indices.len() / 3));
+    }
+
+    let (indices, submeshes) = Submesh::group_by_material(&indices, &face_materials);
+
+    let mut mesh = CpuMesh {
+        label,
+        texture_names: Vec::new(),
+        vertices,
+        indices,
+        submeshes,
+        bounds: BoundingBox::default(),
+        skin: Vec::new(),
+    };
+    mesh.finalize();
+
+    mesh
+}
+
+async fn material_from_mtl(res: &Resources, model: &AssetId, m: &tobj::Material) -> anyhow::Result<CpuMaterial> {
+    async fn load_texture(res: &Resources, model: &AssetId, name: &str) -> anyhow::Result<Option<CpuTexture>> {
+        if name.is_empty() {
+            return Ok(None);
+        }
+
+        let texture = model.sibling(name);
+        let texture = res
+            .read::<AssetDatabase>()
+            .load_asset::<CpuTexture, _>(res, &texture.group, &texture.name)
+            .await
+            .with_context(|| format!("Loading a CpuTexture from {texture}"))?;
+        Ok(Some(texture))
+    }
+
+    let label = Some(m.name.clone());
+    let diffuse_texture = match load_texture(res, model, &m.diffuse_texture).await? {
+        Some(texture) => texture,
+        None => CpuTexture::with_color(label.clone(), [m.diffuse[0], m.diffuse[1], m.diffuse[2], 1.0]),
+    };
+    let normal_texture = load_texture(res, model, &m.normal_texture).await?;
+    let specular_texture = load_texture(res, model, &m.specular_texture).await?;
+
+    let defaults = MaterialDefinition::default();
+    let opacity = opacity_from_mtl(m);
+    Ok(CpuMaterial {
+        label,
+        diffuse_texture,
+        normal_texture,
+        specular_texture,
+        ambient_reflectivity: m.ambient.into_iter().fold(0.0, f32::max),
+        diffuse_reflectivity: defaults.diffuse_reflectivity,
+        specular_reflectivity: m.specular.into_iter().fold(0.0, f32::max),
+        smoothness: if m.shininess > 0.0 {
+            m.shininess
+        } else {
+            defaults.smoothness
+        },
+        // An opacity below one makes the material translucent
+        blend_mode: if opacity < 1.0 {
+            BlendMode::AlphaBlend
+        } else {
+            defaults.blend_mode
+        },
+        alpha_cutoff: defaults.alpha_cutoff,
+        opacity,
+        sampler: defaults.sampler,
+    })
+}
+
+/// Returns the opacity of an MTL material, given by its dissolve `d`, or by its transparency `Tr`
+/// for exporters that only write the latter
+fn opacity_from_mtl(m: &tobj::Material) -> f32 {
+    let transparency = m.unknown_param.get("Tr").and_then(|tr| tr.trim().parse::<f32>().ok());
+    match transparency {
+        Some(tr) if m.dissolve >= 1.0 => (1.0 - tr).clamp(0.0, 1.0),
+        _ => m.dissolve.clamp(0.0, 1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mesh_from_obj_models_groups_objects_by_material() {
+        let source = "\
+o first
+v 0 0 0
+v 1 0 0
+v 1 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+f 1/1 2/2 3/3
+o second
+v 0 0 1
+v 1 0 1
+v 1 1 1
+f 4 5 6
+";
+        let (mut models, _) = tobj::load_obj_buf(&mut source.as_bytes(), &load_options(), |_| {
+            Err(tobj::LoadError::OpenFileFailed)
+        })
+        .unwrap();
+        models[0].mesh.material_id = Some(1);
+
+        let mesh = mesh_from_obj_models(None, &models);
+
+        let positions: Vec<[f32; 3]> = mesh
+            .indices
+            .iter()
+            .map(|&i| mesh.vertices[i as usize].position)
+            .collect();
+        assert_eq!(mesh.vertices.len(), 6);
+        assert_eq!(positions[0], [0.0, 0.0, 1.0]);
+        assert_eq!(positions[3], [0.0, 0.0, 0.0]);
+        assert_eq!(mesh.vertices[mesh.indices[5] as usize].tex_coords, [1.0, 0.0]);
+        assert_eq!(
+            mesh.submeshes,
+            vec![
+                Submesh {
+                    indices: 0..3,
+                    material: 0
+                },
+                Submesh {
+                    indices: 3..6,
+                    material: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn opacity_from_mtl_prefers_dissolve_over_transparency() {
+        let source = "\
+newmtl dissolved
+d 0.25
+newmtl transparent
+Tr 0.75
+newmtl both
+d 0.5
+Tr 0.1
+newmtl opaque
+Kd 1 1 1
+";
+        let (materials, _) = tobj::load_mtl_buf(&mut source.as_bytes()).unwrap();
+
+        let opacities: Vec<f32> = materials.iter().map(opacity_from_mtl).collect();
+        assert_eq!(opacities, vec![0.25, 0.25, 0.5, 1.0]);
+    }
+}
@@ -0,0 +1,512 @@
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use super::cpu_mesh::{CpuMesh, Submesh};
+use super::mesh_processing::BoundingBox;
+use crate::base::vertex::Vertex;
+
+/// Shapes whose meshes are generated from a few parameters instead of being loaded from a file.
+/// Every shape is centered on the origin with y pointing up, and has outward facing normals and
+/// texture coordinates. Segment counts below the minimum of a shape are raised to it.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Primitive {
+    /// Axis-aligned cube with a full texture on every face
+    Cube { size: f32 },
+    /// Sphere made of `segments` meridians and `rings` parallels, with the texture wrapped around
+    /// it in an equirectangular projection
+    UvSphere { radius: f32, segments: u32, rings: u32 },
+    /// Sphere made of an icosahedron whose triangles are split in four `subdivisions` times
+    IcoSphere { radius: f32, subdivisions: u32 },
+    /// Square in the xz-plane facing up, split into `subdivisions` cells along each side
+    Plane { size: f32, subdivisions: u32 },
+    /// Cylinder along the y-axis with flat caps
+    Cylinder { radius: f32, height: f32, segments: u32 },
+    /// Cone along the y-axis with its apex at the top and a flat base
+    Cone { radius: f32, height: f32, segments: u32 },
+    /// Ring in the xz-plane, where `major_radius` is the distance from the center to the middle
+    /// of the tube and `minor_radius` is the radius of the tube
+    Torus {
+        major_radius: f32,
+        minor_radius: f32,
+        major_segments: u32,
+        minor_segments: u32,
+    },
+    /// Cylinder along the y-axis with hemispherical caps. `height` includes the caps and `rings`
+    /// is the number of parallels per cap.
+    Capsule {
+        radius: f32,
+        height: f32,
+        segments: u32,
+        rings: u32,
+    },
+}
+
+impl Primitive {
+    /// Generates the mesh of the shape with a single submesh that uses the first material
+    pub fn to_cpu_mesh(&self) -> CpuMesh {
+        let mut shape = Shape::default();
+        match *self {
+            Primitive::Cube { size } => {
+                let h = size / 2.0;
+                // The right and down directions of the texture on each face as seen from outside
+                let faces = [
+                    ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, -1.0, 0.0]),
+                    ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, -1.0, 0.0]),
+                    ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, -1.0, 0.0]),
+                    ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, -1.0, 0.0]),
+                    ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+                    ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+                ];
+                for (normal, right, down) in faces {
+                    shape.surface(1, &steps(1), |u, v| {
+                        let position =
+                            [0, 1, 2].map(|i| (normal[i] + right[i] * (2.0 * u - 1.0) + down[i] * (2.0 * v - 1.0)) * h);
+                        (position, normal)
+                    });
+                }
+            }
+            Primitive::UvSphere {
+                radius,
+                segments,
+                rings,
+            } => {
+                shape.surface(segments.max(3), &steps(rings.max(2)), |u, v| {
+                    let normal = spherical(TAU * u, PI * v);
+                    (normal.map(|c| c * radius), normal)
+                });
+            }
+            Primitive::IcoSphere { radius, subdivisions } => shape.ico_sphere(radius, subdivisions),
+            Primitive::Plane { size, subdivisions } => {
+                shape.surface(subdivisions.max(1), &steps(subdivisions.max(1)), |u, v| {
+                    ([(u - 0.5) * size, 0.0, (v - 0.5) * size], [0.0, 1.0, 0.0])
+                });
+            }
+            Primitive::Cylinder {
+                radius,
+                height,
+                segments,
+            } => {
+                let (segments, h) = (segments.max(3), height / 2.0);
+                shape.surface(segments, &steps(1), |u, v| {
+                    let normal = spherical(TAU * u, FRAC_PI_2);
+                    ([normal[0] * radius, h - v * height, normal[2] * radius], normal)
+                });
+                shape.disc(h, radius, segments, true);
+                shape.disc(-h, radius, segments, false);
+            }
+            Primitive::Cone {
+                radius,
+                height,
+                segments,
+            } => {
+                let (segments, h) = (segments.max(3), height / 2.0);
+                let slant = (radius * radius + height * height).sqrt().max(f32::EPSILON);
+                shape.surface(segments, &steps(1), |u, v| {
+                    let radial = spherical(TAU * u, FRAC_PI_2);
+                    let normal = [radial[0] * height / slant, radius / slant, radial[2] * height / slant];
+                    ([radial[0] * radius * v, h - v * height, radial[2] * radius * v], normal)
+                });
+                shape.disc(-h, radius, segments, false);
+            }
+            Primitive::Torus {
+                major_radius,
+                minor_radius,
+                major_segments,
+                minor_segments,
+            } => {
+                shape.surface(major_segments.max(3), &steps(minor_segments.max(3)), |u, v| {
+                    let radial = spherical(TAU * u, FRAC_PI_2);
+                    // Starts at the top of the tube and continues down its outer side
+                    let (sin, cos) = (TAU * v).sin_cos();
+                    let normal = [radial[0] * sin, cos, radial[2] * sin];
+                    let position = [0, 1, 2].map(|i| radial[i] * major_radius + normal[i] * minor_radius);
+                    (position, normal)
+                });
+            }
+            Primitive::Capsule {
+                radius,
+                height,
+                segments,
+                rings,
+            } => {
+                let rings = rings.max(1);
+                let (cap, length) = (radius * FRAC_PI_2, (height - 2.0 * radius).max(0.0));
+                let profile = cap * 2.0 + length;
+                // Texture rows are spaced by the distance along the profile of the capsule, and the
+                // seams between the caps and the cylinder fall onto rows
+                let rows: Vec<f32> = steps(rings)
+                    .iter()
+                    .map(|t| t * cap)
+                    .chain(steps(rings).iter().map(|t| cap + length + t * cap))
+                    .map(|d| d / profile.max(f32::EPSILON))
+                    .collect();
+                shape.surface(segments.max(3), &rows, |u, v| {
+                    let d = v * profile;
+                    let (polar, y) = if d <= cap {
+                        (d / radius.max(f32::EPSILON), length / 2.0)
+                    } else if d < cap + length {
+                        (FRAC_PI_2, length / 2.0 - (d - cap))
+                    } else {
+                        (FRAC_PI_2 + (d - cap - length) / radius.max(f32::EPSILON), -length / 2.0)
+                    };
+                    let normal = spherical(TAU * u, polar.min(PI));
+                    ([normal[0] * radius, normal[1] * radius + y, normal[2] * radius], normal)
+                });
+            }
+        }
+
+        let index_count = shape.indices.len() as u32;
+        let mut mesh = CpuMesh {
+            label: Some(format!("{:?}", self)),
+            texture_names: Vec::new(),
+            vertices: shape.vertices,
+            indices: shape.indices,
+            submeshes: vec![Submesh {
+                indices: 0..index_count,
+                material: 0,
+            }],
+            bounds: BoundingBox::default(),
+            skin: Vec::new(),
+        };
+        mesh.finalize();
+
+        mesh
+    }
+}
+
+/// Triangles under construction. Outward facing triangles are wound counter-clockwise.
+#[derive(Debug, Default)]
+struct Shape {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl Shape {
+    fn vertex(&mut self, position: [f32; 3], normal: [f32; 3], tex_coords: [f32; 2]) -> u32 {
+        self.vertices.push(Vertex {
+            position,
+            normal,
+            tex_coords,
+            ..Default::default()
+        });
+        self.vertices.len() as u32 - 1
+    }
+
+    /// Adds a triangle unless it is degenerate, as happens at the poles of a sphere
+    fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        let [pa, pb, pc] = [a, b, c].map(|i| self.vertices[i as usize].position);
+        let n = cross(sub(pb, pa), sub(pc, pa));
+        if n.iter().map(|c| c * c).sum::<f32>() > f32::EPSILON * f32::EPSILON {
+            self.indices.extend([a, b, c]);
+        }
+    }
+
+    /// Adds a grid of `columns` by `rows.len() - 1` quads whose texture coordinates `(u, v)` run
+    /// from 0 to 1, where `rows` holds the v coordinate of every row of vertices. `point` returns
+    /// the position and normal at `(u, v)`, and must increase u towards the right and v downwards
+    /// when looking at the surface from outside.
+    fn surface<F>(&mut self, columns: u32, rows: &[f32], point: F)
+    where
+        F: Fn(f32, f32) -> ([f32; 3], [f32; 3]),
+    {
+        let base = self.vertices.len() as u32;
+        for &v in rows {
+            for u in steps(columns) {
+                let (position, normal) = point(u, v);
+                self.vertex(position, normal, [u, v]);
+            }
+        }
+
+        let stride = columns + 1;
+        for row in 0..rows.len().saturating_sub(1) as u32 {
+            for column in 0..columns {
+                let a = base + row * stride + column;
+                let (b, c, d) = (a + 1, a + stride + 1, a + stride);
+                self.triangle(a, d, c);
+                self.triangle(a, c, b);
+            }
+        }
+    }
+
+    /// Adds a flat disc in the xz-plane at height `y`, facing up if `up` is set and down otherwise
+    fn disc(&mut self, y: f32, radius: f32, segments: u32, up: bool) {
+        let (normal, flip) = if up {
+            ([0.0, 1.0, 0.0], 1.0)
+        } else {
+            ([0.0, -1.0, 0.0], -1.0)
+        };
+        let center = self.vertex([0.0, y, 0.0], normal, [0.5, 0.5]);
+        let rim: Vec<u32> = steps(segments)
+            .into_iter()
+            .map(|u| {
+                let [x, _, z] = spherical(TAU * u, FRAC_PI_2);
+                self.vertex(
+                    [x * radius, y, z * radius],
+                    normal,
+                    [0.5 + x / 2.0, 0.5 + flip * z / 2.0],
+                )
+            })
+            .collect();
+
+        for pair in rim.windows(2) {
+            if up {
+                self.triangle(center, pair[0], pair[1]);
+            } else {
+                self.triangle(center, pair[1], pair[0]);
+            }
+        }
+    }
+
+    fn ico_sphere(&mut self, radius: f32, subdivisions: u32) {
+        let g = (1.0 + 5.0f32.sqrt()) / 2.0;
+        let mut points: Vec<[f32; 3]> = [
+            [-1.0, g, 0.0],
+            [1.0, g, 0.0],
+            [-1.0, -g, 0.0],
+            [1.0, -g, 0.0],
+            [0.0, -1.0, g],
+            [0.0, 1.0, g],
+            [0.0, -1.0, -g],
+            [0.0, 1.0, -g],
+            [g, 0.0, -1.0],
+            [g, 0.0, 1.0],
+            [-g, 0.0, -1.0],
+            [-g, 0.0, 1.0],
+        ]
+        .into_iter()
+        .map(normalize)
+        .collect();
+        let mut faces: Vec<[u32; 3]> = vec![
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+            let mut midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let (pa, pb) = (points[a as usize], points[b as usize]);
+                    points.push(normalize([0, 1, 2].map(|i| pa[i] + pb[i])));
+                    points.len() as u32 - 1
+                })
+            };
+
+            faces = faces
+                .into_iter()
+                .flat_map(|[a, b, c]| {
+                    let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        // Every triangle gets its own vertices so that triangles crossing the seam of the
+        // equirectangular texture can wrap around it. Finalizing the mesh welds the others again.
+        for face in faces {
+            let normals = face.map(|i| points[i as usize]);
+            let mut tex_coords = normals.map(|[x, y, z]| [0.5 + x.atan2(z) / TAU, y.clamp(-1.0, 1.0).acos() / PI]);
+            let us = tex_coords.map(|t| t[0]);
+            if us.into_iter().fold(f32::MIN, f32::max) - us.into_iter().fold(f32::MAX, f32::min) > 0.5 {
+                for t in &mut tex_coords {
+                    if t[0] < 0.5 {
+                        t[0] += 1.0;
+                    }
+                }
+            }
+            // The longitude of a pole is taken from the rest of its triangle
+            for i in 0..3 {
+                let [x, _, z] = normals[i];
+                if x.abs() < 1e-6 && z.abs() < 1e-6 {
+                    tex_coords[i][0] = (tex_coords[(i + 1) % 3][0] + tex_coords[(i + 2) % 3][0]) / 2.0;
+                }
+            }
+
+            let [a, b, c] = [0, 1, 2].map(|i| self.vertex(normals[i].map(|c| c * radius), normals[i], tex_coords[i]));
+            self.triangle(a, b, c);
+        }
+    }
+}
+
+/// Returns `n + 1` evenly spaced values from 0 to 1
+fn steps(n: u32) -> Vec<f32> {
+    (0..=n).map(|i| i as f32 / n as f32).collect()
+}
+
+/// Returns the unit vector at longitude `azimuth`, measured from the z-axis towards the x-axis,
+/// and at `polar` angle from the y-axis
+fn spherical(azimuth: f32, polar: f32) -> [f32; 3] {
+    let (sin_polar, cos_polar) = polar.sin_cos();
+    [sin_polar * azimuth.sin(), cos_polar, sin_polar * azimuth.cos()]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = v.iter().map(|c| c * c).sum::<f32>().sqrt();
+    v.map(|c| c / len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_primitives() -> Vec<Primitive> {
+        vec![
+            Primitive::Cube { size: 2.0 },
+            Primitive::UvSphere {
+                radius: 1.5,
+                segments: 16,
+                rings: 8,
+            },
+            Primitive::IcoSphere {
+                radius: 1.5,
+                subdivisions: 2,
+            },
+            Primitive::Plane {
+                size: 3.0,
+                subdivisions: 4,
+            },
+            Primitive::Cylinder {
+                radius: 0.5,
+                height: 2.0,
+                segments: 12,
+            },
+            Primitive::Cone {
+                radius: 0.5,
+                height: 2.0,
+                segments: 12,
+            },
+            Primitive::Torus {
+                major_radius: 1.0,
+                minor_radius: 0.25,
+                major_segments: 16,
+                minor_segments: 8,
+            },
+            Primitive::Capsule {
+                radius: 0.5,
+                height: 2.0,
+                segments: 12,
+                rings: 4,
+            },
+        ]
+    }
+
+    #[test]
+    fn primitives_have_unit_normals_and_outward_facing_triangles() {
+        for primitive in all_primitives() {
+            let mesh = primitive.to_cpu_mesh();
+            assert!(!mesh.indices.is_empty(), "{primitive:?} has no triangles");
+            assert_eq!(
+                mesh.submeshes,
+                vec![Submesh {
+                    indices: 0..mesh.indices.len() as u32,
+                    material: 0,
+                }]
+            );
+
+            for v in &mesh.vertices {
+                let length = v.normal.iter().map(|c| c * c).sum::<f32>().sqrt();
+                assert!(
+                    (length - 1.0).abs() < 1e-4,
+                    "{primitive:?} has the normal {:?}",
+                    v.normal
+                );
+                assert!(v.tex_coords.iter().all(|t| (-1e-6..=2.0).contains(t)));
+                assert_ne!(v.tangent, [0.0; 4], "{primitive:?} lacks tangents");
+            }
+
+            for triangle in mesh.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+                let face_normal = cross(sub(b.position, a.position), sub(c.position, a.position));
+                let facing: f32 = [a, b, c]
+                    .iter()
+                    .map(|v| (0..3).map(|i| face_normal[i] * v.normal[i]).sum::<f32>())
+                    .sum();
+                assert!(facing > 0.0, "{primitive:?} has an inward facing triangle {triangle:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn primitives_span_their_parameters() {
+        let extents = |p: Primitive| p.to_cpu_mesh().bounds.half_extents();
+        let assert_approx_eq = |a: [f32; 3], b: [f32; 3]| {
+            assert!(a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-4), "{a:?} != {b:?}");
+        };
+
+        assert_approx_eq(extents(Primitive::Cube { size: 2.0 }), [1.0; 3]);
+        assert_approx_eq(
+            extents(Primitive::IcoSphere {
+                radius: 1.5,
+                subdivisions: 0,
+            }),
+            [1.5 * 0.85065085; 3],
+        );
+        assert_approx_eq(
+            extents(Primitive::Plane {
+                size: 3.0,
+                subdivisions: 2,
+            }),
+            [1.5, 0.0, 1.5],
+        );
+        assert_approx_eq(
+            extents(Primitive::Capsule {
+                radius: 0.5,
+                height: 3.0,
+                segments: 4,
+                rings: 2,
+            }),
+            [0.5, 1.5, 0.5],
+        );
+        assert_approx_eq(
+            extents(Primitive::Torus {
+                major_radius: 1.0,
+                minor_radius: 0.25,
+                major_segments: 4,
+                minor_segments: 4,
+            }),
+            [1.25, 0.25, 1.25],
+        );
+    }
+
+    #[test]
+    fn segment_counts_are_raised_to_their_minimum() {
+        let mesh = Primitive::Cylinder {
+            radius: 1.0,
+            height: 1.0,
+            segments: 0,
+        }
+        .to_cpu_mesh();
+
+        // Three side quads and two caps of three triangles each
+        assert_eq!(mesh.indices.len(), (3 * 2 + 2 * 3) * 3);
+    }
+}
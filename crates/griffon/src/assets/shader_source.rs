@@ -0,0 +1,449 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+
+use anyhow::{Context, bail};
+use assam::{AssetDependencies, AssetId, LoadAsset};
+use ecs::Resources;
+use wgpu::naga;
+
+pub const SHADER_ASSET_GROUP: &str = "shaders";
+
+/// A WGSL shader together with all files it includes. Supports the following preprocessor
+/// directives, each on a line of its own:
+///
+/// - `#include "common/lighting.wgsl"` inserts a file of the `shaders` group, at most once
+/// - `#define NAME` enables a feature flag for the remainder of the shader
+/// - `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif` select lines by feature flags
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderSource {
+    name: String,
+    files: BTreeMap<String, String>,
+}
+
+impl ShaderSource {
+    /// Creates a shader from files that are already in memory, where `name` refers to the file
+    /// that is compiled
+    pub fn with_files<N, I>(name: N, files: I) -> Self
+    where
+        N: Into<String>,
+        I: IntoIterator<Item = (String, String)>,
+    {
+        ShaderSource {
+            name: name.into(),
+            files: files.into_iter().collect(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Identifies the contents of all files, used to cache compiled variants
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.name.hash(&mut hasher);
+        self.files.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Resolves includes and conditionals with the feature flags in `defines`
+    pub fn preprocess(&self, defines: &[&str]) -> Result<PreprocessedShader, ShaderError> {
+        let mut preprocessor = Preprocessor {
+            source: self,
+            defines: defines.iter().map(|d| d.to_string()).collect(),
+            included: HashSet::new(),
+            output: PreprocessedShader::default(),
+        };
+        preprocessor.process_file(&self.name)?;
+        Ok(preprocessor.output)
+    }
+
+    /// Preprocesses the shader and validates the result with naga. Returns the WGSL source of
+    /// the variant.
+    pub fn validate(&self, defines: &[&str]) -> Result<String, ShaderError> {
+        let shader = self.preprocess(defines)?;
+
+        let module = naga::front::wgsl::parse_str(&shader.source).map_err(|e| ShaderError::Parse {
+            name: self.name.clone(),
+            origin: shader.origin(e.location(&shader.source).map(|l| l.line_number)),
+            message: e.emit_to_string_with_path(&shader.source, &self.name),
+        })?;
+
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+            .validate(&module)
+            .map_err(|e| ShaderError::Validation {
+                name: self.name.clone(),
+                origin: shader.origin(e.location(&shader.source).map(|l| l.line_number)),
+                message: e.emit_to_string_with_path(&shader.source, &self.name),
+            })?;
+
+        Ok(shader.source)
+    }
+}
+
+impl LoadAsset for ShaderSource {
+    type Output = Self;
+
+    /// Loads the shader and every file it includes, in any branch of its conditionals, from the
+    /// same group. The variant without feature flags is validated.
+    #[tracing::instrument(skip(_res))]
+    async fn with_path(_res: &Resources, path: &Path) -> anyhow::Result<Self::Output> {
+        let group_dir = path
+            .parent()
+            .with_context(|| format!("The shader path '{}' has no parent", path.display()))?;
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .with_context(|| format!("The shader path '{}' has no valid file name", path.display()))?;
+
+        let mut files = BTreeMap::new();
+        let mut queue = VecDeque::from([name.to_string()]);
+        while let Some(file) = queue.pop_front() {
+            if files.contains_key(&file) {
+                continue;
+            }
+
+            let file_path = file.split('/').fold(group_dir.to_path_buf(), |p, c| p.join(c));
+            let text = std::fs::read_to_string(&file_path)
+                .with_context(|| format!("Loading a shader source from '{}'", file_path.display()))?;
+
+            queue.extend(includes(&file, &text)?.into_iter().map(str::to_string));
+            files.insert(file, text);
+        }
+
+        let source = ShaderSource::with_files(name, files);
+        source.validate(&[])?;
+        Ok(source)
+    }
+}
+
+impl AssetDependencies for ShaderSource {
+    /// Returns the files the shader includes directly, in any branch of its conditionals
+    async fn dependencies(asset: &AssetId, path: &Path) -> anyhow::Result<Vec<AssetId>> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Loading a shader source from '{}'", path.display()))?;
+
+        Ok(includes(&asset.name, &text)?
+            .into_iter()
+            .map(|include| AssetId::new(asset.group.as_str(), include))
+            .collect())
+    }
+}
+
+/// Returns the files included by the shader `file`, in any branch of its conditionals
+fn includes<'a>(file: &str, text: &'a str) -> anyhow::Result<Vec<&'a str>> {
+    let mut includes = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if let Some(Ok(Directive::Include(include))) = Directive::parse(line) {
+            if !is_valid_include(include) {
+                bail!(
+                    "{}:{}: The include '{}' is not a valid shader name",
+                    file,
+                    i + 1,
+                    include
+                );
+            }
+            includes.push(include);
+        }
+    }
+
+    Ok(includes)
+}
+
+/// The WGSL source of a shader variant, with the origin of each line
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PreprocessedShader {
+    pub source: String,
+    /// The file and line number of each line of the source
+    pub lines: Vec<(String, usize)>,
+}
+
+impl PreprocessedShader {
+    fn origin(&self, line_number: Option<u32>) -> String {
+        line_number
+            .and_then(|n| self.lines.get((n as usize).checked_sub(1)?))
+            .map_or_else(
+                || String::from("unknown location"),
+                |(file, line)| format!("{file}:{line}"),
+            )
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ShaderError {
+    #[error("{file}:{line}: Invalid preprocessor directive '{directive}'")]
+    InvalidDirective {
+        file: String,
+        line: usize,
+        directive: String,
+    },
+    #[error("{file}:{line}: The included shader '{include}' was not loaded")]
+    MissingInclude { file: String, line: usize, include: String },
+    #[error("{file}:{line}: '{directive}' without a preceding '#ifdef' or '#ifndef'")]
+    UnbalancedConditional {
+        file: String,
+        line: usize,
+        directive: String,
+    },
+    #[error("{file}:{line}: The conditional is not closed by '#endif'")]
+    UnterminatedConditional { file: String, line: usize },
+    #[error("The shader '{name}' could not be parsed at {origin}:\n{message}")]
+    Parse {
+        name: String,
+        origin: String,
+        message: String,
+    },
+    #[error("The shader '{name}' is invalid at {origin}:\n{message}")]
+    Validation {
+        name: String,
+        origin: String,
+        message: String,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Directive<'a> {
+    Include(&'a str),
+    Define(&'a str),
+    Ifdef(&'a str),
+    Ifndef(&'a str),
+    Else,
+    Endif,
+}
+
+impl<'a> Directive<'a> {
+    /// Returns `None` for lines that are not directives
+    fn parse(line: &'a str) -> Option<Result<Self, ()>> {
+        let line = line.trim();
+        let directive = line.strip_prefix('#')?;
+        let (keyword, argument) = directive
+            .split_once(char::is_whitespace)
+            .map_or((directive, ""), |(k, a)| (k, a.trim()));
+
+        let is_flag = |a: &str| !a.is_empty() && a.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        let directive = match keyword {
+            "include" => argument
+                .strip_prefix('"')
+                .and_then(|a| a.strip_suffix('"'))
+                .filter(|a| !a.is_empty())
+                .map(Directive::Include),
+            "define" if is_flag(argument) => Some(Directive::Define(argument)),
+            "ifdef" if is_flag(argument) => Some(Directive::Ifdef(argument)),
+            "ifndef" if is_flag(argument) => Some(Directive::Ifndef(argument)),
+            "else" if argument.is_empty() => Some(Directive::Else),
+            "endif" if argument.is_empty() => Some(Directive::Endif),
+            _ => None,
+        };
+
+        Some(directive.ok_or(()))
+    }
+}
+
+/// Includes may refer to subdirectories of the group, but not leave it
+fn is_valid_include(include: &str) -> bool {
+    include.split('/').all(|c| {
+        !c.is_empty()
+            && !c.starts_with('.')
+            && c.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    })
+}
+
+struct Conditional {
+    line: usize,
+    active: bool,
+    parent_active: bool,
+    has_else: bool,
+}
+
+struct Preprocessor<'a> {
+    source: &'a ShaderSource,
+    defines: BTreeSet<String>,
+    included: HashSet<&'a str>,
+    output: PreprocessedShader,
+}
+
+impl<'a> Preprocessor<'a> {
+    fn process_file(&mut self, file: &'a str) -> Result<(), ShaderError> {
+        if !self.included.insert(file) {
+            return Ok(());
+        }
+
+        let text = &self.source.files[file];
+        let mut conditionals: Vec<Conditional> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let active = conditionals.last().is_none_or(|c| c.active);
+
+            let directive = match Directive::parse(line) {
+                None => {
+                    if active {
+                        self.output.source.push_str(line);
+                        self.output.source.push('\n');
+                        self.output.lines.push((file.to_string(), line_number));
+                    }
+                    continue;
+                }
+                Some(Err(())) => {
+                    return Err(ShaderError::InvalidDirective {
+                        file: file.to_string(),
+                        line: line_number,
+                        directive: line.trim().to_string(),
+                    });
+                }
+                Some(Ok(directive)) => directive,
+            };
+
+            match directive {
+                Directive::Include(include) if active => {
+                    let Some((include, _)) = self.source.files.get_key_value(include) else {
+                        return Err(ShaderError::MissingInclude {
+                            file: file.to_string(),
+                            line: line_number,
+                            include: include.to_string(),
+                        });
+                    };
+                    self.process_file(include)?;
+                }
+                Directive::Define(flag) if active => {
+                    self.defines.insert(flag.to_string());
+                }
+                Directive::Include(_) | Directive::Define(_) => (),
+                Directive::Ifdef(flag) | Directive::Ifndef(flag) => {
+                    let expected = matches!(directive, Directive::Ifdef(_));
+                    conditionals.push(Conditional {
+                        line: line_number,
+                        active: active && self.defines.contains(flag) == expected,
+                        parent_active: active,
+                        has_else: false,
+                    });
+                }
+                Directive::Else => match conditionals.last_mut() {
+                    Some(c) if !c.has_else => {
+                        c.active = c.parent_active && !c.active;
+                        c.has_else = true;
+                    }
+                    _ => return Err(unbalanced(file, line_number, "#else")),
+                },
+                Directive::Endif => {
+                    conditionals
+                        .pop()
+                        .ok_or_else(|| unbalanced(file, line_number, "#endif"))?;
+                }
+            }
+        }
+
+        match conditionals.first() {
+            Some(c) => Err(ShaderError::UnterminatedConditional {
+                file: file.to_string(),
+                line: c.line,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+fn unbalanced(file: &str, line: usize, directive: &str) -> ShaderError {
+    ShaderError::UnbalancedConditional {
+        file: file.to_string(),
+        line,
+        directive: directive.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(files: &[(&str, &str)]) -> ShaderSource {
+        ShaderSource::with_files(files[0].0, files.iter().map(|(n, t)| (n.to_string(), t.to_string())))
+    }
+
+    #[test]
+    fn includes_are_resolved_once_and_conditionals_select_lines() {
+        let shader = source(&[
+            (
+                "main.wgsl",
+                "#include \"common/a.wgsl\"\n#include \"common/a.wgsl\"\n#ifdef A\nyes\n#else\nno\n#endif\n#ifndef B\nnot-b\n#endif",
+            ),
+            ("common/a.wgsl", "a\n#define A"),
+        ]);
+
+        let output = shader.preprocess(&[]).unwrap();
+        assert_eq!(output.source, "a\nyes\nnot-b\n");
+        assert_eq!(output.lines[0], (String::from("common/a.wgsl"), 1));
+        assert_eq!(output.lines[2], (String::from("main.wgsl"), 9));
+
+        assert_eq!(shader.preprocess(&["B"]).unwrap().source, "a\nyes\n");
+    }
+
+    #[test]
+    fn nested_conditionals_inherit_inactive_branches() {
+        let shader = source(&[("main.wgsl", "#ifdef A\n#ifndef B\nx\n#else\ny\n#endif\n#endif\nz")]);
+
+        assert_eq!(shader.preprocess(&[]).unwrap().source, "z\n");
+        assert_eq!(shader.preprocess(&["A"]).unwrap().source, "x\nz\n");
+        assert_eq!(shader.preprocess(&["A", "B"]).unwrap().source, "y\nz\n");
+    }
+
+    #[test]
+    fn malformed_shaders_report_their_location() {
+        let error = source(&[("main.wgsl", "#ifdef A\nx")]).preprocess(&[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "main.wgsl:1: The conditional is not closed by '#endif'"
+        );
+
+        let error = source(&[("main.wgsl", "x\n#endif")]).preprocess(&[]).unwrap_err();
+        assert!(matches!(error, ShaderError::UnbalancedConditional { line: 2, .. }));
+
+        let error = source(&[("main.wgsl", "#include \"b.wgsl\"")])
+            .preprocess(&[])
+            .unwrap_err();
+        assert!(matches!(error, ShaderError::MissingInclude { .. }));
+
+        let error = source(&[("main.wgsl", "#define A 1")]).preprocess(&[]).unwrap_err();
+        assert!(matches!(error, ShaderError::InvalidDirective { .. }));
+    }
+
+    #[test]
+    fn compilation_errors_point_to_the_included_file() {
+        let shader = source(&[
+            (
+                "main.wgsl",
+                "#include \"common.wgsl\"\nfn main() -> f32 { return one(); }",
+            ),
+            ("common.wgsl", "fn one() -> f32 {\n    return two;\n}"),
+        ]);
+
+        match shader.validate(&[]).unwrap_err() {
+            ShaderError::Validation { origin, .. } | ShaderError::Parse { origin, .. } => {
+                assert_eq!(origin, "common.wgsl:2")
+            }
+            e => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn include_names_stay_within_the_group() {
+        assert!(is_valid_include("common/lighting.wgsl"));
+        assert!(!is_valid_include("../textures/a.wgsl"));
+        assert!(!is_valid_include("/etc/passwd"));
+    }
+
+    #[test]
+    fn includes_are_listed_from_every_branch() {
+        let text = "#include \"common/a.wgsl\"\n#ifdef A\n#include \"common/b.wgsl\"\n#endif\nfn main() {}";
+        assert_eq!(
+            includes("main.wgsl", text).unwrap(),
+            vec!["common/a.wgsl", "common/b.wgsl"]
+        );
+
+        let error = includes("main.wgsl", "\n#include \"../a.wgsl\"").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "main.wgsl:2: The include '../a.wgsl' is not a valid shader name"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+/// Determines how the fragments of a material are combined with the render target
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlendMode {
+    /// Replaces the color behind it
+    #[default]
+    Opaque,
+    /// Like [`BlendMode::Opaque`], but discards fragments with an alpha below the alpha cutoff
+    AlphaTest,
+    /// Mixes with the color behind it according to its alpha
+    AlphaBlend,
+    /// Adds its color, scaled by its alpha, to the color behind it
+    Additive,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 4] = [
+        BlendMode::Opaque,
+        BlendMode::AlphaTest,
+        BlendMode::AlphaBlend,
+        BlendMode::Additive,
+    ];
+
+    /// Transparent materials are drawn after opaque geometry, from back to front, and do not write
+    /// depth
+    pub fn is_transparent(&self) -> bool {
+        matches!(self, BlendMode::AlphaBlend | BlendMode::Additive)
+    }
+
+    pub fn blend_state(&self) -> wgpu::BlendState {
+        match self {
+            BlendMode::Opaque | BlendMode::AlphaTest => wgpu::BlendState::REPLACE,
+            BlendMode::AlphaBlend => wgpu::BlendState::ALPHA_BLENDING,
+            BlendMode::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Zero,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for BlendMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlendMode::Opaque => write!(f, "Opaque"),
+            BlendMode::AlphaTest => write!(f, "Alpha test"),
+            BlendMode::AlphaBlend => write!(f, "Alpha blend"),
+            BlendMode::Additive => write!(f, "Additive"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_blended_modes_are_transparent() {
+        let transparent: Vec<_> = BlendMode::ALL.into_iter().filter(|m| m.is_transparent()).collect();
+        assert_eq!(transparent, vec![BlendMode::AlphaBlend, BlendMode::Additive]);
+    }
+}
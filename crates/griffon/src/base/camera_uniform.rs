@@ -1,5 +1,7 @@
 #[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 pub struct CameraUniform {
+    /// Transforms world space positions to view space
+    pub view: [[f32; 4]; 4],
     pub projection: [[f32; 4]; 4],
 }
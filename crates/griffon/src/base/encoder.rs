@@ -1,9 +1,10 @@
 use std::ops::Range;
 
 use super::gpu_object_database::GpuObjectDatabase;
+use super::gpu_timer::GpuTimer;
 use super::ids::TextureViewId;
 use super::ids::{BindGroupId, BufferId, PipelineId};
-use super::runtime::Runtime;
+use super::runtime::{RenderTarget, Runtime};
 use super::settings::Settings;
 use wgpu::{LoadOp, StoreOp};
 
@@ -13,7 +14,11 @@ pub struct Encoder<'rt> {
     settings: &'rt Settings,
     database: &'rt GpuObjectDatabase,
     depth_texture_view: TextureViewId,
-    output: wgpu::SurfaceTexture,
+    /// Measures the GPU time of each pass if the device supports timestamp queries
+    timer: Option<&'rt GpuTimer>,
+    /// The labels of the passes timed so far
+    timed_passes: Vec<String>,
+    output: Option<wgpu::SurfaceTexture>,
     surface_view: wgpu::TextureView,
     encoder: wgpu::CommandEncoder,
 }
@@ -25,15 +30,23 @@ impl<'rt> Encoder<'rt> {
         settings: &'rt Settings,
         database: &'rt GpuObjectDatabase,
         depth_texture_view: TextureViewId,
+        timer: Option<&'rt GpuTimer>,
     ) -> Result<Self, wgpu::SurfaceError> {
-        crate::trace_gfx!("Getting surface texture");
-        let output = runtime.surface.get_current_texture()?;
-
         crate::trace_gfx!("Creating surface texture view '{}'", label.unwrap_or("unnamed"));
-        let surface_view = output.texture.create_view(&wgpu::TextureViewDescriptor {
-            label: label.map(|lbl| format!("{}:surface-texture-view", lbl)).as_deref(),
+        let view_label = label.map(|lbl| format!("{}:surface-texture-view", lbl));
+        let view_descriptor = wgpu::TextureViewDescriptor {
+            label: view_label.as_deref(),
             ..Default::default()
-        });
+        };
+        let (output, surface_view) = match &runtime.target {
+            RenderTarget::Window { surface, .. } => {
+                crate::trace_gfx!("Getting surface texture");
+                let output = surface.get_current_texture()?;
+                let view = output.texture.create_view(&view_descriptor);
+                (Some(output), view)
+            }
+            RenderTarget::Offscreen { texture } => (None, texture.create_view(&view_descriptor)),
+        };
 
         crate::trace_gfx!("Creating command encoder '{}'", label.unwrap_or("unnamed"));
         let encoder = runtime
@@ -45,6 +58,8 @@ impl<'rt> Encoder<'rt> {
             settings,
             database,
             depth_texture_view,
+            timer,
+            timed_passes: Vec::new(),
             output,
             surface_view,
             encoder,
@@ -52,64 +67,156 @@ impl<'rt> Encoder<'rt> {
     }
 
     pub fn begin(&mut self, label: Option<&str>) -> RenderPass<'_> {
+        self.begin_target(label, None, true)
+    }
+
+    /// Begin a render pass into the given color and depth texture views, or into the surface and
+    /// the default depth texture if `target` is `None`. The depth is always cleared, the color
+    /// only if `clear_color` is set, so that several cameras can share a render target.
+    pub fn begin_target(
+        &mut self,
+        label: Option<&str>,
+        target: Option<(TextureViewId, TextureViewId)>,
+        clear_color: bool,
+    ) -> RenderPass<'_> {
+        let (color_view, depth_view) = match target {
+            Some((color, depth)) => (Some(color), depth),
+            None => (None, self.depth_texture_view),
+        };
+        let clear = clear_color.then_some(self.settings.clear_color);
+
+        self.begin_pass(
+            label,
+            &[ColorAttachment {
+                view: color_view,
+                clear,
+            }],
+            Some(DepthAttachment {
+                view: depth_view,
+                clear: Some(1.0),
+            }),
+        )
+    }
+
+    /// Begin a pass that only renders depth into the given texture view, e.g. a shadow map
+    pub fn begin_depth_pass(&mut self, label: Option<&str>, view: TextureViewId) -> RenderPass<'_> {
+        self.begin_pass(label, &[], Some(DepthAttachment { view, clear: Some(1.0) }))
+    }
+
+    /// Begin a render pass with any number of color attachments and an optional depth attachment.
+    /// Attachments without a clear value retain their previous contents.
+    pub fn begin_pass(
+        &mut self,
+        label: Option<&str>,
+        color_attachments: &[ColorAttachment],
+        depth_attachment: Option<DepthAttachment>,
+    ) -> RenderPass<'_> {
+        let color_attachments: Vec<_> = color_attachments
+            .iter()
+            .map(|ca| {
+                let view = match ca.view {
+                    Some(id) => self
+                        .database
+                        .texture_views
+                        .get(&id)
+                        .unwrap_or_else(|| panic!("Developer error: found no color texture with ID {:?}", id)),
+                    None => &self.surface_view,
+                };
+                Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    depth_slice: None,
+                    ops: wgpu::Operations {
+                        load: ca.clear.map_or(LoadOp::Load, LoadOp::Clear),
+                        store: StoreOp::Store,
+                    },
+                })
+            })
+            .collect();
+
         crate::trace_gfx!("Obtain ref. for depth texture view");
-        let dtv = self
-            .database
-            .texture_views
-            .get(&self.depth_texture_view)
-            .unwrap_or_else(|| {
-                panic!(
-                    "Developer error: found no depth texture with ID {:?}",
-                    self.depth_texture_view
-                )
-            });
+        let depth_stencil_attachment = depth_attachment.map(|da| wgpu::RenderPassDepthStencilAttachment {
+            view: self
+                .database
+                .texture_views
+                .get(&da.view)
+                .unwrap_or_else(|| panic!("Developer error: found no depth texture with ID {:?}", da.view)),
+            depth_ops: Some(wgpu::Operations {
+                load: da.clear.map_or(LoadOp::Load, LoadOp::Clear),
+                store: StoreOp::Store,
+            }),
+            stencil_ops: None,
+        });
+
+        let timestamp_writes = self
+            .timer
+            .and_then(|timer| timer.timestamp_writes(self.timed_passes.len()));
+        if timestamp_writes.is_some() {
+            self.timed_passes.push(label.unwrap_or("unnamed").to_string());
+        }
 
         crate::trace_gfx!("Beginning render pass '{}'", label.unwrap_or("unnamed"));
         let render_pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &self.surface_view,
-                resolve_target: None,
-                depth_slice: None,
-                ops: wgpu::Operations {
-                    load: LoadOp::Clear(self.settings.clear_color),
-                    store: StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: dtv,
-                depth_ops: Some(wgpu::Operations {
-                    load: LoadOp::Clear(1.0),
-                    store: StoreOp::Store,
-                }),
-                stencil_ops: None,
-            }),
-            timestamp_writes: None,
+            color_attachments: &color_attachments,
+            depth_stencil_attachment,
+            timestamp_writes,
             occlusion_query_set: None,
         });
 
         RenderPass {
             render_pass,
             database: self.database,
+            draw_calls: 0,
         }
     }
 
-    pub fn submit(self) {
+    /// Submits the recorded passes and presents the surface. The GPU times of the passes can be
+    /// collected a few frames later with
+    /// [`Graphics::collect_gpu_timings`](crate::Graphics::collect_gpu_timings).
+    pub fn submit(mut self) {
+        let readback = self
+            .timer
+            .and_then(|timer| timer.resolve(&mut self.encoder, std::mem::take(&mut self.timed_passes)));
+
         crate::trace_gfx!("Creating command buffer");
         let command_buffer = self.encoder.finish();
         crate::trace_gfx!("Submitting command buffer");
         #[allow(unused_variables)]
         let si = self.runtime.queue.submit(std::iter::once(command_buffer));
         crate::trace_gfx!("Submission index: {:?}", si);
-        self.runtime.window.pre_present_notify();
-        self.output.present();
+        if let (Some(timer), Some(readback)) = (self.timer, readback) {
+            timer.map(readback);
+        }
+        if let (Some(output), Some(window)) = (self.output, self.runtime.window()) {
+            window.pre_present_notify();
+            output.present();
+        }
     }
 }
 
+/// A color attachment of a render pass
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorAttachment {
+    /// The texture view to render into, or `None` for the surface
+    pub view: Option<TextureViewId>,
+    /// Clears the attachment before rendering if set
+    pub clear: Option<wgpu::Color>,
+}
+
+/// The depth attachment of a render pass
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthAttachment {
+    pub view: TextureViewId,
+    /// Clears the attachment to this depth before rendering if set
+    pub clear: Option<f32>,
+}
+
 #[derive(Debug)]
 pub struct RenderPass<'rp> {
     render_pass: wgpu::RenderPass<'rp>,
     database: &'rp GpuObjectDatabase,
+    draw_calls: usize,
 }
 
 impl<'rp> RenderPass<'rp> {
@@ -130,6 +237,12 @@ impl<'rp> RenderPass<'rp> {
         self
     }
 
+    /// Restrict rendering to a rectangle of the render target, in pixels
+    pub fn set_viewport(&mut self, x: f32, y: f32, width: f32, height: f32) -> &mut Self {
+        self.render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        self
+    }
+
     pub fn set_vertex_buffer(&mut self, slot: u32, buffer: BufferId) -> &mut Self {
         self.render_pass
             .set_vertex_buffer(slot, self.database.buffers[&buffer].slice(..));
@@ -142,8 +255,22 @@ impl<'rp> RenderPass<'rp> {
         self
     }
 
+    /// Draws vertices without vertex buffers, e.g. a full-screen triangle generated from the vertex
+    /// index
+    pub fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>) -> &mut Self {
+        self.draw_calls += 1;
+        self.render_pass.draw(vertices, instances);
+        self
+    }
+
     pub fn draw_indexed(&mut self, ind: Range<u32>, base_vert: i32, inst: Range<u32>) -> &mut Self {
+        self.draw_calls += 1;
         self.render_pass.draw_indexed(ind, base_vert, inst);
         self
     }
+
+    /// Returns the number of draw calls recorded so far
+    pub fn draw_calls(&self) -> usize {
+        self.draw_calls
+    }
 }
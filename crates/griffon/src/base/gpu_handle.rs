@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use crate::base::ids::{BindGroupId, BufferId, InstanceId, SamplerId, TextureId, TextureViewId};
+
+/// Shared ownership of a set of GPU objects. [`Graphics`](crate::Graphics) keeps one handle of its
+/// own and releases the objects during [`Graphics::collect_garbage`](crate::Graphics::collect_garbage)
+/// once all other handles have been dropped.
+#[derive(Debug, Clone)]
+pub struct GpuHandle(Arc<GpuObjects>);
+
+impl GpuHandle {
+    pub(crate) fn new(objects: GpuObjects) -> Self {
+        GpuHandle(Arc::new(objects))
+    }
+
+    /// Returns the number of handles that refer to the same objects
+    pub fn owners(&self) -> usize {
+        Arc::strong_count(&self.0)
+    }
+
+    /// Returns the objects if this is the last handle that refers to them
+    pub(crate) fn into_objects(self) -> Option<GpuObjects> {
+        Arc::into_inner(self.0)
+    }
+}
+
+/// The GPU objects and mesh instances owned by a [`GpuHandle`]
+#[derive(Debug, Default)]
+pub(crate) struct GpuObjects {
+    pub bind_groups: Vec<BindGroupId>,
+    pub buffers: Vec<BufferId>,
+    pub textures: Vec<TextureId>,
+    pub texture_views: Vec<TextureViewId>,
+    pub samplers: Vec<SamplerId>,
+    pub instances: Vec<InstanceId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objects_are_only_released_by_the_last_handle() {
+        let handle = GpuHandle::new(GpuObjects {
+            buffers: vec![BufferId::from(3)],
+            ..Default::default()
+        });
+        let clone = handle.clone();
+        assert_eq!(handle.owners(), 2);
+        assert!(clone.into_objects().is_none());
+
+        let objects = handle.into_objects().unwrap();
+        assert_eq!(objects.buffers, vec![BufferId::from(3)]);
+    }
+}
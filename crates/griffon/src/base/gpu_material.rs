@@ -1,8 +1,15 @@
+use crate::base::blend_mode::BlendMode;
+use crate::base::gpu_handle::GpuHandle;
 use crate::base::gpu_texture::GpuTexture;
 use crate::base::ids::BindGroupId;
 
 #[derive(Debug, Clone)]
 pub struct GpuMaterial {
-    pub texture: GpuTexture,
+    pub diffuse_texture: GpuTexture,
+    pub normal_texture: GpuTexture,
+    pub specular_texture: GpuTexture,
     pub bind_group: BindGroupId,
+    pub blend_mode: BlendMode,
+    /// Owns the textures, uniform buffer and bind group of the material
+    pub handle: GpuHandle,
 }
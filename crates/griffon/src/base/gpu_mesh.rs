@@ -1,3 +1,6 @@
+use crate::assets::cpu_mesh::Submesh;
+use crate::assets::mesh_processing::BoundingBox;
+use crate::base::gpu_handle::GpuHandle;
 use crate::base::ids::BufferId;
 use crate::base::ids::InstanceId;
 
@@ -7,5 +10,14 @@ pub struct GpuMesh {
     pub instance_buffer: BufferId,
     pub index_buffer: BufferId,
     pub num_indices: u32,
+    pub submeshes: Vec<Submesh>,
+    pub bounds: BoundingBox,
+    /// The vertex buffer holds [`SkinnedVertex`](crate::base::skinned_vertex::SkinnedVertex)
+    /// instead of [`Vertex`](crate::base::vertex::Vertex) data
+    pub skinned: bool,
     pub instance_id: InstanceId,
+    /// Owns the buffers, shared by all instances of the mesh
+    pub handle: GpuHandle,
+    /// Owns the instance ID
+    pub instance_handle: GpuHandle,
 }
@@ -50,6 +50,12 @@ impl GpuObjectDatabase {
         id
     }
 
+    /// Replaces the buffer behind an existing ID, such that everything referring to the ID uses the
+    /// new buffer from now on
+    pub fn replace_buffer(&mut self, id: BufferId, obj: wgpu::Buffer) -> Option<wgpu::Buffer> {
+        self.buffers.insert(id, obj)
+    }
+
     pub fn insert_texture(&mut self, obj: wgpu::Texture) -> TextureId {
         let id = self.texture_index.take();
         self.textures.insert(id, obj);
@@ -73,4 +79,96 @@ impl GpuObjectDatabase {
         self.render_pipelines.insert(id, obj);
         id
     }
+
+    /// Returns the number of objects of each kind that are currently allocated
+    pub fn allocation_counts(&self) -> AllocationCounts {
+        AllocationCounts {
+            shader_modules: self.shader_modules.len(),
+            bind_group_layouts: self.bind_group_layouts.len(),
+            bind_groups: self.bind_groups.len(),
+            buffers: self.buffers.len(),
+            textures: self.textures.len(),
+            texture_views: self.texture_views.len(),
+            samplers: self.samplers.len(),
+            render_pipelines: self.render_pipelines.len(),
+            instances: 0,
+        }
+    }
+
+    pub fn remove_shader_module(&mut self, id: ShaderModuleId) -> Option<wgpu::ShaderModule> {
+        let obj = self.shader_modules.remove(&id)?;
+        self.shader_module_index.give_back(id);
+        Some(obj)
+    }
+
+    pub fn remove_bind_group_layout(&mut self, id: BindGroupLayoutId) -> Option<wgpu::BindGroupLayout> {
+        let obj = self.bind_group_layouts.remove(&id)?;
+        self.bind_group_layout_index.give_back(id);
+        Some(obj)
+    }
+
+    pub fn remove_bind_group(&mut self, id: BindGroupId) -> Option<wgpu::BindGroup> {
+        let obj = self.bind_groups.remove(&id)?;
+        self.bind_group_index.give_back(id);
+        Some(obj)
+    }
+
+    pub fn remove_buffer(&mut self, id: BufferId) -> Option<wgpu::Buffer> {
+        let obj = self.buffers.remove(&id)?;
+        self.buffer_index.give_back(id);
+        Some(obj)
+    }
+
+    pub fn remove_texture(&mut self, id: TextureId) -> Option<wgpu::Texture> {
+        let obj = self.textures.remove(&id)?;
+        self.texture_index.give_back(id);
+        Some(obj)
+    }
+
+    pub fn remove_texture_view(&mut self, id: TextureViewId) -> Option<wgpu::TextureView> {
+        let obj = self.texture_views.remove(&id)?;
+        self.texture_view_index.give_back(id);
+        Some(obj)
+    }
+
+    pub fn remove_sampler(&mut self, id: SamplerId) -> Option<wgpu::Sampler> {
+        let obj = self.samplers.remove(&id)?;
+        self.sampler_index.give_back(id);
+        Some(obj)
+    }
+
+    pub fn remove_render_pipeline(&mut self, id: PipelineId) -> Option<wgpu::RenderPipeline> {
+        let obj = self.render_pipelines.remove(&id)?;
+        self.render_pipeline_index.give_back(id);
+        Some(obj)
+    }
+}
+
+/// The number of GPU objects held by [`GpuObjectDatabase`] and of mesh instances handed out by
+/// [`Graphics`](crate::Graphics)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AllocationCounts {
+    pub shader_modules: usize,
+    pub bind_group_layouts: usize,
+    pub bind_groups: usize,
+    pub buffers: usize,
+    pub textures: usize,
+    pub texture_views: usize,
+    pub samplers: usize,
+    pub render_pipelines: usize,
+    pub instances: usize,
+}
+
+impl std::fmt::Display for AllocationCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "shader modules: {}", self.shader_modules)?;
+        writeln!(f, "bind group layouts: {}", self.bind_group_layouts)?;
+        writeln!(f, "bind groups: {}", self.bind_groups)?;
+        writeln!(f, "buffers: {}", self.buffers)?;
+        writeln!(f, "textures: {}", self.textures)?;
+        writeln!(f, "texture views: {}", self.texture_views)?;
+        writeln!(f, "samplers: {}", self.samplers)?;
+        writeln!(f, "render pipelines: {}", self.render_pipelines)?;
+        write!(f, "instances: {}", self.instances)
+    }
 }
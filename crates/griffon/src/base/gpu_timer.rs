@@ -0,0 +1,210 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+/// The maximum number of render passes per frame whose GPU time is measured
+pub const MAX_TIMED_PASSES: u32 = 64;
+/// The number of frames whose timestamps may be read back at the same time
+const READBACK_SLOTS: usize = 3;
+const TIMESTAMP_SIZE: wgpu::BufferAddress = size_of::<u64>() as wgpu::BufferAddress;
+
+const SLOT_FREE: u8 = 0;
+const SLOT_MAPPING: u8 = 1;
+const SLOT_MAPPED: u8 = 2;
+
+/// The time the GPU spent executing a render pass
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PassTiming {
+    pub label: String,
+    pub duration: Duration,
+}
+
+/// Measures the GPU time of render passes with timestamp queries, which requires
+/// [`wgpu::Features::TIMESTAMP_QUERY`]. The timestamps of a frame are copied into a readback
+/// buffer when it is submitted and collected once the GPU is done with it, usually a frame or two
+/// later, without ever blocking.
+#[derive(Debug)]
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    slots: Vec<ReadbackSlot>,
+    /// The number of nanoseconds per timestamp tick
+    period: f32,
+}
+
+#[derive(Debug)]
+struct ReadbackSlot {
+    buffer: wgpu::Buffer,
+    /// Set by the callback of [`wgpu::Buffer::map_async`]
+    state: Arc<AtomicU8>,
+    /// The labels of the timed passes, in the order of their timestamps
+    labels: std::sync::Mutex<Vec<String>>,
+}
+
+impl GpuTimer {
+    /// Returns `None` if the device does not support timestamp queries
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            tracing::debug!("The device does not support timestamp queries, GPU timings are unavailable");
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("gpu-timer:query-set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2 * MAX_TIMED_PASSES,
+        });
+        let size = 2 * MAX_TIMED_PASSES as wgpu::BufferAddress * TIMESTAMP_SIZE;
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("gpu-timer:resolve-buffer"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let slots = (0..READBACK_SLOTS)
+            .map(|_| ReadbackSlot {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("gpu-timer:readback-buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                state: Arc::new(AtomicU8::new(SLOT_FREE)),
+                labels: std::sync::Mutex::new(Vec::new()),
+            })
+            .collect();
+
+        Some(GpuTimer {
+            query_set,
+            resolve_buffer,
+            slots,
+            period: queue.get_timestamp_period(),
+        })
+    }
+
+    /// Returns the timestamp writes of the `index`th pass of a frame, or `None` if the pass is not
+    /// timed
+    pub(crate) fn timestamp_writes(&self, index: usize) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        let index = u32::try_from(index).ok().filter(|&i| i < MAX_TIMED_PASSES)?;
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(2 * index),
+            end_of_pass_write_index: Some(2 * index + 1),
+        })
+    }
+
+    /// Copies the timestamps of the timed passes into a free readback buffer, and returns the
+    /// index of that buffer. Returns `None` while all readback buffers are in use, in which case
+    /// the timings of the frame are dropped.
+    pub(crate) fn resolve(&self, encoder: &mut wgpu::CommandEncoder, labels: Vec<String>) -> Option<usize> {
+        if labels.is_empty() {
+            return None;
+        }
+
+        let index = self
+            .slots
+            .iter()
+            .position(|s| s.state.load(Ordering::Acquire) == SLOT_FREE)?;
+        let slot = &self.slots[index];
+        let num_queries = 2 * labels.len() as u32;
+        encoder.resolve_query_set(&self.query_set, 0..num_queries, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &slot.buffer,
+            0,
+            num_queries as wgpu::BufferAddress * TIMESTAMP_SIZE,
+        );
+        slot.state.store(SLOT_MAPPING, Ordering::Release);
+        *slot.labels.lock().unwrap() = labels;
+
+        Some(index)
+    }
+
+    /// Maps the readback buffer once the submitted frame has been executed by the GPU
+    pub(crate) fn map(&self, index: usize) {
+        let slot = &self.slots[index];
+        let num_queries = 2 * slot.labels.lock().unwrap().len() as wgpu::BufferAddress;
+        let state = slot.state.clone();
+        slot.buffer
+            .map_async(wgpu::MapMode::Read, 0..num_queries * TIMESTAMP_SIZE, move |result| {
+                if let Err(e) = result {
+                    tracing::warn!("Failed to read back the GPU timestamps: {e}");
+                    state.store(SLOT_FREE, Ordering::Release);
+                } else {
+                    state.store(SLOT_MAPPED, Ordering::Release);
+                }
+            });
+    }
+
+    /// Returns the pass timings of a frame that the GPU finished since the last call, if any.
+    /// Should several frames have finished in the meantime, all but one are dropped.
+    pub fn collect(&self, device: &wgpu::Device) -> Option<Vec<PassTiming>> {
+        if let Err(e) = device.poll(wgpu::PollType::Poll) {
+            tracing::warn!("Failed to poll the device for GPU timestamps: {e}");
+        }
+
+        let mut latest = None;
+        for slot in &self.slots {
+            if slot.state.load(Ordering::Acquire) != SLOT_MAPPED {
+                continue;
+            }
+
+            let labels = std::mem::take(&mut *slot.labels.lock().unwrap());
+            let timestamps: Vec<u64> = {
+                let range = slot
+                    .buffer
+                    .slice(0..2 * labels.len() as wgpu::BufferAddress * TIMESTAMP_SIZE)
+                    .get_mapped_range();
+                bytemuck::cast_slice(&range).to_vec()
+            };
+            slot.buffer.unmap();
+            slot.state.store(SLOT_FREE, Ordering::Release);
+
+            latest = Some(pass_timings(labels, &timestamps, self.period));
+        }
+
+        latest
+    }
+}
+
+/// Pairs each label with the difference of its begin and end timestamp
+fn pass_timings(labels: Vec<String>, timestamps: &[u64], period: f32) -> Vec<PassTiming> {
+    labels
+        .into_iter()
+        .zip(timestamps.chunks_exact(2))
+        .map(|(label, ts)| PassTiming {
+            label,
+            // Some drivers report timestamps that decrease within a pass
+            duration: Duration::from_nanos((ts[1].saturating_sub(ts[0]) as f64 * period as f64) as u64),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pass_timings_scale_ticks_by_the_period() {
+        let timings = pass_timings(
+            vec!["shadow-pass".into(), "opaque-pass".into()],
+            &[100, 600, 700, 650],
+            2.0,
+        );
+
+        assert_eq!(
+            timings,
+            vec![
+                PassTiming {
+                    label: "shadow-pass".into(),
+                    duration: Duration::from_nanos(1000),
+                },
+                PassTiming {
+                    label: "opaque-pass".into(),
+                    duration: Duration::ZERO,
+                },
+            ]
+        );
+    }
+}
@@ -45,6 +45,12 @@ impl From<InstanceId> for u32 {
     }
 }
 
+impl From<InstanceId> for usize {
+    fn from(value: InstanceId) -> Self {
+        value.0 as usize
+    }
+}
+
 impl From<usize> for InstanceId {
     fn from(value: usize) -> Self {
         Self(value as u32)
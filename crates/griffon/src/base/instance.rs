@@ -1,13 +1,19 @@
 use crate::base::descriptors::VertexAttributeDescriptor;
 
+/// The joint offset of instances that are drawn without skinning
+pub const NO_SKELETON: u32 = u32::MAX;
+
 #[derive(Debug, Default, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, serde::Serialize, serde::Deserialize)]
 #[repr(C)]
 pub struct Instance {
-    pub model_view: [[f32; 4]; 4],
+    /// Transforms model space positions to world space
+    pub model: [[f32; 4]; 4],
+    /// Transforms model space normals to world space
     pub normal: [[f32; 4]; 4],
-    // TODO: this kind of handling of non-projected UI objects is janky
-    pub with_camera: f32,
     pub with_material: f32,
+    /// Index of the first joint matrix of the instance, or [`NO_SKELETON`] if the mesh is not
+    /// deformed
+    pub joint_offset: u32,
 }
 
 impl VertexAttributeDescriptor for Instance {
@@ -22,6 +28,6 @@ impl VertexAttributeDescriptor for Instance {
         10 => Float32x4,
         11 => Float32x4,
         12 => Float32,
-        13 => Float32,
+        13 => Uint32,
     ];
 }
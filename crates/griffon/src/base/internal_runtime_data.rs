@@ -1,12 +1,40 @@
-use crate::base::ids::{BindGroupLayoutId, InstanceId, TextureId, TextureViewId};
+use std::collections::HashMap;
+
+use crate::base::gpu_handle::GpuHandle;
+use crate::base::gpu_material::GpuMaterial;
+use crate::base::gpu_texture::GpuTexture;
+use crate::base::gpu_timer::GpuTimer;
+use crate::base::ids::{BindGroupLayoutId, InstanceId, ShaderModuleId, TextureId, TextureViewId};
+use crate::base::render_graph::TransientTexture;
 use urn::Urn;
 
 #[derive(Debug)]
 pub struct InternalRuntimeData {
     pub camera_bind_group_layout: BindGroupLayoutId,
     pub light_bind_group_layout: BindGroupLayoutId,
+    /// Binds the light transform of a single shadow map with a dynamic offset
+    pub shadow_bind_group_layout: BindGroupLayoutId,
+    /// Binds the joint matrices of all skinned instances of a frame
+    pub joint_bind_group_layout: BindGroupLayoutId,
     pub material_bind_group_layout: BindGroupLayoutId,
+    /// Binds the source texture and parameters of a full-screen post-processing pass
+    pub post_process_bind_group_layout: BindGroupLayoutId,
     pub depth_texture: TextureId,
     pub depth_texture_view: TextureViewId,
+    /// Flat tangent-space normal map used by materials without a normal texture
+    pub default_normal_texture: GpuTexture,
+    /// White specular map used by materials without a specular texture
+    pub default_specular_texture: GpuTexture,
+    /// White material bound for models without materials, only `None` during initialization
+    pub default_material: Option<GpuMaterial>,
     pub instances: Urn<InstanceId>,
+    /// One handle for each set of objects owned by models and materials, see
+    /// [`Graphics::collect_garbage`](crate::Graphics::collect_garbage)
+    pub handles: Vec<GpuHandle>,
+    /// Compiled shader variants by the fingerprint of their source and their sorted feature flags
+    pub shader_variants: HashMap<(u64, Vec<String>), ShaderModuleId>,
+    /// The textures of render graph slots, which are kept while consecutive frames request them
+    pub transient_textures: Vec<(TransientTexture, TextureId, TextureViewId)>,
+    /// Only present if the device supports timestamp queries
+    pub gpu_timer: Option<GpuTimer>,
 }
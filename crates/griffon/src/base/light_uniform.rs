@@ -1,11 +1,46 @@
+/// Precedes the array of [`LightUniform`] in the light storage buffer
+#[derive(Debug, Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct LightBufferHeader {
+    pub num_lights: u32,
+    pub shadow_pcf_radius: u32,
+    /// The size of a texel of the shadow atlas in texture coordinates
+    pub shadow_texel_size: f32,
+    pub _padding: u32,
+}
+
 #[derive(Debug, Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 pub struct LightUniform {
-    pub model_view: [[f32; 4]; 4],
+    /// Transforms model space positions of the light to world space
+    pub model: [[f32; 4]; 4],
+    /// The direction of the light in world space
+    pub direction: [f32; 4],
     pub ambient_color: [f32; 4],
     pub diffuse_color: [f32; 4],
     pub specular_color: [f32; 4],
+    /// Constant, linear, and quadratic attenuation factors
+    pub attenuation: [f32; 3],
+    /// One of the discriminants of [`LightKind`](crate::components::light::LightKind)
+    pub kind: u32,
     pub ambient_intensity: f32,
     pub point_intensity: f32,
-    pub _padding: [f32; 2],
+    pub cos_inner_cone_angle: f32,
+    pub cos_outer_cone_angle: f32,
+    /// Transforms world space positions to the clip space of the light's shadow map
+    pub shadow_world_to_clip: [[f32; 4]; 4],
+    /// The offset and size of the light's shadow map in the shadow atlas, in texture coordinates
+    pub shadow_atlas_rect: [f32; 4],
+    pub shadow_bias: f32,
+    /// Non-zero if the light has a shadow map
+    pub casts_shadows: u32,
+    pub _padding: [u32; 2],
+}
+
+/// The per-light data of the shadow pass
+#[derive(Debug, Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct ShadowUniform {
+    /// Transforms world space positions to the clip space of the light's shadow map
+    pub world_to_clip: [[f32; 4]; 4],
 }
@@ -0,0 +1,14 @@
+use crate::base::descriptors::VertexAttributeDescriptor;
+
+/// An end point of a line segment, drawn with a line list topology
+#[derive(Debug, Default, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct LineVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl VertexAttributeDescriptor for LineVertex {
+    const STEP_MODE: wgpu::VertexStepMode = wgpu::VertexStepMode::Vertex;
+    const ATTRS: &'static [wgpu::VertexAttribute] = &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x4];
+}
@@ -5,4 +5,9 @@ pub struct MaterialUniform {
     pub diffuse_reflectivity: f32,
     pub specular_reflectivity: f32,
     pub smoothness: f32,
+    /// Fragments with an alpha below this value are discarded by alpha-tested materials
+    pub alpha_cutoff: f32,
+    /// Multiplies the alpha of the diffuse texture
+    pub opacity: f32,
+    pub _padding: [f32; 2],
 }
@@ -0,0 +1,93 @@
+use image::RgbaImage;
+
+/// Returns the number of levels of a full mip chain, down to a single texel
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+/// Generates a full mip chain with a 2x2 box filter, where the first level is the image itself.
+/// The color channels of sRGB images are averaged in linear space, alpha is always linear.
+pub fn generate_mip_chain(image: &RgbaImage, srgb: bool) -> Vec<RgbaImage> {
+    let mut levels = vec![image.clone()];
+    while let Some(previous) = levels.last().filter(|l| l.width() > 1 || l.height() > 1) {
+        levels.push(downsample(previous, srgb));
+    }
+    levels
+}
+
+/// Halves both dimensions of the image. Texels of the last row or column of an odd dimension
+/// are sampled twice.
+fn downsample(image: &RgbaImage, srgb: bool) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    RgbaImage::from_fn((width / 2).max(1), (height / 2).max(1), |x, y| {
+        let xs = [(2 * x).min(width - 1), (2 * x + 1).min(width - 1)];
+        let ys = [(2 * y).min(height - 1), (2 * y + 1).min(height - 1)];
+
+        let mut sum = [0.0f32; 4];
+        for (sx, sy) in ys.iter().flat_map(|&sy| xs.iter().map(move |&sx| (sx, sy))) {
+            let texel = image.get_pixel(sx, sy);
+            for (c, s) in sum.iter_mut().enumerate() {
+                let value = f32::from(texel[c]) / 255.0;
+                *s += if srgb && c < 3 { srgb_to_linear(value) } else { value };
+            }
+        }
+
+        image::Rgba(std::array::from_fn(|c| {
+            let value = sum[c] / 4.0;
+            let value = if srgb && c < 3 { linear_to_srgb(value) } else { value };
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        }))
+    })
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mip_chain_halves_down_to_a_single_texel() {
+        let image = RgbaImage::from_pixel(5, 2, image::Rgba([10, 20, 30, 255]));
+        let levels = generate_mip_chain(&image, true);
+
+        let dims: Vec<_> = levels.iter().map(|l| l.dimensions()).collect();
+        assert_eq!(dims, vec![(5, 2), (2, 1), (1, 1)]);
+        assert_eq!(levels.len() as u32, mip_level_count(5, 2));
+        // A uniform image stays uniform, also through the sRGB round trip
+        assert_eq!(levels[2].get_pixel(0, 0), &image::Rgba([10, 20, 30, 255]));
+    }
+
+    #[test]
+    fn box_filter_averages_linear_data() {
+        let image = RgbaImage::from_fn(2, 2, |x, _| {
+            if x == 0 {
+                image::Rgba([0, 0, 0, 0])
+            } else {
+                image::Rgba([255, 255, 255, 255])
+            }
+        });
+
+        assert_eq!(
+            generate_mip_chain(&image, false)[1].get_pixel(0, 0),
+            &image::Rgba([128; 4])
+        );
+        // Averaging black and white in linear space yields a brighter sRGB value
+        let srgb = generate_mip_chain(&image, true);
+        assert_eq!(srgb[1].get_pixel(0, 0), &image::Rgba([188, 188, 188, 128]));
+    }
+}
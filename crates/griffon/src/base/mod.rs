@@ -1,21 +1,31 @@
 pub mod bind_group_builder;
 pub mod bind_group_layout_builder;
+pub mod blend_mode;
 pub mod camera_uniform;
 pub mod descriptors;
 pub mod encoder;
+pub mod gpu_handle;
 pub mod gpu_material;
 pub mod gpu_mesh;
 pub mod gpu_model;
 pub mod gpu_object_database;
 pub mod gpu_texture;
+pub mod gpu_timer;
 pub mod ids;
 pub mod instance;
 pub mod internal_runtime_data;
 pub mod light_uniform;
+pub mod line_vertex;
 pub mod material_uniform;
+pub mod mipmaps;
+pub mod post_process_settings;
+pub mod post_process_uniform;
+pub mod render_graph;
 pub mod render_pipeline_builder;
 pub mod runtime;
 pub mod sampler_builder;
 pub mod settings;
+pub mod skinned_vertex;
 pub mod texture_builder;
 pub mod vertex;
+pub mod window_settings;
@@ -0,0 +1,189 @@
+use crate::base::post_process_uniform::PostProcessUniform;
+
+/// The format of the offscreen texture the scene is rendered into before post-processing
+pub const HDR_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// The chain of full-screen effects applied to the scene, in the order of the fields
+///
+/// ```toml
+/// [post_process]
+/// enabled = true
+///
+/// [post_process.bloom]
+/// threshold = 1.5
+/// intensity = 0.2
+///
+/// [post_process.fxaa]
+/// enabled = false
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct PostProcessSettings {
+    /// Renders the scene into a texture of [`HDR_TEXTURE_FORMAT`] followed by the effects below.
+    /// Otherwise, the scene is rendered straight into the surface and all effects are skipped.
+    pub enabled: bool,
+    pub bloom: BloomSettings,
+    pub tonemapping: TonemappingSettings,
+    pub gamma: GammaSettings,
+    pub fxaa: FxaaSettings,
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        PostProcessSettings {
+            enabled: true,
+            bloom: BloomSettings::default(),
+            tonemapping: TonemappingSettings::default(),
+            gamma: GammaSettings::default(),
+            fxaa: FxaaSettings::default(),
+        }
+    }
+}
+
+impl PostProcessSettings {
+    /// Returns the parameters of the effects as seen by the shaders, where `srgb_surface` states
+    /// whether the surface encodes colors in hardware
+    pub fn uniform(&self, srgb_surface: bool) -> PostProcessUniform {
+        // sRGB surfaces apply a gamma of about 2.2 on their own
+        let gamma_exponent = match (self.gamma.enabled, srgb_surface) {
+            (true, true) => 2.2 / self.gamma.gamma,
+            (true, false) => 1.0 / self.gamma.gamma,
+            (false, _) => 1.0,
+        };
+
+        PostProcessUniform {
+            bloom_threshold: self.bloom.threshold,
+            bloom_knee: self.bloom.knee,
+            bloom_intensity: if self.bloom.enabled { self.bloom.intensity } else { 0.0 },
+            exposure: if self.tonemapping.enabled {
+                self.tonemapping.exposure
+            } else {
+                1.0
+            },
+            tonemapping: self.tonemapping.enabled as u32,
+            gamma_exponent,
+            fxaa_edge_threshold: self.fxaa.edge_threshold,
+            fxaa_subpixel_blending: self.fxaa.subpixel_blending,
+        }
+    }
+}
+
+/// Makes bright parts of the image bleed into their surroundings
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct BloomSettings {
+    pub enabled: bool,
+    /// The luminance above which colors contribute to the bloom
+    pub threshold: f32,
+    /// The width of the luminance range below the threshold in which the contribution fades in
+    pub knee: f32,
+    /// The fraction of the bloom that is added to the image
+    pub intensity: f32,
+    /// The number of times the bright parts are downsampled by half, which determines the radius
+    /// of the bloom
+    pub mip_levels: u32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        BloomSettings {
+            enabled: true,
+            threshold: 1.0,
+            knee: 0.5,
+            intensity: 0.1,
+            mip_levels: 5,
+        }
+    }
+}
+
+/// Maps HDR colors to the displayable range with the ACES filmic curve
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TonemappingSettings {
+    pub enabled: bool,
+    /// Scales the colors before tonemapping
+    pub exposure: f32,
+}
+
+impl Default for TonemappingSettings {
+    fn default() -> Self {
+        TonemappingSettings {
+            enabled: true,
+            exposure: 1.0,
+        }
+    }
+}
+
+/// Encodes the colors for a display with the given gamma
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct GammaSettings {
+    pub enabled: bool,
+    /// Values above the standard gamma of 2.2 brighten the image, values below darken it
+    pub gamma: f32,
+}
+
+impl Default for GammaSettings {
+    fn default() -> Self {
+        GammaSettings {
+            enabled: true,
+            gamma: 2.2,
+        }
+    }
+}
+
+/// Fast approximate anti-aliasing, which smooths the edges found in the final image
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct FxaaSettings {
+    pub enabled: bool,
+    /// The minimum contrast in luma, relative to the brightest neighbour, that counts as an edge
+    pub edge_threshold: f32,
+    /// How much of the contrast of a pixel to its neighbours is smoothed away, between 0 and 1
+    pub subpixel_blending: f32,
+}
+
+impl Default for FxaaSettings {
+    fn default() -> Self {
+        FxaaSettings {
+            enabled: true,
+            edge_threshold: 0.125,
+            subpixel_blending: 0.75,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_effects_do_not_alter_the_image() {
+        let mut settings: PostProcessSettings = toml::from_str(
+            r#"
+            [bloom]
+            enabled = false
+            intensity = 0.5
+
+            [tonemapping]
+            exposure = 2.0
+            "#,
+        )
+        .unwrap();
+        assert!(settings.enabled);
+        assert_eq!(settings.bloom.threshold, BloomSettings::default().threshold);
+
+        let uniform = settings.uniform(true);
+        assert_eq!(uniform.bloom_intensity, 0.0);
+        assert_eq!(uniform.exposure, 2.0);
+        assert_eq!(uniform.gamma_exponent, 1.0);
+        assert_eq!(settings.uniform(false).gamma_exponent, 1.0 / 2.2);
+
+        settings.tonemapping.enabled = false;
+        settings.gamma.enabled = false;
+        let uniform = settings.uniform(false);
+        assert_eq!(uniform.exposure, 1.0);
+        assert_eq!(uniform.tonemapping, 0);
+        assert_eq!(uniform.gamma_exponent, 1.0);
+    }
+}
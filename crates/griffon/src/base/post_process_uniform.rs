@@ -0,0 +1,17 @@
+/// The parameters of the post-processing passes, see
+/// [`PostProcessSettings::uniform`](crate::base::post_process_settings::PostProcessSettings::uniform)
+#[derive(Debug, Default, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct PostProcessUniform {
+    pub bloom_threshold: f32,
+    pub bloom_knee: f32,
+    /// Zero if bloom is disabled
+    pub bloom_intensity: f32,
+    pub exposure: f32,
+    /// Non-zero if the ACES curve is applied
+    pub tonemapping: u32,
+    /// The exponent the final colors are raised to
+    pub gamma_exponent: f32,
+    pub fxaa_edge_threshold: f32,
+    pub fxaa_subpixel_blending: f32,
+}
@@ -0,0 +1,588 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::base::encoder::{ColorAttachment, DepthAttachment, Encoder, RenderPass};
+use crate::base::ids::{BufferId, TextureViewId};
+
+/// Refers to a version of a texture in a [`RenderGraph`]. Every pass that writes the texture
+/// produces a new version, and passes that read a version run after the pass that produced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureHandle {
+    resource: usize,
+    version: u32,
+}
+
+/// Refers to a version of a buffer in a [`RenderGraph`], see [`TextureHandle`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BufferHandle {
+    resource: usize,
+    version: u32,
+}
+
+/// Describes a texture that only lives for the duration of a frame. Transient textures with the
+/// same description share memory if their lifetimes do not overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransientTexture {
+    pub width: u32,
+    pub height: u32,
+    /// Depth formats create depth textures, all others render targets
+    pub format: wgpu::TextureFormat,
+}
+
+/// Sums up the execution of a [`RenderGraph`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderGraphStats {
+    pub passes: usize,
+    /// Passes whose results were not used by any other pass or imported resource
+    pub culled_passes: usize,
+    pub draw_calls: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderGraphError {
+    #[error("Pass '{pass}' writes an outdated version of resource '{resource}', which another pass already wrote")]
+    OutdatedWrite { pass: String, resource: String },
+    #[error("Pass '{pass}' samples the surface, which can only be rendered into")]
+    SurfaceRead { pass: String },
+    #[error("The passes {passes:?} depend on each other")]
+    Cycle { passes: Vec<String> },
+}
+
+/// Executes the commands of a pass, after its attachments have been bound
+type PassFn<'a> = Box<dyn FnOnce(&mut RenderPass<'_>) + 'a>;
+
+#[derive(Debug)]
+enum Resource {
+    Texture(TextureSource),
+    Buffer(BufferId),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TextureSource {
+    Imported(TextureViewId),
+    Surface,
+    Transient(TransientTexture),
+}
+
+#[derive(Debug)]
+struct ResourceNode {
+    name: String,
+    resource: Resource,
+    /// The most recent version, incremented by every write
+    version: u32,
+}
+
+impl ResourceNode {
+    /// Imported resources are visible outside of the graph, hence passes that write them are
+    /// never culled
+    fn is_imported(&self) -> bool {
+        !matches!(self.resource, Resource::Texture(TextureSource::Transient(_)))
+    }
+}
+
+struct PassNode<'a> {
+    name: String,
+    /// The resources and versions the pass reads
+    reads: Vec<(usize, u32)>,
+    /// The resources and versions the pass produces
+    writes: Vec<(usize, u32)>,
+    color_attachments: Vec<(usize, Option<wgpu::Color>)>,
+    depth_attachment: Option<(usize, Option<f32>)>,
+    execute: Option<PassFn<'a>>,
+}
+
+impl std::fmt::Debug for PassNode<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PassNode")
+            .field("name", &self.name)
+            .field("reads", &self.reads)
+            .field("writes", &self.writes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Composes a frame from render passes that declare the textures and buffers they read and
+/// write. [`RenderGraph::compile`] culls the passes whose results are never used, orders the
+/// rest by their dependencies and assigns memory to transient textures. Barriers between passes
+/// are inserted by wgpu, based on the order of the passes and the usage of their resources.
+#[derive(Debug, Default)]
+pub struct RenderGraph<'a> {
+    resources: Vec<ResourceNode>,
+    passes: Vec<PassNode<'a>>,
+    errors: Vec<RenderGraphError>,
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes a texture view that outlives the frame available to the passes
+    pub fn import_texture<S: Into<String>>(&mut self, name: S, view: TextureViewId) -> TextureHandle {
+        let resource = self.add_resource(name.into(), Resource::Texture(TextureSource::Imported(view)));
+        TextureHandle { resource, version: 0 }
+    }
+
+    /// Makes the surface texture of the current frame available to the passes
+    pub fn import_surface(&mut self) -> TextureHandle {
+        let resource = self.add_resource(String::from("surface"), Resource::Texture(TextureSource::Surface));
+        TextureHandle { resource, version: 0 }
+    }
+
+    pub fn import_buffer<S: Into<String>>(&mut self, name: S, buffer: BufferId) -> BufferHandle {
+        let resource = self.add_resource(name.into(), Resource::Buffer(buffer));
+        BufferHandle { resource, version: 0 }
+    }
+
+    /// Returns the buffer a handle of any version refers to
+    pub fn buffer(&self, handle: BufferHandle) -> BufferId {
+        match self.resources[handle.resource].resource {
+            Resource::Buffer(buffer) => buffer,
+            Resource::Texture(_) => unreachable!("buffer handles only refer to buffers"),
+        }
+    }
+
+    /// Declares a texture that is allocated by the graph for the duration of the frame. Its
+    /// contents are undefined until a pass writes it.
+    pub fn create_texture<S: Into<String>>(&mut self, name: S, texture: TransientTexture) -> TextureHandle {
+        let resource = self.add_resource(name.into(), Resource::Texture(TextureSource::Transient(texture)));
+        TextureHandle { resource, version: 0 }
+    }
+
+    /// Starts declaring a render pass, which is added to the graph by [`PassBuilder::submit`]
+    #[must_use]
+    pub fn add_pass<S: Into<String>>(&mut self, name: S) -> PassBuilder<'_, 'a> {
+        PassBuilder {
+            graph: self,
+            pass: PassNode {
+                name: name.into(),
+                reads: Vec::new(),
+                writes: Vec::new(),
+                color_attachments: Vec::new(),
+                depth_attachment: None,
+                execute: None,
+            },
+        }
+    }
+
+    /// Culls and orders the passes and assigns the transient textures to physical textures
+    pub fn compile(mut self) -> Result<CompiledRenderGraph<'a>, RenderGraphError> {
+        if !self.errors.is_empty() {
+            return Err(self.errors.swap_remove(0));
+        }
+
+        let num_passes = self.passes.len();
+        // The pass that produced each version of each resource, where version 0 has no producer
+        let mut producers: Vec<Vec<Option<usize>>> = self
+            .resources
+            .iter()
+            .map(|r| vec![None; r.version as usize + 1])
+            .collect();
+        for (p, pass) in self.passes.iter().enumerate() {
+            for &(resource, version) in &pass.writes {
+                producers[resource][version as usize] = Some(p);
+            }
+        }
+
+        // A pass depends on the producers of the versions it reads and of the versions preceding
+        // those it writes. The producer of a version also waits for all readers of the previous one.
+        let mut dependencies: Vec<Vec<usize>> = vec![Vec::new(); num_passes];
+        for (p, pass) in self.passes.iter().enumerate() {
+            let previous_versions = pass.writes.iter().map(|&(r, v)| (r, v - 1));
+            for (resource, version) in pass.reads.iter().copied().chain(previous_versions) {
+                if let Some(producer) = producers[resource][version as usize] {
+                    dependencies[p].push(producer);
+                }
+            }
+            for &(resource, version) in &pass.reads {
+                if let Some(next_producer) = producers[resource].get(version as usize + 1).copied().flatten()
+                    && next_producer != p
+                {
+                    dependencies[next_producer].push(p);
+                }
+            }
+        }
+
+        // Passes that write imported resources and everything they depend on are kept
+        let mut alive = vec![false; num_passes];
+        let mut stack: Vec<usize> = (0..num_passes)
+            .filter(|&p| {
+                self.passes[p]
+                    .writes
+                    .iter()
+                    .any(|&(r, _)| self.resources[r].is_imported())
+            })
+            .collect();
+        while let Some(p) = stack.pop() {
+            if !std::mem::replace(&mut alive[p], true) {
+                stack.extend(dependencies[p].iter().copied().filter(|&d| !alive[d]));
+            }
+        }
+
+        // Topological sort that keeps the order of declaration where there are no dependencies
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); num_passes];
+        let mut pending = vec![0usize; num_passes];
+        for p in (0..num_passes).filter(|&p| alive[p]) {
+            let mut deps = dependencies[p].clone();
+            deps.sort_unstable();
+            deps.dedup();
+            for d in deps {
+                dependents[d].push(p);
+                pending[p] += 1;
+            }
+        }
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..num_passes)
+            .filter(|&p| alive[p] && pending[p] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = Vec::new();
+        while let Some(Reverse(p)) = ready.pop() {
+            order.push(p);
+            for &d in &dependents[p] {
+                pending[d] -= 1;
+                if pending[d] == 0 {
+                    ready.push(Reverse(d));
+                }
+            }
+        }
+
+        let num_alive = alive.iter().filter(|&&a| a).count();
+        if order.len() < num_alive {
+            let passes = (0..num_passes)
+                .filter(|&p| alive[p] && pending[p] > 0)
+                .map(|p| self.passes[p].name.clone())
+                .collect();
+            return Err(RenderGraphError::Cycle { passes });
+        }
+
+        // Transient textures whose lifetimes do not overlap share a slot
+        let mut lifetimes: Vec<Option<(usize, usize)>> = vec![None; self.resources.len()];
+        for (position, &p) in order.iter().enumerate() {
+            let pass = &self.passes[p];
+            for &(resource, _) in pass.reads.iter().chain(&pass.writes) {
+                let lifetime = lifetimes[resource].get_or_insert((position, position));
+                lifetime.1 = position;
+            }
+        }
+        let mut transient_textures: Vec<TransientTexture> = Vec::new();
+        let mut slot_ends: Vec<usize> = Vec::new();
+        let mut slots: Vec<Option<usize>> = vec![None; self.resources.len()];
+        let mut by_first_use: Vec<usize> = (0..self.resources.len()).filter(|&r| lifetimes[r].is_some()).collect();
+        by_first_use.sort_by_key(|&r| lifetimes[r].map(|l| l.0));
+        for r in by_first_use {
+            let Resource::Texture(TextureSource::Transient(texture)) = self.resources[r].resource else {
+                continue;
+            };
+            let Some((first, last)) = lifetimes[r] else {
+                continue;
+            };
+            let slot = (0..transient_textures.len())
+                .find(|&s| transient_textures[s] == texture && slot_ends[s] < first)
+                .unwrap_or_else(|| {
+                    transient_textures.push(texture);
+                    slot_ends.push(0);
+                    transient_textures.len() - 1
+                });
+            slot_ends[slot] = last;
+            slots[r] = Some(slot);
+        }
+
+        let mut passes: Vec<Option<PassNode<'a>>> = self.passes.into_iter().map(Some).collect();
+        Ok(CompiledRenderGraph {
+            passes: order.iter().filter_map(|&p| passes[p].take()).collect(),
+            culled_passes: num_passes - num_alive,
+            resources: self.resources,
+            slots,
+            transient_textures,
+        })
+    }
+
+    fn add_resource(&mut self, name: String, resource: Resource) -> usize {
+        self.resources.push(ResourceNode {
+            name,
+            resource,
+            version: 0,
+        });
+        self.resources.len() - 1
+    }
+
+    /// Creates the next version of a resource, which must be written from its latest version
+    fn next_version(&mut self, pass: &str, resource: usize, version: u32) -> u32 {
+        let node = &mut self.resources[resource];
+        if node.version != version {
+            self.errors.push(RenderGraphError::OutdatedWrite {
+                pass: pass.to_string(),
+                resource: node.name.clone(),
+            });
+        }
+        node.version += 1;
+        node.version
+    }
+}
+
+/// Declares the resources of a single pass of a [`RenderGraph`]
+#[derive(Debug)]
+pub struct PassBuilder<'g, 'a> {
+    graph: &'g mut RenderGraph<'a>,
+    pass: PassNode<'a>,
+}
+
+impl<'g, 'a> PassBuilder<'g, 'a> {
+    /// Declares that the pass samples the texture
+    pub fn read_texture(&mut self, texture: TextureHandle) -> &mut Self {
+        if matches!(
+            self.graph.resources[texture.resource].resource,
+            Resource::Texture(TextureSource::Surface)
+        ) {
+            self.graph.errors.push(RenderGraphError::SurfaceRead {
+                pass: self.pass.name.clone(),
+            });
+        }
+        self.pass.reads.push((texture.resource, texture.version));
+        self
+    }
+
+    pub fn read_buffer(&mut self, buffer: BufferHandle) -> &mut Self {
+        self.pass.reads.push((buffer.resource, buffer.version));
+        self
+    }
+
+    /// Declares that the pass writes the buffer, e.g. as a storage buffer, and returns the new
+    /// version
+    pub fn write_buffer(&mut self, buffer: BufferHandle) -> BufferHandle {
+        let version = self.write(buffer.resource, buffer.version);
+        BufferHandle {
+            resource: buffer.resource,
+            version,
+        }
+    }
+
+    /// Renders into the texture, after clearing it to `clear` if set, and returns the new version
+    pub fn add_color_attachment(&mut self, texture: TextureHandle, clear: Option<wgpu::Color>) -> TextureHandle {
+        self.pass.color_attachments.push((texture.resource, clear));
+        let version = self.write(texture.resource, texture.version);
+        TextureHandle {
+            resource: texture.resource,
+            version,
+        }
+    }
+
+    /// Uses the texture for depth testing, after clearing it to `clear` if set, and returns the
+    /// new version
+    pub fn with_depth_attachment(&mut self, texture: TextureHandle, clear: Option<f32>) -> TextureHandle {
+        self.pass.depth_attachment = Some((texture.resource, clear));
+        let version = self.write(texture.resource, texture.version);
+        TextureHandle {
+            resource: texture.resource,
+            version,
+        }
+    }
+
+    /// Adds the pass to the graph, with the commands it records once its attachments are bound
+    pub fn submit<F>(mut self, execute: F)
+    where
+        F: FnOnce(&mut RenderPass<'_>) + 'a,
+    {
+        self.pass.execute = Some(Box::new(execute));
+        self.graph.passes.push(self.pass);
+    }
+
+    fn write(&mut self, resource: usize, version: u32) -> u32 {
+        let version = self.graph.next_version(&self.pass.name, resource, version);
+        self.pass.writes.push((resource, version));
+        version
+    }
+}
+
+/// A [`RenderGraph`] whose passes are ordered and whose transient textures are assigned to slots
+#[derive(Debug)]
+pub struct CompiledRenderGraph<'a> {
+    passes: Vec<PassNode<'a>>,
+    culled_passes: usize,
+    resources: Vec<ResourceNode>,
+    /// The slot of each transient texture, `None` for other resources
+    slots: Vec<Option<usize>>,
+    transient_textures: Vec<TransientTexture>,
+}
+
+impl<'a> CompiledRenderGraph<'a> {
+    /// Returns the names of the passes in the order of execution
+    pub fn pass_names(&self) -> impl Iterator<Item = &str> {
+        self.passes.iter().map(|p| p.name.as_str())
+    }
+
+    /// Returns the textures that must be allocated, one per slot, see
+    /// [`Graphics::acquire_transient_textures`](crate::Graphics::acquire_transient_textures)
+    pub fn transient_textures(&self) -> &[TransientTexture] {
+        &self.transient_textures
+    }
+
+    /// Records all passes into the encoder, with `transient_views` holding one texture view for
+    /// each of the [`CompiledRenderGraph::transient_textures`]
+    #[tracing::instrument(skip_all)]
+    pub fn execute(mut self, encoder: &mut Encoder, transient_views: &[TextureViewId]) -> RenderGraphStats {
+        assert_eq!(
+            transient_views.len(),
+            self.transient_textures.len(),
+            "Developer error: expected one texture view per transient texture"
+        );
+        let view = |resource: usize| -> Option<TextureViewId> {
+            match self.resources[resource].resource {
+                Resource::Texture(TextureSource::Imported(view)) => Some(view),
+                Resource::Texture(TextureSource::Surface) => None,
+                Resource::Texture(TextureSource::Transient(_)) => self.slots[resource].map(|s| transient_views[s]),
+                Resource::Buffer(_) => panic!("Developer error: a buffer cannot be an attachment"),
+            }
+        };
+
+        let mut stats = RenderGraphStats {
+            passes: self.passes.len(),
+            culled_passes: self.culled_passes,
+            draw_calls: 0,
+        };
+        for pass in self.passes.iter_mut() {
+            let color_attachments: Vec<_> = pass
+                .color_attachments
+                .iter()
+                .map(|&(resource, clear)| ColorAttachment {
+                    view: view(resource),
+                    clear,
+                })
+                .collect();
+            let depth_attachment = pass.depth_attachment.map(|(resource, clear)| DepthAttachment {
+                view: view(resource).expect("Developer error: the surface cannot be a depth attachment"),
+                clear,
+            });
+
+            let mut rp = encoder.begin_pass(Some(&pass.name), &color_attachments, depth_attachment);
+            if let Some(execute) = pass.execute.take() {
+                execute(&mut rp);
+            }
+            stats.draw_calls += rp.draw_calls();
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HDR: TransientTexture = TransientTexture {
+        width: 64,
+        height: 64,
+        format: wgpu::TextureFormat::Rgba16Float,
+    };
+
+    fn names(compiled: &CompiledRenderGraph) -> Vec<String> {
+        compiled.pass_names().map(String::from).collect()
+    }
+
+    #[test]
+    fn passes_are_ordered_by_their_dependencies() {
+        let mut graph = RenderGraph::new();
+        let surface = graph.import_surface();
+        let atlas = graph.import_texture("shadow-atlas", TextureViewId::from(0));
+
+        // The main pass reads the shadow atlas before the shadow pass writes it, hence the shadow
+        // pass must run afterwards
+        let mut main = graph.add_pass("main");
+        main.read_texture(atlas);
+        main.add_color_attachment(surface, None);
+        main.submit(|_| ());
+
+        let mut shadow = graph.add_pass("shadow");
+        shadow.with_depth_attachment(atlas, Some(1.0));
+        shadow.submit(|_| ());
+
+        assert_eq!(names(&graph.compile().unwrap()), ["main", "shadow"]);
+    }
+
+    #[test]
+    fn unused_passes_are_culled() {
+        let mut graph = RenderGraph::new();
+        let surface = graph.import_surface();
+        let unused = graph.create_texture("unused", HDR);
+
+        let mut debug = graph.add_pass("debug");
+        debug.add_color_attachment(unused, None);
+        debug.submit(|_| ());
+
+        let mut main = graph.add_pass("main");
+        main.add_color_attachment(surface, None);
+        main.submit(|_| ());
+
+        let compiled = graph.compile().unwrap();
+        assert_eq!(names(&compiled), ["main"]);
+        assert_eq!(compiled.culled_passes, 1);
+        assert!(compiled.transient_textures().is_empty());
+    }
+
+    #[test]
+    fn transient_textures_with_disjoint_lifetimes_share_a_slot() {
+        let mut graph = RenderGraph::new();
+        let surface = graph.import_surface();
+        let a = graph.create_texture("a", HDR);
+        let b = graph.create_texture("b", HDR);
+        let c = graph.create_texture("c", HDR);
+
+        let mut pass = graph.add_pass("write-a");
+        let a = pass.add_color_attachment(a, None);
+        pass.submit(|_| ());
+        let mut pass = graph.add_pass("a-to-b");
+        pass.read_texture(a);
+        let b = pass.add_color_attachment(b, None);
+        pass.submit(|_| ());
+        let mut pass = graph.add_pass("b-to-c");
+        pass.read_texture(b);
+        let c = pass.add_color_attachment(c, None);
+        pass.submit(|_| ());
+        let mut pass = graph.add_pass("c-to-surface");
+        pass.read_texture(c);
+        pass.add_color_attachment(surface, None);
+        pass.submit(|_| ());
+
+        let compiled = graph.compile().unwrap();
+        // a and b overlap in the second pass, b and c in the third, but a and c do not
+        assert_eq!(compiled.transient_textures(), [HDR, HDR]);
+        assert_eq!(compiled.slots[a.resource], compiled.slots[c.resource]);
+        assert_ne!(compiled.slots[a.resource], compiled.slots[b.resource]);
+    }
+
+    #[test]
+    fn invalid_graphs_are_rejected() {
+        let mut graph = RenderGraph::new();
+        let surface = graph.import_surface();
+
+        let mut first = graph.add_pass("first");
+        first.add_color_attachment(surface, None);
+        first.submit(|_| ());
+        let mut second = graph.add_pass("second");
+        second.add_color_attachment(surface, None);
+        second.submit(|_| ());
+
+        assert!(matches!(
+            graph.compile(),
+            Err(RenderGraphError::OutdatedWrite { pass, .. }) if pass == "second"
+        ));
+
+        let mut graph = RenderGraph::new();
+        let x = graph.import_texture("x", TextureViewId::from(0));
+        let z = graph.import_texture("z", TextureViewId::from(1));
+        let w = graph.import_texture("w", TextureViewId::from(2));
+
+        // The second pass reads the version of x that the first one overwrites, but also the
+        // version of z that the first one produces
+        let mut first = graph.add_pass("first");
+        first.add_color_attachment(x, None);
+        let z = first.add_color_attachment(z, None);
+        first.submit(|_| ());
+        let mut second = graph.add_pass("second");
+        second.read_texture(x).read_texture(z);
+        second.add_color_attachment(w, None);
+        second.submit(|_| ());
+
+        assert!(matches!(graph.compile(), Err(RenderGraphError::Cycle { passes }) if passes.len() == 2));
+    }
+}
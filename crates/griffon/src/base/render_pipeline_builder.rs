@@ -1,3 +1,4 @@
+use crate::base::blend_mode::BlendMode;
 use crate::base::descriptors::VertexAttributeDescriptor;
 use crate::base::gpu_object_database::GpuObjectDatabase;
 use crate::base::ids::{BindGroupLayoutId, PipelineId, ShaderModuleId};
@@ -12,7 +13,22 @@ pub struct RenderPipelineBuilder<'rt, 'ep, 'vbl> {
     fragment_shader_module: Option<(ShaderModuleId, &'ep str)>,
     bind_group_layouts: Vec<BindGroupLayoutId>,
     vertex_buffer_layouts: Vec<wgpu::VertexBufferLayout<'vbl>>,
+    color_targets: Vec<Option<wgpu::ColorTargetState>>,
+    color_output: bool,
+    topology: wgpu::PrimitiveTopology,
+    front_face: wgpu::FrontFace,
+    cull_mode: Option<wgpu::Face>,
+    polygon_mode: wgpu::PolygonMode,
+    depth_test: bool,
     depth_texture_format: wgpu::TextureFormat,
+    depth_write: Option<bool>,
+    depth_compare: wgpu::CompareFunction,
+    depth_bias: wgpu::DepthBiasState,
+    stencil: wgpu::StencilState,
+    blend_mode: BlendMode,
+    blend_state: Option<wgpu::BlendState>,
+    sample_count: u32,
+    alpha_to_coverage: bool,
     label: Option<&'static str>,
 }
 
@@ -25,7 +41,22 @@ impl<'rt, 'ep, 'vbl> RenderPipelineBuilder<'rt, 'ep, 'vbl> {
             fragment_shader_module: None,
             bind_group_layouts: Vec::new(),
             vertex_buffer_layouts: Vec::new(),
+            color_targets: Vec::new(),
+            color_output: true,
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            depth_test: true,
             depth_texture_format: settings.depth_texture_format,
+            depth_write: None,
+            depth_compare: wgpu::CompareFunction::Less,
+            depth_bias: wgpu::DepthBiasState::default(),
+            stencil: wgpu::StencilState::default(),
+            blend_mode: BlendMode::Opaque,
+            blend_state: None,
+            sample_count: 1,
+            alpha_to_coverage: false,
             label: None,
         }
     }
@@ -45,6 +76,115 @@ impl<'rt, 'ep, 'vbl> RenderPipelineBuilder<'rt, 'ep, 'vbl> {
         self
     }
 
+    /// Sets how vertices are assembled into primitives, such as lines for debug gizmos. Strip
+    /// topologies use 32-bit indices.
+    pub fn with_topology(mut self, topology: wgpu::PrimitiveTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_front_face(mut self, front_face: wgpu::FrontFace) -> Self {
+        self.front_face = front_face;
+        self
+    }
+
+    /// Sets which faces are culled, or `None` to draw both sides
+    pub fn with_cull_mode(mut self, cull_mode: Option<wgpu::Face>) -> Self {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    /// Sets how polygons are rasterized. Anything other than [`wgpu::PolygonMode::Fill`] requires
+    /// a device feature, see [`Graphics::supports_features`](crate::Graphics::supports_features).
+    pub fn with_polygon_mode(mut self, polygon_mode: wgpu::PolygonMode) -> Self {
+        self.polygon_mode = polygon_mode;
+        self
+    }
+
+    /// Creates a pipeline that renders without a depth-stencil attachment
+    pub fn without_depth_stencil(mut self) -> Self {
+        self.depth_test = false;
+        self
+    }
+
+    /// Creates a pipeline whose fragment stage writes no color, for example to discard fragments
+    /// of depth-only passes
+    pub fn without_color_targets(mut self) -> Self {
+        self.color_output = false;
+        self
+    }
+
+    /// Sets the format of the depth-stencil attachment, which defaults to
+    /// [`Settings::depth_texture_format`]
+    pub fn with_depth_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.depth_texture_format = format;
+        self
+    }
+
+    /// Enables or disables depth writes, which otherwise depend on the blend mode
+    pub fn with_depth_write(mut self, enabled: bool) -> Self {
+        self.depth_write = Some(enabled);
+        self
+    }
+
+    pub fn with_depth_compare(mut self, compare: wgpu::CompareFunction) -> Self {
+        self.depth_compare = compare;
+        self
+    }
+
+    /// Sets the stencil test and operations, for example to draw outlines
+    pub fn with_stencil(mut self, stencil: wgpu::StencilState) -> Self {
+        self.stencil = stencil;
+        self
+    }
+
+    /// Offsets the depth of rasterized fragments, typically to avoid shadow acne in shadow maps
+    pub fn with_depth_bias(mut self, constant: i32, slope_scale: f32) -> Self {
+        self.depth_bias = wgpu::DepthBiasState {
+            constant,
+            slope_scale,
+            clamp: 0.0,
+        };
+        self
+    }
+
+    /// Sets how fragments are blended with the color target. Transparent blend modes test against
+    /// the depth buffer, but do not write to it.
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    /// Sets the blend state of the default color target, which takes precedence over the blend
+    /// state of the blend mode
+    pub fn with_blend_state(mut self, blend_state: wgpu::BlendState) -> Self {
+        self.blend_state = Some(blend_state);
+        self
+    }
+
+    /// Sets the number of samples per pixel for multisample anti-aliasing. All attachments of the
+    /// render pass must have the same sample count.
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    pub fn with_alpha_to_coverage(mut self, enabled: bool) -> Self {
+        self.alpha_to_coverage = enabled;
+        self
+    }
+
+    /// Adds a color target in place of the default one, which uses the surface format. Add
+    /// several to render into multiple targets at once.
+    pub fn add_color_target(mut self, format: wgpu::TextureFormat, blend: Option<wgpu::BlendState>) -> Self {
+        self.color_targets.push(Some(wgpu::ColorTargetState {
+            format,
+            blend,
+            write_mask: wgpu::ColorWrites::ALL,
+        }));
+        self
+    }
+
     pub fn add_bind_group_layout(mut self, bgl: BindGroupLayoutId) -> Self {
         self.bind_group_layouts.push(bgl);
         self
@@ -62,11 +202,17 @@ impl<'rt, 'ep, 'vbl> RenderPipelineBuilder<'rt, 'ep, 'vbl> {
 
     pub fn submit(self) -> PipelineId {
         // Helper variables
-        let cts = [Some(wgpu::ColorTargetState {
-            format: self.runtime.config.format,
-            blend: Some(wgpu::BlendState::REPLACE),
-            write_mask: wgpu::ColorWrites::ALL,
-        })];
+        let cts = if !self.color_output {
+            Vec::new()
+        } else if self.color_targets.is_empty() {
+            vec![Some(wgpu::ColorTargetState {
+                format: self.runtime.config.format,
+                blend: Some(self.blend_state.unwrap_or_else(|| self.blend_mode.blend_state())),
+                write_mask: wgpu::ColorWrites::ALL,
+            })]
+        } else {
+            self.color_targets
+        };
         let bgl = self
             .bind_group_layouts
             .into_iter()
@@ -125,28 +271,28 @@ impl<'rt, 'ep, 'vbl> RenderPipelineBuilder<'rt, 'ep, 'vbl> {
                     compilation_options: Default::default(),
                 }),
                 primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                    polygon_mode: wgpu::PolygonMode::Fill,
+                    topology: self.topology,
+                    strip_index_format: self.topology.is_strip().then_some(wgpu::IndexFormat::Uint32),
+                    front_face: self.front_face,
+                    cull_mode: self.cull_mode,
+                    // Line requires Features::POLYGON_MODE_LINE, Point requires Features::POLYGON_MODE_POINT
+                    polygon_mode: self.polygon_mode,
                     // Requires Features::DEPTH_CLIP_CONTROL
                     unclipped_depth: false,
                     // Requires Features::CONSERVATIVE_RASTERIZATION
                     conservative: false,
                 },
-                depth_stencil: Some(wgpu::DepthStencilState {
+                depth_stencil: self.depth_test.then(|| wgpu::DepthStencilState {
                     format: self.depth_texture_format,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Less,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
+                    depth_write_enabled: self.depth_write.unwrap_or(!self.blend_mode.is_transparent()),
+                    depth_compare: self.depth_compare,
+                    stencil: self.stencil,
+                    bias: self.depth_bias,
                 }),
                 multisample: wgpu::MultisampleState {
-                    count: 1,
+                    count: self.sample_count,
                     mask: !0,
-                    alpha_to_coverage_enabled: false,
+                    alpha_to_coverage_enabled: self.alpha_to_coverage,
                 },
                 multiview: None,
                 cache: None,
@@ -1,13 +1,27 @@
 use crate::base::settings::Settings;
+use crate::base::window_settings::WindowMode;
 use anyhow::{Context, anyhow};
 use wgpu::{DeviceDescriptor, RequestAdapterOptions, TextureUsages};
-use winit::{event_loop::EventLoopWindowTarget, window::Fullscreen};
+use winit::event_loop::EventLoopWindowTarget;
+use winit::monitor::MonitorHandle;
+use winit::window::{Window, WindowBuilder};
+
+/// The destination of rendered frames
+#[derive(Debug)]
+pub enum RenderTarget<'a> {
+    /// Frames are presented to the surface of an operating system window
+    Window {
+        window: std::sync::Arc<winit::window::Window>,
+        surface: wgpu::Surface<'a>,
+    },
+    /// Frames are rendered into a texture that can be copied back to the CPU
+    Offscreen { texture: wgpu::Texture },
+}
 
 #[derive(Debug)]
 pub struct Runtime<'a> {
-    pub window: std::sync::Arc<winit::window::Window>,
+    pub target: RenderTarget<'a>,
     pub instance: wgpu::Instance,
-    pub surface: wgpu::Surface<'a>,
     pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
@@ -18,13 +32,7 @@ pub struct Runtime<'a> {
 
 impl<'a> Runtime<'a> {
     pub async fn new<T>(event_loop: &EventLoopWindowTarget<T>, settings: &Settings) -> anyhow::Result<Runtime<'a>> {
-        let primary_monitor = event_loop.primary_monitor();
-        let window = std::sync::Arc::new(
-            winit::window::WindowBuilder::new()
-                .with_fullscreen(Some(Fullscreen::Borderless(primary_monitor)))
-                .build(event_loop)
-                .context("Creating a window")?,
-        );
+        let window = std::sync::Arc::new(Self::create_window(event_loop, settings)?);
 
         let size = window.inner_size();
         tracing::debug!("Physical window size: {:?}", &size);
@@ -34,31 +42,17 @@ impl<'a> Runtime<'a> {
             .ok_or_else(|| anyhow!("No monitor assigned to the current window"))?
             .size();
 
-        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: settings.backends,
-            ..Default::default()
-        });
+        let instance = Self::create_instance(settings);
         let surface = instance.create_surface(window.clone())?;
 
         let adapter = instance
             .request_adapter(&RequestAdapterOptions {
                 power_preference: settings.power_preference,
                 compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await?;
-        tracing::debug!("Supported adapter features: {:?}", adapter.features());
-
-        let (device, queue) = adapter
-            .request_device(&DeviceDescriptor {
-                required_features: settings.required_features,
-                required_limits: settings.required_limits.clone(),
-                label: None,
-                memory_hints: Default::default(),
-                trace: Default::default(),
-                experimental_features: Default::default(),
+                force_fallback_adapter: settings.force_fallback_adapter,
             })
             .await?;
+        let (device, queue) = Self::request_device(&adapter, settings).await?;
 
         let capabilities = surface.get_capabilities(&adapter);
         tracing::debug!("Supported texture formats: {:?}", &capabilities.formats);
@@ -85,9 +79,8 @@ impl<'a> Runtime<'a> {
         surface.configure(&device, &config);
 
         Ok(Runtime {
-            window,
+            target: RenderTarget::Window { window, surface },
             instance,
-            surface,
             adapter,
             device,
             queue,
@@ -96,4 +89,151 @@ impl<'a> Runtime<'a> {
             max_size,
         })
     }
+
+    /// Creates a runtime without a window that renders into an offscreen texture of the given size.
+    /// Any adapter will do, including software rasterizers like lavapipe or llvmpipe.
+    pub async fn new_headless(settings: &Settings, size: winit::dpi::PhysicalSize<u32>) -> anyhow::Result<Runtime<'a>> {
+        tracing::debug!("Physical offscreen size: {:?}", &size);
+
+        let instance = Self::create_instance(settings);
+        let adapter = instance
+            .request_adapter(&RequestAdapterOptions {
+                power_preference: settings.power_preference,
+                compatible_surface: None,
+                force_fallback_adapter: settings.force_fallback_adapter,
+            })
+            .await?;
+        let (device, queue) = Self::request_device(&adapter, settings).await?;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            format: settings.preferred_texture_format,
+            width: size.width,
+            height: size.height,
+            present_mode: settings.present_mode,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: settings.alpha_mode,
+            view_formats: vec![settings.preferred_texture_format],
+        };
+        let texture = Self::create_offscreen_texture(&device, &config);
+
+        Ok(Runtime {
+            target: RenderTarget::Offscreen { texture },
+            instance,
+            adapter,
+            device,
+            queue,
+            config,
+            size,
+            max_size: size,
+        })
+    }
+
+    /// Returns the window if the runtime presents to one
+    pub fn window(&self) -> Option<&winit::window::Window> {
+        match &self.target {
+            RenderTarget::Window { window, .. } => Some(window),
+            RenderTarget::Offscreen { .. } => None,
+        }
+    }
+
+    /// Applies the current configuration to the surface or recreates the offscreen texture
+    pub fn configure(&mut self) {
+        match &mut self.target {
+            RenderTarget::Window { surface, .. } => surface.configure(&self.device, &self.config),
+            RenderTarget::Offscreen { texture } => {
+                *texture = Self::create_offscreen_texture(&self.device, &self.config)
+            }
+        }
+    }
+
+    /// Switches the window between windowed and fullscreen modes on its current monitor
+    pub fn set_window_mode(&self, mode: WindowMode) {
+        if let Some(window) = self.window() {
+            let size = (mode == WindowMode::Exclusive).then_some(self.size);
+            window.set_fullscreen(mode.fullscreen(window.current_monitor(), size));
+        }
+    }
+
+    fn create_window<T>(event_loop: &EventLoopWindowTarget<T>, settings: &Settings) -> anyhow::Result<Window> {
+        let ws = &settings.window;
+        let monitor = Self::select_monitor(event_loop, ws.monitor);
+
+        let mut builder = WindowBuilder::new()
+            .with_resizable(ws.resizable)
+            .with_fullscreen(ws.mode.fullscreen(monitor.clone(), ws.inner_size));
+        if let Some(title) = &ws.title {
+            builder = builder.with_title(title);
+        }
+        if let Some(inner_size) = ws.inner_size {
+            builder = builder.with_inner_size(inner_size);
+        }
+        if let Some(position) = ws.position {
+            let origin = monitor.as_ref().map_or_else(Default::default, |m| m.position());
+            builder = builder.with_position(winit::dpi::PhysicalPosition::new(
+                origin.x + position.x,
+                origin.y + position.y,
+            ));
+        }
+
+        builder.build(event_loop).context("Creating a window")
+    }
+
+    fn select_monitor<T>(event_loop: &EventLoopWindowTarget<T>, index: Option<usize>) -> Option<MonitorHandle> {
+        let Some(index) = index else {
+            return event_loop.primary_monitor();
+        };
+
+        let monitor = event_loop.available_monitors().nth(index);
+        if monitor.is_none() {
+            tracing::warn!("Monitor {index} is not available, using the primary monitor");
+            return event_loop.primary_monitor();
+        }
+        monitor
+    }
+
+    fn create_instance(settings: &Settings) -> wgpu::Instance {
+        wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: settings.backends,
+            ..Default::default()
+        })
+    }
+
+    async fn request_device(
+        adapter: &wgpu::Adapter,
+        settings: &Settings,
+    ) -> anyhow::Result<(wgpu::Device, wgpu::Queue)> {
+        tracing::debug!("Chosen adapter: {:?}", adapter.get_info());
+        tracing::debug!("Supported adapter features: {:?}", adapter.features());
+
+        let device_and_queue = adapter
+            .request_device(&DeviceDescriptor {
+                required_features: settings.required_features | (settings.optional_features & adapter.features()),
+                required_limits: settings.required_limits.clone(),
+                label: None,
+                memory_hints: Default::default(),
+                trace: Default::default(),
+                experimental_features: Default::default(),
+            })
+            .await?;
+
+        Ok(device_and_queue)
+    }
+
+    fn create_offscreen_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen:texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &config.view_formats,
+        })
+    }
 }
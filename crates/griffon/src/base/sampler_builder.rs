@@ -2,10 +2,48 @@ use crate::base::gpu_object_database::GpuObjectDatabase;
 use crate::base::ids::SamplerId;
 use crate::base::runtime::Runtime;
 
+/// Address and filter modes of the sampler of a material
+///
+/// ```toml
+/// [sampler]
+/// address_mode_u = "repeat"
+/// address_mode_v = "repeat"
+/// mag_filter = "linear"
+/// anisotropy = 8
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SamplerSettings {
+    pub address_mode_u: wgpu::AddressMode,
+    pub address_mode_v: wgpu::AddressMode,
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    pub mipmap_filter: wgpu::FilterMode,
+    /// The maximum anisotropy between 1 and 16. Anisotropic filtering implies linear filtering and
+    /// overrides the filter modes.
+    pub anisotropy: u16,
+}
+
+impl Default for SamplerSettings {
+    /// Trilinear filtering without anisotropy
+    fn default() -> Self {
+        SamplerSettings {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            anisotropy: 1,
+        }
+    }
+}
+
 pub struct SamplerBuilder<'rt> {
     runtime: &'rt Runtime<'rt>,
     database: &'rt mut GpuObjectDatabase,
     label: Option<&'rt str>,
+    compare: Option<wgpu::CompareFunction>,
+    settings: Option<SamplerSettings>,
 }
 
 impl<'rt> SamplerBuilder<'rt> {
@@ -14,6 +52,8 @@ impl<'rt> SamplerBuilder<'rt> {
             runtime,
             database,
             label: None,
+            compare: None,
+            settings: None,
         }
     }
 
@@ -22,12 +62,55 @@ impl<'rt> SamplerBuilder<'rt> {
         self
     }
 
+    /// Create a comparison sampler for depth textures that filters the comparison results linearly
+    pub fn with_compare(mut self, compare: wgpu::CompareFunction) -> Self {
+        self.compare = Some(compare);
+        self
+    }
+
+    /// Create a sampler for material textures
+    pub fn with_settings(mut self, settings: &SamplerSettings) -> Self {
+        self.settings = Some(*settings);
+        self
+    }
+
     pub fn submit(self) -> SamplerId {
         tracing::trace!("Creating sampler '{}'", self.label.unwrap_or("unnamed"));
-        let sampler = self.runtime.device.create_sampler(&wgpu::SamplerDescriptor {
-            label: self.label,
-            ..Default::default()
-        });
+        let descriptor = if let Some(s) = self.settings {
+            let anisotropy_clamp = s.anisotropy.clamp(1, 16);
+            let filter = |f| {
+                if anisotropy_clamp > 1 {
+                    wgpu::FilterMode::Linear
+                } else {
+                    f
+                }
+            };
+            wgpu::SamplerDescriptor {
+                label: self.label,
+                address_mode_u: s.address_mode_u,
+                address_mode_v: s.address_mode_v,
+                mag_filter: filter(s.mag_filter),
+                min_filter: filter(s.min_filter),
+                mipmap_filter: filter(s.mipmap_filter),
+                anisotropy_clamp,
+                compare: self.compare,
+                ..Default::default()
+            }
+        } else {
+            let filter = if self.compare.is_some() {
+                wgpu::FilterMode::Linear
+            } else {
+                wgpu::FilterMode::Nearest
+            };
+            wgpu::SamplerDescriptor {
+                label: self.label,
+                mag_filter: filter,
+                min_filter: filter,
+                compare: self.compare,
+                ..Default::default()
+            }
+        };
+        let sampler = self.runtime.device.create_sampler(&descriptor);
         self.database.insert_sampler(sampler)
     }
 }
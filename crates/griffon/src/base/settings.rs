@@ -1,17 +1,37 @@
+use crate::base::post_process_settings::PostProcessSettings;
+use crate::base::window_settings::WindowSettings;
+
 #[derive(Debug, Clone)]
 pub struct Settings {
     pub backends: wgpu::Backends,
     pub power_preference: wgpu::PowerPreference,
+    pub force_fallback_adapter: bool,
+    pub window: WindowSettings,
     pub required_features: wgpu::Features,
+    /// Features that are enabled only if the adapter supports them
+    pub optional_features: wgpu::Features,
     pub required_limits: wgpu::Limits,
     pub preferred_texture_format: wgpu::TextureFormat,
     pub present_mode: wgpu::PresentMode,
     pub alpha_mode: wgpu::CompositeAlphaMode,
     pub clear_color: wgpu::Color,
+    /// The maximum number of cameras, each of which occupies one slot of the camera uniform buffer
     pub max_cameras: u32,
     pub max_lights: u32,
-    pub max_instances: u64,
+    /// The maximum number of joint matrices of all skinned instances drawn in a frame
+    pub max_joints: u32,
+    /// The number of instances that fit into the instance buffer of a new mesh. The buffer grows
+    /// as more instances of the mesh are drawn.
+    pub initial_instance_capacity: u64,
     pub depth_texture_format: wgpu::TextureFormat,
+    pub offscreen_size: winit::dpi::PhysicalSize<u32>,
+    /// The edge length in texels of the square texture that holds the shadow maps of all lights
+    pub shadow_atlas_size: u32,
+    /// The edge length in texels of the shadow map of a single light
+    pub shadow_map_size: u32,
+    /// Shadow maps are filtered over `(2 * radius + 1)^2` texels (percentage-closer filtering)
+    pub shadow_pcf_radius: u32,
+    pub post_process: PostProcessSettings,
 }
 
 impl Default for Settings {
@@ -19,7 +39,12 @@ impl Default for Settings {
         Settings {
             backends: wgpu::Backends::all(),
             power_preference: wgpu::PowerPreference::LowPower,
+            force_fallback_adapter: false,
+            window: WindowSettings::default(),
             required_features: wgpu::Features::empty(),
+            optional_features: wgpu::Features::TEXTURE_COMPRESSION_BC
+                | wgpu::Features::POLYGON_MODE_LINE
+                | wgpu::Features::TIMESTAMP_QUERY,
             required_limits: wgpu::Limits::defaults(),
             preferred_texture_format: wgpu::TextureFormat::Bgra8UnormSrgb,
             present_mode: wgpu::PresentMode::AutoVsync,
@@ -30,10 +55,16 @@ impl Default for Settings {
                 b: 0.3,
                 a: 1.0,
             },
-            max_cameras: 1,
-            max_lights: 1,
-            max_instances: 1 << 10,
+            max_cameras: 8,
+            max_lights: 64,
+            max_joints: 1024,
+            initial_instance_capacity: 4,
             depth_texture_format: wgpu::TextureFormat::Depth32Float,
+            offscreen_size: winit::dpi::PhysicalSize::new(1280, 720),
+            shadow_atlas_size: 4096,
+            shadow_map_size: 1024,
+            shadow_pcf_radius: 1,
+            post_process: PostProcessSettings::default(),
         }
     }
 }
@@ -0,0 +1,34 @@
+use crate::base::descriptors::VertexAttributeDescriptor;
+use crate::base::vertex::Vertex;
+
+/// The joints that deform a vertex of a skinned mesh
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable, serde::Serialize, serde::Deserialize,
+)]
+#[repr(C)]
+pub struct VertexSkin {
+    /// Indices into the joints of the skeleton that deforms the mesh
+    pub joints: [u32; 4],
+    /// The influence of each joint. Vertices whose weights are all zero are not deformed.
+    pub weights: [f32; 4],
+}
+
+/// A vertex of a skinned mesh
+#[derive(Debug, Default, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct SkinnedVertex {
+    pub vertex: Vertex,
+    pub skin: VertexSkin,
+}
+
+impl VertexAttributeDescriptor for SkinnedVertex {
+    const STEP_MODE: wgpu::VertexStepMode = wgpu::VertexStepMode::Vertex;
+    const ATTRS: &'static [wgpu::VertexAttribute] = &wgpu::vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32x3,
+        2 => Float32x2,
+        3 => Float32x4,
+        14 => Uint32x4,
+        15 => Float32x4,
+    ];
+}
@@ -1,5 +1,7 @@
+use crate::assets::cpu_texture::CompressedImage;
 use crate::base::gpu_object_database::GpuObjectDatabase;
 use crate::base::ids::TextureId;
+use crate::base::mipmaps::{generate_mip_chain, mip_level_count};
 use crate::base::runtime::Runtime;
 use crate::base::settings::Settings;
 use wgpu::util::TextureDataOrder;
@@ -10,7 +12,13 @@ pub struct TextureBuilder<'rt> {
     settings: &'rt Settings,
     label: Option<&'rt str>,
     image: Option<&'rt image::DynamicImage>,
+    compressed_image: Option<&'rt CompressedImage>,
+    mipmaps: bool,
     depth_texture: bool,
+    render_target: bool,
+    size: Option<(u32, u32)>,
+    format: Option<wgpu::TextureFormat>,
+    srgb: bool,
 }
 
 impl<'rt> TextureBuilder<'rt> {
@@ -21,7 +29,13 @@ impl<'rt> TextureBuilder<'rt> {
             settings,
             label: None,
             image: None,
+            compressed_image: None,
+            mipmaps: false,
             depth_texture: false,
+            render_target: false,
+            size: None,
+            format: None,
+            srgb: true,
         }
     }
 
@@ -35,28 +49,115 @@ impl<'rt> TextureBuilder<'rt> {
         self
     }
 
+    /// Upload a block-compressed image together with its mip levels
+    pub fn with_compressed_image(mut self, image: &'rt CompressedImage) -> Self {
+        self.compressed_image = Some(image);
+        self
+    }
+
+    /// Generate the full mip chain of the source image
+    pub fn with_mipmaps(mut self) -> Self {
+        self.mipmaps = true;
+        self
+    }
+
     pub fn with_depth_texture(mut self) -> Self {
         self.depth_texture = true;
         self
     }
 
+    /// Create a texture that can be rendered into and sampled afterwards, in the format of the
+    /// surface
+    pub fn with_render_target(mut self) -> Self {
+        self.render_target = true;
+        self
+    }
+
+    /// Specify the dimensions of a depth or render target texture, which otherwise match the
+    /// surface
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Specify the format of a depth or render target texture, which otherwise matches the depth
+    /// format of the settings or the surface, respectively
+    pub fn with_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Specify whether the image data is stored in sRGB color space (the default). Data textures
+    /// like normal maps must not be converted.
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
     pub fn submit(self) -> TextureId {
         let texture = if self.depth_texture {
             tracing::trace!("Creating depth texture '{}'", self.label.unwrap_or("unnamed"));
+            let (width, height) = self
+                .size
+                .unwrap_or((self.runtime.config.width, self.runtime.config.height));
             self.runtime.device.create_texture(&wgpu::TextureDescriptor {
-                label: Some("depth-texture"),
+                label: self.label.or(Some("depth-texture")),
                 size: wgpu::Extent3d {
-                    width: self.runtime.config.width,
-                    height: self.runtime.config.height,
+                    width,
+                    height,
                     depth_or_array_layers: 1,
                 },
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                format: self.settings.depth_texture_format,
+                format: self.format.unwrap_or(self.settings.depth_texture_format),
                 usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
             })
+        } else if self.render_target {
+            tracing::trace!("Creating render target texture '{}'", self.label.unwrap_or("unnamed"));
+            let (width, height) = self
+                .size
+                .unwrap_or((self.runtime.config.width, self.runtime.config.height));
+            self.runtime.device.create_texture(&wgpu::TextureDescriptor {
+                label: self.label.or(Some("render-target-texture")),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.format.unwrap_or(self.runtime.config.format),
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            })
+        } else if let Some(image) = self.compressed_image {
+            use wgpu::util::DeviceExt;
+
+            tracing::trace!("Creating compressed texture '{}'", self.label.unwrap_or("unnamed"));
+            self.runtime.device.create_texture_with_data(
+                &self.runtime.queue,
+                &wgpu::TextureDescriptor {
+                    label: self.label,
+                    size: wgpu::Extent3d {
+                        width: image.width,
+                        height: image.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: image.levels.len() as u32,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: image.format,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                    view_formats: &[],
+                },
+                TextureDataOrder::LayerMajor,
+                &image.levels.concat(),
+            )
         } else {
             use wgpu::util::DeviceExt;
 
@@ -66,6 +167,25 @@ impl<'rt> TextureBuilder<'rt> {
 
             let dims = rgba8_image.dimensions();
 
+            // Most images are stored using sRGB, while data textures like normal maps are not.
+            let format = if self.srgb {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+            } else {
+                wgpu::TextureFormat::Rgba8Unorm
+            };
+
+            // The levels are uploaded one after another, starting with the full-size image
+            let (mip_level_count, data) = if self.mipmaps {
+                let levels = generate_mip_chain(&rgba8_image, self.srgb);
+                debug_assert_eq!(levels.len() as u32, mip_level_count(dims.0, dims.1));
+                (
+                    levels.len() as u32,
+                    levels.into_iter().flat_map(|l| l.into_raw()).collect(),
+                )
+            } else {
+                (1, rgba8_image.into_raw())
+            };
+
             tracing::trace!("Creating texture '{}'", self.label.unwrap_or("unnamed"));
             self.runtime.device.create_texture_with_data(
                 &self.runtime.queue,
@@ -76,18 +196,17 @@ impl<'rt> TextureBuilder<'rt> {
                         height: dims.1,
                         depth_or_array_layers: 1,
                     },
-                    mip_level_count: 1,
+                    mip_level_count,
                     sample_count: 1,
                     dimension: wgpu::TextureDimension::D2,
-                    // Most images are stored using sRGB so we need to reflect that here.
-                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    format,
                     // TEXTURE_BINDING tells wgpu that we want to use this texture in shaders
                     // COPY_DST means that we want to copy data to this texture
                     usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                    view_formats: &[wgpu::TextureFormat::Rgba8UnormSrgb],
+                    view_formats: &[format],
                 },
-                TextureDataOrder::default(),
-                &rgba8_image,
+                TextureDataOrder::LayerMajor,
+                &data,
             )
         };
 
@@ -6,10 +6,13 @@ pub struct Vertex {
     pub position: [f32; 3],
     pub normal: [f32; 3],
     pub tex_coords: [f32; 2],
+    /// Tangent in the direction of increasing texture coordinate u, with the handedness of the
+    /// bitangent in the fourth component
+    pub tangent: [f32; 4],
 }
 
 impl VertexAttributeDescriptor for Vertex {
     const STEP_MODE: wgpu::VertexStepMode = wgpu::VertexStepMode::Vertex;
     const ATTRS: &'static [wgpu::VertexAttribute] =
-        &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2];
+        &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2, 3 => Float32x4];
 }
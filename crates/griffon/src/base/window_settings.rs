@@ -0,0 +1,125 @@
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::monitor::{MonitorHandle, VideoMode};
+use winit::window::Fullscreen;
+
+/// How the window occupies the screen
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowMode {
+    /// A decorated window of [`WindowSettings::inner_size`]
+    Windowed,
+    /// A window that covers the whole monitor without changing its video mode
+    #[default]
+    Borderless,
+    /// Exclusive fullscreen with the video mode of the monitor that best matches
+    /// [`WindowSettings::inner_size`]
+    Exclusive,
+}
+
+impl WindowMode {
+    pub const ALL: [WindowMode; 3] = [WindowMode::Windowed, WindowMode::Borderless, WindowMode::Exclusive];
+
+    /// Returns the fullscreen state of this mode on the given monitor. Exclusive fullscreen falls
+    /// back to borderless if the monitor reports no video modes.
+    pub fn fullscreen(self, monitor: Option<MonitorHandle>, size: Option<PhysicalSize<u32>>) -> Option<Fullscreen> {
+        match self {
+            WindowMode::Windowed => None,
+            WindowMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+            WindowMode::Exclusive => match monitor.as_ref().and_then(|m| best_video_mode(m, size)) {
+                Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                None => {
+                    tracing::warn!("No video mode available for exclusive fullscreen, using borderless fullscreen");
+                    Some(Fullscreen::Borderless(monitor))
+                }
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for WindowMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowMode::Windowed => write!(f, "windowed"),
+            WindowMode::Borderless => write!(f, "borderless"),
+            WindowMode::Exclusive => write!(f, "exclusive"),
+        }
+    }
+}
+
+impl std::str::FromStr for WindowMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WindowMode::ALL
+            .into_iter()
+            .find(|m| m.to_string() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown window mode '{s}', expected windowed, borderless or exclusive"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub mode: WindowMode,
+    /// The title of the window, defaults to the title chosen by the platform
+    pub title: Option<String>,
+    /// The initial size of the window contents, defaults to a platform-dependent size in windowed
+    /// mode and to the highest resolution of the monitor in exclusive mode
+    pub inner_size: Option<PhysicalSize<u32>>,
+    /// The initial position of a windowed window relative to the top left corner of its monitor
+    pub position: Option<PhysicalPosition<i32>>,
+    pub resizable: bool,
+    /// The index of the monitor among the available monitors, defaults to the primary monitor
+    pub monitor: Option<usize>,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        WindowSettings {
+            mode: WindowMode::default(),
+            title: None,
+            inner_size: None,
+            position: None,
+            resizable: true,
+            monitor: None,
+        }
+    }
+}
+
+/// Prefers the exact size if requested, otherwise the largest size, then the highest bit depth and
+/// refresh rate
+fn best_video_mode(monitor: &MonitorHandle, size: Option<PhysicalSize<u32>>) -> Option<VideoMode> {
+    monitor.video_modes().max_by_key(|vm| {
+        let vm_size = vm.size();
+        (
+            size.is_some_and(|s| s == vm_size),
+            vm_size.width * vm_size.height,
+            vm.bit_depth(),
+            vm.refresh_rate_millihertz(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_settings_are_read_from_toml() {
+        let settings: WindowSettings = toml::from_str(
+            r#"
+            mode = "windowed"
+            inner_size = { width = 1280, height = 720 }
+            resizable = false
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(settings.mode, WindowMode::Windowed);
+        assert_eq!(settings.inner_size, Some(PhysicalSize::new(1280, 720)));
+        assert!(!settings.resizable);
+        assert_eq!(settings.title, None);
+        assert_eq!("exclusive".parse::<WindowMode>().unwrap(), WindowMode::Exclusive);
+        assert!("fullscreen".parse::<WindowMode>().is_err());
+    }
+}
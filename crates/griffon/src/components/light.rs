@@ -15,6 +15,15 @@ pub struct Light {
     pub specular_color: Vec4<f32>,
     pub ambient_intensity: f32,
     pub point_intensity: f32,
+    pub kind: LightKind,
+    pub direction: Vec4<f32>,
+    pub attenuation: Attenuation,
+    pub inner_cone_angle: f32,
+    pub outer_cone_angle: f32,
+    pub casts_shadows: bool,
+    pub shadow_bias: f32,
+    pub shadow_extent: f32,
+    pub shadow_range: f32,
     pub group: String,
     pub name: String,
 }
@@ -33,7 +42,7 @@ impl Light {
             }
         }
 
-        let model = load_instanced_gpu_model(res, &source.group, &source.name).await?;
+        let model = load_instanced_gpu_model(res, &source.group, &source.name, None).await?;
 
         Ok(Self {
             model,
@@ -43,6 +52,15 @@ impl Light {
             specular_color: source.specular_color,
             ambient_intensity: source.ambient_intensity,
             point_intensity: source.point_intensity,
+            kind: source.kind,
+            direction: source.direction,
+            attenuation: source.attenuation,
+            inner_cone_angle: source.inner_cone_angle,
+            outer_cone_angle: source.outer_cone_angle,
+            casts_shadows: source.casts_shadows,
+            shadow_bias: source.shadow_bias,
+            shadow_extent: source.shadow_extent,
+            shadow_range: source.shadow_range,
             group: source.group.clone(),
             name: source.name.clone(),
         })
@@ -63,4 +81,111 @@ pub struct LightSource {
     pub specular_color: Vec4<f32>,
     pub ambient_intensity: f32,
     pub point_intensity: f32,
+    #[serde(default)]
+    pub kind: LightKind,
+    /// The direction light travels in, used by directional and spot lights
+    #[serde(default = "default_direction")]
+    pub direction: Vec4<f32>,
+    #[serde(default)]
+    pub attenuation: Attenuation,
+    /// The angle in radians between the axis of a spot light and the edge of its full intensity cone
+    #[serde(default = "default_inner_cone_angle")]
+    pub inner_cone_angle: f32,
+    /// The angle in radians between the axis of a spot light and the edge of its light cone
+    #[serde(default = "default_outer_cone_angle")]
+    pub outer_cone_angle: f32,
+    /// Renders a shadow map for this light. Only directional and spot lights cast shadows.
+    #[serde(default)]
+    pub casts_shadows: bool,
+    /// The depth offset that prevents surfaces from shadowing themselves (shadow acne)
+    #[serde(default = "default_shadow_bias")]
+    pub shadow_bias: f32,
+    /// Half the edge length of the area covered by the shadow map of a directional light
+    #[serde(default = "default_shadow_extent")]
+    pub shadow_extent: f32,
+    /// The distance from the light up to which shadows are cast
+    #[serde(default = "default_shadow_range")]
+    pub shadow_range: f32,
+}
+
+impl Default for LightSource {
+    /// A white point light at the origin without a model. Optional parameters assume the same
+    /// values as when they are omitted from a scene file.
+    fn default() -> Self {
+        LightSource {
+            group: String::new(),
+            name: String::new(),
+            position: Vec4::new_point(0.0, 0.0, 0.0),
+            ambient_color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            diffuse_color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            specular_color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            ambient_intensity: 0.05,
+            point_intensity: 1.0,
+            kind: LightKind::default(),
+            direction: default_direction(),
+            attenuation: Attenuation::default(),
+            inner_cone_angle: default_inner_cone_angle(),
+            outer_cone_angle: default_outer_cone_angle(),
+            casts_shadows: false,
+            shadow_bias: default_shadow_bias(),
+            shadow_extent: default_shadow_extent(),
+            shadow_range: default_shadow_range(),
+        }
+    }
+}
+
+/// Determines how light is emitted by a [`Light`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[repr(u32)]
+pub enum LightKind {
+    /// Emits light in all directions from its position
+    #[default]
+    Point = 0,
+    /// Emits parallel light along its direction from infinitely far away
+    Directional = 1,
+    /// Emits light from its position in a cone around its direction
+    Spot = 2,
+}
+
+/// Distance attenuation of point and spot lights, following `1 / (constant + linear * d + quadratic * d^2)`
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Default for Attenuation {
+    /// No attenuation at all
+    fn default() -> Self {
+        Attenuation {
+            constant: 1.0,
+            linear: 0.0,
+            quadratic: 0.0,
+        }
+    }
+}
+
+fn default_direction() -> Vec4<f32> {
+    Vec4::new(0.0, -1.0, 0.0, 0.0)
+}
+
+fn default_inner_cone_angle() -> f32 {
+    std::f32::consts::PI / 8.0
+}
+
+fn default_outer_cone_angle() -> f32 {
+    std::f32::consts::PI / 6.0
+}
+
+fn default_shadow_bias() -> f32 {
+    0.002
+}
+
+fn default_shadow_extent() -> f32 {
+    20.0
+}
+
+fn default_shadow_range() -> f32 {
+    50.0
 }
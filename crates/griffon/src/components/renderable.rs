@@ -1,23 +1,37 @@
+use crate::assets::primitives::Primitive;
 use crate::base::gpu_model::GpuModel;
-use crate::utilities::load_instanced_gpu_model;
+use crate::utilities::{ModelSetCache, load_instanced_gpu_model_with_cache, load_instanced_primitive};
 use ecs::{Component, Resources, VecStorage};
 
 #[derive(Debug)]
 pub struct Renderable {
     pub model: GpuModel,
-    pub group: String,
-    pub name: String,
+    pub source: RenderableSource,
 }
 
 impl Renderable {
-    #[tracing::instrument(skip(res))]
     pub async fn new(res: &Resources, source: &RenderableSource) -> anyhow::Result<Self> {
-        let model = load_instanced_gpu_model(res, &source.group, &source.name).await?;
+        Self::with_cache(res, source, &mut ModelSetCache::default()).await
+    }
+
+    /// Like [`Renderable::new`], but takes the meshes of glTF models from `cache`, for example
+    /// while loading a scene with many of them
+    #[tracing::instrument(skip(res, cache))]
+    pub async fn with_cache(
+        res: &Resources,
+        source: &RenderableSource,
+        cache: &mut ModelSetCache,
+    ) -> anyhow::Result<Self> {
+        let model = match source {
+            RenderableSource::Asset { group, name, mesh } => {
+                load_instanced_gpu_model_with_cache(res, group, name, *mesh, cache).await?
+            }
+            RenderableSource::Primitive { primitive } => load_instanced_primitive(res, primitive),
+        };
 
         Ok(Renderable {
             model,
-            group: source.group.clone(),
-            name: source.name.clone(),
+            source: source.clone(),
         })
     }
 }
@@ -26,8 +40,24 @@ impl Component for Renderable {
     type Storage = VecStorage<Self>;
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
-pub struct RenderableSource {
-    pub group: String,
-    pub name: String,
+/// Describes where the model of a [`Renderable`] comes from
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum RenderableSource {
+    /// A model loaded from the asset database
+    Asset {
+        group: String,
+        name: String,
+        /// Selects a single mesh of a model that contains several, such as a glTF document
+        #[serde(default)]
+        mesh: Option<usize>,
+    },
+    /// A mesh generated from a shape and its parameters, drawn with the default material
+    Primitive { primitive: Primitive },
+}
+
+impl From<Primitive> for RenderableSource {
+    fn from(primitive: Primitive) -> Self {
+        RenderableSource::Primitive { primitive }
+    }
 }
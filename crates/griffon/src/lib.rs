@@ -5,8 +5,12 @@ mod macros;
 pub mod resources;
 mod utilities;
 
+pub use self::base::post_process_settings::PostProcessSettings;
 pub use self::base::settings::Settings;
+pub use self::base::window_settings::{WindowMode, WindowSettings};
 pub use self::resources::{Graphics, GraphicsDeps};
+pub use self::utilities::ModelSetCache;
+pub use image;
 pub use wgpu;
 pub use wgpu_core;
 pub use wgpu_types;
@@ -1,5 +1,8 @@
+use std::collections::HashMap;
 use std::mem::size_of;
 
+use anyhow::Context;
+
 use wgpu::{BindingType, BufferAddress, BufferBindingType, BufferSize, BufferUsages, ShaderStages};
 use winit::dpi::PhysicalSize;
 use winit::event_loop::EventLoopWindowTarget;
@@ -7,26 +10,33 @@ use winit::event_loop::EventLoopWindowTarget;
 use super::assets::cpu_material::CpuMaterial;
 use super::assets::cpu_mesh::CpuMesh;
 use super::assets::cpu_model::CpuModel;
-use super::assets::cpu_texture::CpuTexture;
+use super::assets::cpu_texture::TextureData;
+use super::assets::shader_source::{ShaderError, ShaderSource};
 use crate::base::bind_group_builder::BindGroupBuilder;
 use crate::base::bind_group_layout_builder::BindGroupLayoutBuilder;
 use crate::base::camera_uniform::CameraUniform;
 use crate::base::encoder::Encoder;
+use crate::base::gpu_handle::{GpuHandle, GpuObjects};
 use crate::base::gpu_material::GpuMaterial;
 use crate::base::gpu_mesh::GpuMesh;
 use crate::base::gpu_model::GpuModel;
-use crate::base::gpu_object_database::GpuObjectDatabase;
+use crate::base::gpu_object_database::{AllocationCounts, GpuObjectDatabase};
 use crate::base::gpu_texture::GpuTexture;
-use crate::base::ids::{BindGroupLayoutId, BufferId, ShaderModuleId, TextureId, TextureViewId};
+use crate::base::gpu_timer::{GpuTimer, PassTiming};
+use crate::base::ids::{BindGroupId, BindGroupLayoutId, BufferId, ShaderModuleId, TextureId, TextureViewId};
 use crate::base::instance::Instance;
 use crate::base::internal_runtime_data::InternalRuntimeData;
-use crate::base::light_uniform::LightUniform;
+use crate::base::light_uniform::{LightBufferHeader, LightUniform, ShadowUniform};
 use crate::base::material_uniform::MaterialUniform;
+use crate::base::post_process_settings::PostProcessSettings;
+use crate::base::post_process_uniform::PostProcessUniform;
+use crate::base::render_graph::TransientTexture;
 use crate::base::render_pipeline_builder::RenderPipelineBuilder;
-use crate::base::runtime::Runtime;
-use crate::base::sampler_builder::SamplerBuilder;
+use crate::base::runtime::{RenderTarget, Runtime};
+use crate::base::sampler_builder::{SamplerBuilder, SamplerSettings};
 use crate::base::settings::Settings;
 use crate::base::texture_builder::TextureBuilder;
+use crate::base::window_settings::WindowMode;
 use ecs::{Resource, WithDependencies};
 use urn::Urn;
 
@@ -36,7 +46,9 @@ const DEPTH_TEXTURE_VIEW_LABEL: Option<&str> = Some("depth-stencil:view");
 pub trait GraphicsDeps {
     type CustomEvent: 'static;
 
-    fn event_loop(&self) -> &EventLoopWindowTarget<Self::CustomEvent>;
+    /// Returns the event loop that owns the window. Without an event loop, [`Graphics`] renders
+    /// headless into an offscreen texture of [`Settings::offscreen_size`].
+    fn event_loop(&self) -> Option<&EventLoopWindowTarget<Self::CustomEvent>>;
     fn settings(&self) -> &Settings;
 }
 
@@ -61,16 +73,62 @@ impl Graphics {
         self.settings.max_lights
     }
 
-    pub fn max_instances(&self) -> u64 {
-        self.settings.max_instances
+    /// Returns the size of a light storage buffer that holds up to [`Graphics::max_lights`] lights
+    pub fn light_buffer_size(&self) -> BufferAddress {
+        (size_of::<LightBufferHeader>() + self.settings.max_lights as usize * size_of::<LightUniform>())
+            as BufferAddress
+    }
+
+    pub fn max_joints(&self) -> u32 {
+        self.settings.max_joints
+    }
+
+    /// Returns the size of a joint storage buffer that holds up to [`Graphics::max_joints`] joint
+    /// matrices
+    pub fn joint_buffer_size(&self) -> BufferAddress {
+        (self.settings.max_joints.max(1) as usize * size_of::<[[f32; 4]; 4]>()) as BufferAddress
+    }
+
+    /// Returns the edge length of the shadow atlas, limited by the maximum texture size of the device
+    pub fn shadow_atlas_size(&self) -> u32 {
+        self.settings
+            .shadow_atlas_size
+            .min(self.runtime.device.limits().max_texture_dimension_2d)
+    }
+
+    pub fn shadow_map_size(&self) -> u32 {
+        self.settings.shadow_map_size
+    }
+
+    pub fn shadow_pcf_radius(&self) -> u32 {
+        self.settings.shadow_pcf_radius
+    }
+
+    /// Returns the number of shadow maps that fit into the shadow atlas
+    pub fn max_shadow_casters(&self) -> u32 {
+        let tiles_per_row = self.shadow_atlas_size() / self.settings.shadow_map_size.max(1);
+        tiles_per_row * tiles_per_row
+    }
+
+    pub fn post_process_settings(&self) -> &PostProcessSettings {
+        &self.settings.post_process
+    }
+
+    /// Changes the post-processing effects, which take effect with the next frame
+    pub fn set_post_process_settings(&mut self, post_process: PostProcessSettings) {
+        tracing::debug!("Setting the post-processing effects to {post_process:?}");
+        self.settings.post_process = post_process;
     }
 
     pub fn gen_instance_report(&self) -> Option<wgpu_core::global::GlobalReport> {
         self.runtime.instance.generate_report()
     }
 
-    pub fn gen_surface_capabilities(&self) -> wgpu::SurfaceCapabilities {
-        self.runtime.surface.get_capabilities(&self.runtime.adapter)
+    pub fn gen_surface_capabilities(&self) -> Option<wgpu::SurfaceCapabilities> {
+        match &self.runtime.target {
+            RenderTarget::Window { surface, .. } => Some(surface.get_capabilities(&self.runtime.adapter)),
+            RenderTarget::Offscreen { .. } => None,
+        }
     }
 
     pub fn gen_adapter_features(&self) -> wgpu::Features {
@@ -93,20 +151,76 @@ impl Graphics {
         self.runtime.device.generate_allocator_report()
     }
 
+    pub fn gen_allocation_counts(&self) -> AllocationCounts {
+        AllocationCounts {
+            instances: self.internal.instances.taken(),
+            ..self.database.allocation_counts()
+        }
+    }
+
+    /// Returns `true` if the device was created with all of the features, see
+    /// [`Settings::optional_features`]
+    pub fn supports_features(&self, features: wgpu::Features) -> bool {
+        self.runtime.device.features().contains(features)
+    }
+
+    /// Returns `true` if textures of the format can be sampled, which for compressed formats
+    /// depends on the features of the device
+    pub fn supports_texture_format(&self, format: wgpu::TextureFormat) -> bool {
+        self.supports_features(format.required_features())
+    }
+
     pub fn limits(&self) -> wgpu::Limits {
         self.runtime.device.limits()
     }
 
-    pub fn window_id(&self) -> winit::window::WindowId {
-        self.runtime.window.id()
+    /// Returns `true` if frames are rendered into an offscreen texture instead of a window
+    pub fn is_headless(&self) -> bool {
+        matches!(self.runtime.target, RenderTarget::Offscreen { .. })
+    }
+
+    pub fn window_id(&self) -> Option<winit::window::WindowId> {
+        self.runtime.window().map(|w| w.id())
+    }
+
+    /// Returns the size of the surface textures and the default depth texture
+    pub fn surface_size(&self) -> PhysicalSize<u32> {
+        self.runtime.size
+    }
+
+    pub fn surface_format(&self) -> wgpu::TextureFormat {
+        self.runtime.config.format
     }
 
     pub fn window_inner_size(&self) -> PhysicalSize<u32> {
-        self.runtime.window.inner_size()
+        self.runtime.window().map_or(self.runtime.size, |w| w.inner_size())
     }
 
     pub fn request_redraw(&self) {
-        self.runtime.window.request_redraw()
+        if let Some(window) = self.runtime.window() {
+            window.request_redraw()
+        }
+    }
+
+    /// Switches the window between windowed and fullscreen modes. The surface is resized once the
+    /// window reports its new size.
+    pub fn set_window_mode(&mut self, mode: WindowMode) {
+        tracing::debug!("Setting the window mode to {mode}");
+        self.settings.window.mode = mode;
+        self.runtime.set_window_mode(mode);
+    }
+
+    /// Chooses between presenting frames synchronized to the display and as fast as possible
+    pub fn set_vsync(&mut self, vsync: bool) {
+        let present_mode = if vsync {
+            wgpu::PresentMode::AutoVsync
+        } else {
+            wgpu::PresentMode::AutoNoVsync
+        };
+        tracing::debug!("Setting the present mode to {present_mode:?}");
+        self.settings.present_mode = present_mode;
+        self.runtime.config.present_mode = present_mode;
+        self.runtime.configure();
     }
 
     pub fn reconfigure(&mut self) {
@@ -128,10 +242,10 @@ impl Graphics {
         self.runtime.size = new_size;
         self.runtime.config.width = new_size.width;
         self.runtime.config.height = new_size.height;
-        self.runtime
-            .surface
-            .configure(&self.runtime.device, &self.runtime.config);
+        self.runtime.configure();
 
+        self.database.remove_texture_view(self.internal.depth_texture_view);
+        self.database.remove_texture(self.internal.depth_texture);
         self.internal.depth_texture =
             Self::create_depth_texture_int(&self.runtime, &mut self.database, &self.settings, DEPTH_TEXTURE_LABEL);
         self.internal.depth_texture_view = Self::create_texture_view_int(
@@ -149,10 +263,30 @@ impl Graphics {
         self.internal.light_bind_group_layout
     }
 
+    pub fn shadow_bind_group_layout(&self) -> BindGroupLayoutId {
+        self.internal.shadow_bind_group_layout
+    }
+
+    pub fn joint_bind_group_layout(&self) -> BindGroupLayoutId {
+        self.internal.joint_bind_group_layout
+    }
+
     pub fn material_bind_group_layout(&self) -> BindGroupLayoutId {
         self.internal.material_bind_group_layout
     }
 
+    pub fn post_process_bind_group_layout(&self) -> BindGroupLayoutId {
+        self.internal.post_process_bind_group_layout
+    }
+
+    /// Returns the white material that is bound for models without materials
+    pub fn default_material(&self) -> &GpuMaterial {
+        self.internal
+            .default_material
+            .as_ref()
+            .expect("the default material is created along with the graphics resources")
+    }
+
     pub fn write_buffer<T>(&self, buffer: BufferId, data: &[T])
     where
         T: bytemuck::NoUninit,
@@ -162,6 +296,46 @@ impl Graphics {
             .write_buffer(&self.database.buffers[&buffer], 0, bytemuck::cast_slice(data));
     }
 
+    /// Writes the instances to the instance buffer of a mesh. A buffer that is too small is
+    /// replaced by one with at least twice the capacity under the same [`BufferId`], such that all
+    /// instances of the mesh keep referring to it.
+    pub fn write_instance_buffer(&mut self, buffer: BufferId, instances: &[Instance]) {
+        self.write_growing_buffer(buffer, "instance-buffer", instances);
+    }
+
+    /// Writes vertices that change every frame to a vertex buffer, which grows like an instance
+    /// buffer, see [`Graphics::write_instance_buffer`]
+    pub fn write_vertex_buffer<T: bytemuck::NoUninit>(&mut self, buffer: BufferId, vertices: &[T]) {
+        self.write_growing_buffer(buffer, "vertex-buffer", vertices);
+    }
+
+    fn write_growing_buffer<T: bytemuck::NoUninit>(&mut self, buffer: BufferId, label: &str, data: &[T]) {
+        let element_size = size_of::<T>() as BufferAddress;
+        let required_size = data.len() as BufferAddress * element_size;
+        let current_size = self.database.buffers[&buffer].size();
+        if required_size > current_size {
+            let capacity = (data.len() as u64)
+                .next_power_of_two()
+                .max(2 * current_size / element_size);
+            tracing::debug!(
+                "Growing {} {:?} from {} to {} elements",
+                label,
+                buffer,
+                current_size / element_size,
+                capacity
+            );
+            let grown = self.runtime.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: capacity * element_size,
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            self.database.replace_buffer(buffer, grown);
+        }
+
+        self.write_buffer(buffer, data);
+    }
+
     #[must_use]
     pub fn create_shader_module<'a, 's, S: Into<std::borrow::Cow<'s, str>>>(
         &mut self,
@@ -177,6 +351,28 @@ impl Graphics {
         self.database.insert_shader_module(sm)
     }
 
+    /// Creates the variant of a shader with the given feature flags, after validating it. Variants
+    /// are cached by the contents of the shader files and the set of flags.
+    pub fn create_shader_variant(
+        &mut self,
+        source: &ShaderSource,
+        defines: &[&str],
+    ) -> Result<ShaderModuleId, ShaderError> {
+        let mut flags: Vec<String> = defines.iter().map(|d| d.to_string()).collect();
+        flags.sort();
+        flags.dedup();
+        let key = (source.fingerprint(), flags);
+        if let Some(&shader_module) = self.internal.shader_variants.get(&key) {
+            return Ok(shader_module);
+        }
+
+        let wgsl = source.validate(defines)?;
+        let label = format!("{}[{}]", source.name(), key.1.join(","));
+        let shader_module = self.create_shader_module(Some(&label), wgsl);
+        self.internal.shader_variants.insert(key, shader_module);
+        Ok(shader_module)
+    }
+
     pub fn create_encoder(&self, label: Option<&str>) -> Result<Encoder<'_>, wgpu::SurfaceError> {
         Encoder::new(
             label,
@@ -184,9 +380,148 @@ impl Graphics {
             &self.settings,
             &self.database,
             self.internal.depth_texture_view,
+            self.internal.gpu_timer.as_ref(),
         )
     }
 
+    /// Returns whether the GPU time of render passes is measured, which requires
+    /// [`wgpu::Features::TIMESTAMP_QUERY`]
+    pub fn gpu_timing_supported(&self) -> bool {
+        self.internal.gpu_timer.is_some()
+    }
+
+    /// Returns the GPU time of each render pass of a frame that the GPU finished since the last
+    /// call. Returns `None` if no frame finished in the meantime or timing is not supported.
+    pub fn collect_gpu_timings(&self) -> Option<Vec<PassTiming>> {
+        self.internal.gpu_timer.as_ref()?.collect(&self.runtime.device)
+    }
+
+    pub fn clear_color(&self) -> wgpu::Color {
+        self.settings.clear_color
+    }
+
+    /// Returns the depth texture view that matches the size of the surface
+    pub fn depth_texture_view(&self) -> TextureViewId {
+        self.internal.depth_texture_view
+    }
+
+    /// Returns one texture view per transient texture of a compiled
+    /// [`RenderGraph`](crate::base::render_graph::RenderGraph). Textures requested by the
+    /// previous frame are reused, the others are released.
+    #[tracing::instrument(skip_all)]
+    pub fn acquire_transient_textures(&mut self, textures: &[TransientTexture]) -> Vec<TextureViewId> {
+        let mut available = std::mem::take(&mut self.internal.transient_textures);
+        let mut acquired = Vec::with_capacity(textures.len());
+        for &desc in textures {
+            let entry = match available.iter().position(|(d, _, _)| *d == desc) {
+                Some(i) => available.swap_remove(i),
+                None => {
+                    tracing::debug!(
+                        "Creating a {}x{} transient texture of format {:?}",
+                        desc.width,
+                        desc.height,
+                        desc.format
+                    );
+                    let builder = self
+                        .create_texture()
+                        .with_label(Some("transient-texture"))
+                        .with_size(desc.width, desc.height)
+                        .with_format(desc.format);
+                    let texture = if desc.format.is_depth_stencil_format() {
+                        builder.with_depth_texture().submit()
+                    } else {
+                        builder.with_render_target().submit()
+                    };
+                    let view = self.create_texture_view(Some("transient-texture-view"), texture);
+                    (desc, texture, view)
+                }
+            };
+            acquired.push(entry);
+        }
+
+        for (_, texture, view) in available {
+            self.remove_texture_view(view);
+            self.remove_texture(texture);
+        }
+
+        let views = acquired.iter().map(|(_, _, view)| *view).collect();
+        self.internal.transient_textures = acquired;
+        views
+    }
+
+    /// Copies the most recently rendered frame back to the CPU. Only available in headless mode.
+    #[tracing::instrument(skip_all)]
+    pub fn read_frame(&self) -> anyhow::Result<image::RgbaImage> {
+        let RenderTarget::Offscreen { texture } = &self.runtime.target else {
+            anyhow::bail!("Frames can only be read back when rendering headless");
+        };
+
+        let swap_red_blue = match texture.format() {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            f => anyhow::bail!("Reading back frames of texture format {:?} is not supported", f),
+        };
+
+        let width = texture.width();
+        let height = texture.height();
+        let unpadded_bytes_per_row = width * 4;
+        let padded_bytes_per_row =
+            unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = self.runtime.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("offscreen:readback-buffer"),
+            size: padded_bytes_per_row as BufferAddress * height as BufferAddress,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .runtime
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("offscreen:readback-encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        self.runtime.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |r| {
+            let _ = tx.send(r);
+        });
+        self.runtime
+            .device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .context("Waiting for the readback of the offscreen texture")?;
+        rx.recv()
+            .context("Receiving the mapping status of the readback buffer")?
+            .context("Mapping the readback buffer")?;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        for row in slice.get_mapped_range().chunks_exact(padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+        buffer.unmap();
+
+        if swap_red_blue {
+            pixels.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+        }
+
+        image::RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| anyhow::anyhow!("The readback buffer does not match the frame dimensions"))
+    }
+
     #[must_use]
     pub fn create_render_pipeline(&mut self) -> RenderPipelineBuilder<'_, '_, '_> {
         RenderPipelineBuilder::new(&self.runtime, &mut self.database, &self.settings)
@@ -242,65 +577,96 @@ impl Graphics {
         self.database.insert_buffer(buf)
     }
 
-    #[tracing::instrument(skip_all)]
-    #[must_use]
-    fn create_gpu_texture(&mut self, t: &CpuTexture) -> GpuTexture {
-        let texture = TextureBuilder::new(&self.runtime, &mut self.database, &self.settings)
-            .with_label(t.label.as_ref().map(|l| format!("{}:texture", &l)).as_deref())
-            .with_image(&t.image)
-            .submit();
-        let view = self.create_texture_view(
-            t.label.as_ref().map(|l| format!("{}:texture-view", &l)).as_deref(),
-            texture,
-        );
-        let sampler = self
-            .create_sampler()
-            .with_label(t.label.as_ref().map(|l| format!("{}:texture-sampler", &l)).as_deref())
-            .submit();
+    pub fn remove_buffer(&mut self, buffer: BufferId) {
+        self.database.remove_buffer(buffer);
+    }
 
-        GpuTexture { texture, view, sampler }
+    pub fn remove_bind_group(&mut self, bind_group: BindGroupId) {
+        self.database.remove_bind_group(bind_group);
+    }
+
+    pub fn remove_texture(&mut self, texture: TextureId) {
+        self.database.remove_texture(texture);
     }
 
+    pub fn remove_texture_view(&mut self, view: TextureViewId) {
+        self.database.remove_texture_view(view);
+    }
+
+    /// Releases the GPU objects and mesh instances of all [`GpuHandle`]s that are no longer owned
+    /// by anything but [`Graphics`], such as the models of destroyed [`Renderable`]s and
+    /// [`Light`]s. Returns the number of released handles.
+    ///
+    /// [`Renderable`]: crate::components::renderable::Renderable
+    /// [`Light`]: crate::components::light::Light
     #[tracing::instrument(skip_all)]
-    #[must_use]
-    fn create_gpu_material(&mut self, m: &CpuMaterial) -> GpuMaterial {
-        let texture = self.create_gpu_texture(&m.texture);
+    pub fn collect_garbage(&mut self) -> usize {
+        let (unused, used): (Vec<_>, Vec<_>) = std::mem::take(&mut self.internal.handles)
+            .into_iter()
+            .partition(|h| h.owners() == 1);
+        self.internal.handles = used;
+
+        let released = unused.len();
+        for objects in unused.into_iter().filter_map(GpuHandle::into_objects) {
+            for id in objects.bind_groups {
+                self.database.remove_bind_group(id);
+            }
+            for id in objects.buffers {
+                self.database.remove_buffer(id);
+            }
+            for id in objects.texture_views {
+                self.database.remove_texture_view(id);
+            }
+            for id in objects.textures {
+                self.database.remove_texture(id);
+            }
+            for id in objects.samplers {
+                self.database.remove_sampler(id);
+            }
+            for id in objects.instances {
+                self.internal.instances.give_back(id);
+            }
+        }
 
-        let material = self.create_buffer_init(
-            Some("material-buffer"),
-            BufferUsages::UNIFORM,
-            &[MaterialUniform {
-                ambient_reflectivity: m.ambient_reflectivity,
-                diffuse_reflectivity: m.diffuse_reflectivity,
-                specular_reflectivity: m.specular_reflectivity,
-                smoothness: m.smoothness,
-            }],
-        );
+        if released > 0 {
+            tracing::debug!("Released {released} unused GPU resource handles");
+        }
+        released
+    }
 
-        let layout = self.material_bind_group_layout();
-        let bind_group = self
-            .create_bind_group(layout)
-            .with_label(m.label.as_ref().map(|l| format!("{}:bind-group", &l)).as_deref())
-            .add_texture_view(0, texture.view)
-            .add_sampler(1, texture.sampler)
-            .add_entire_buffer(2, material)
-            .submit();
+    /// Hands out a [`GpuHandle`] for the objects and keeps a second one, such that
+    /// [`Graphics::collect_garbage`] notices when the objects are no longer used
+    fn track(&mut self, objects: GpuObjects) -> GpuHandle {
+        let handle = GpuHandle::new(objects);
+        self.internal.handles.push(handle.clone());
+        handle
+    }
 
-        GpuMaterial { texture, bind_group }
+    #[tracing::instrument(skip_all)]
+    #[must_use]
+    fn create_gpu_material(&mut self, m: &CpuMaterial) -> GpuMaterial {
+        let material =
+            Self::create_gpu_material_int(&self.runtime, &mut self.database, &self.settings, &self.internal, m);
+        self.internal.handles.push(material.handle.clone());
+        material
     }
 
     #[tracing::instrument(skip_all)]
     #[must_use]
     fn create_gpu_mesh(&mut self, m: &CpuMesh) -> GpuMesh {
-        let vertex_buffer = self.create_buffer_init(
-            m.label.as_ref().map(|l| format!("{}:vertex-buffer", &l)).as_deref(),
-            BufferUsages::VERTEX,
-            &m.vertices,
-        );
+        let vertex_buffer_label = m.label.as_ref().map(|l| format!("{}:vertex-buffer", &l));
+        let vertex_buffer = if m.is_skinned() {
+            self.create_buffer_init(
+                vertex_buffer_label.as_deref(),
+                BufferUsages::VERTEX,
+                &m.skinned_vertices(),
+            )
+        } else {
+            self.create_buffer_init(vertex_buffer_label.as_deref(), BufferUsages::VERTEX, &m.vertices)
+        };
         let instance_buffer = {
-            let max_instances = self.max_instances();
-            let buffer_alignment = size_of::<Instance>() as u64;
-            let buffer_size = (max_instances * buffer_alignment) as BufferAddress;
+            let buffer_size =
+                (self.settings.initial_instance_capacity.max(1) * size_of::<Instance>() as u64) as BufferAddress;
             self.create_buffer(
                 m.label.as_ref().map(|l| format!("{}:instance-buffer", &l)).as_deref(),
                 buffer_size,
@@ -313,24 +679,50 @@ impl Graphics {
             &m.indices,
         );
 
+        let handle = self.track(GpuObjects {
+            buffers: vec![vertex_buffer, instance_buffer, index_buffer],
+            ..Default::default()
+        });
+        let instance_id = self.internal.instances.take();
+        let instance_handle = self.track(GpuObjects {
+            instances: vec![instance_id],
+            ..Default::default()
+        });
+
         GpuMesh {
             vertex_buffer,
             instance_buffer,
             index_buffer,
             num_indices: m.indices.len() as u32,
-            instance_id: self.internal.instances.take(),
+            submeshes: m.submeshes.clone(),
+            bounds: m.bounds,
+            skinned: m.is_skinned(),
+            instance_id,
+            handle,
+            instance_handle,
         }
     }
 
     #[tracing::instrument(skip_all)]
     #[must_use]
     fn create_instanced_gpu_mesh(&mut self, m: &GpuMesh) -> GpuMesh {
+        let instance_id = self.internal.instances.take();
+        let instance_handle = self.track(GpuObjects {
+            instances: vec![instance_id],
+            ..Default::default()
+        });
+
         GpuMesh {
             vertex_buffer: m.vertex_buffer,
             instance_buffer: m.instance_buffer,
             index_buffer: m.index_buffer,
             num_indices: m.num_indices,
-            instance_id: self.internal.instances.take(),
+            submeshes: m.submeshes.clone(),
+            bounds: m.bounds,
+            skinned: m.skinned,
+            instance_id,
+            handle: m.handle.clone(),
+            instance_handle,
         }
     }
 
@@ -384,6 +776,136 @@ impl Graphics {
             .submit()
     }
 
+    #[tracing::instrument(skip_all)]
+    fn create_gpu_material_int(
+        runtime: &Runtime,
+        database: &mut GpuObjectDatabase,
+        settings: &Settings,
+        internal: &InternalRuntimeData,
+        m: &CpuMaterial,
+    ) -> GpuMaterial {
+        use wgpu::util::DeviceExt;
+
+        // Only the textures created for this material are owned by it, not the defaults
+        let mut owned_textures = Vec::new();
+        let diffuse_texture = Self::create_gpu_texture_int(
+            runtime,
+            database,
+            settings,
+            m.diffuse_texture.label.as_deref(),
+            &m.diffuse_texture.data,
+            true,
+            &m.sampler,
+        );
+        owned_textures.push(diffuse_texture.clone());
+        let normal_texture = match m.normal_texture {
+            Some(ref t) => {
+                let t = Self::create_gpu_texture_int(
+                    runtime,
+                    database,
+                    settings,
+                    t.label.as_deref(),
+                    &t.data,
+                    false,
+                    &m.sampler,
+                );
+                owned_textures.push(t.clone());
+                t
+            }
+            None => internal.default_normal_texture.clone(),
+        };
+        let specular_texture = match m.specular_texture {
+            Some(ref t) => {
+                let t = Self::create_gpu_texture_int(
+                    runtime,
+                    database,
+                    settings,
+                    t.label.as_deref(),
+                    &t.data,
+                    false,
+                    &m.sampler,
+                );
+                owned_textures.push(t.clone());
+                t
+            }
+            None => internal.default_specular_texture.clone(),
+        };
+
+        tracing::trace!("Creating and initializing buffer 'material-buffer'");
+        let material = runtime.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("material-buffer"),
+            usage: BufferUsages::UNIFORM,
+            contents: bytemuck::cast_slice(&[MaterialUniform {
+                ambient_reflectivity: m.ambient_reflectivity,
+                diffuse_reflectivity: m.diffuse_reflectivity,
+                specular_reflectivity: m.specular_reflectivity,
+                smoothness: m.smoothness,
+                alpha_cutoff: m.alpha_cutoff,
+                opacity: m.opacity,
+                _padding: [0.0; 2],
+            }]),
+        });
+        let material = database.insert_buffer(material);
+
+        let bind_group = BindGroupBuilder::new(runtime, database, internal.material_bind_group_layout)
+            .with_label(m.label.as_ref().map(|l| format!("{}:bind-group", &l)).as_deref())
+            .add_texture_view(0, diffuse_texture.view)
+            .add_sampler(1, diffuse_texture.sampler)
+            .add_entire_buffer(2, material)
+            .add_texture_view(3, normal_texture.view)
+            .add_texture_view(4, specular_texture.view)
+            .submit();
+
+        let handle = GpuHandle::new(GpuObjects {
+            bind_groups: vec![bind_group],
+            buffers: vec![material],
+            textures: owned_textures.iter().map(|t| t.texture).collect(),
+            texture_views: owned_textures.iter().map(|t| t.view).collect(),
+            samplers: owned_textures.iter().map(|t| t.sampler).collect(),
+            ..Default::default()
+        });
+
+        GpuMaterial {
+            diffuse_texture,
+            normal_texture,
+            specular_texture,
+            bind_group,
+            blend_mode: m.blend_mode,
+            handle,
+        }
+    }
+
+    /// This function does not bind `self` on purpose because it needs to work during the constructor.
+    #[must_use]
+    fn create_gpu_texture_int(
+        runtime: &Runtime,
+        database: &mut GpuObjectDatabase,
+        settings: &Settings,
+        label: Option<&str>,
+        data: &TextureData,
+        srgb: bool,
+        sampler: &SamplerSettings,
+    ) -> GpuTexture {
+        let texture_label = label.map(|l| format!("{}:texture", &l));
+        let builder = TextureBuilder::new(runtime, database, settings).with_label(texture_label.as_deref());
+        // Compressed images bring their own mip levels and encode the color space in their format
+        let texture = match data {
+            TextureData::Image(image) => builder.with_image(image).with_srgb(srgb).with_mipmaps().submit(),
+            TextureData::Compressed(image) => builder.with_compressed_image(image).submit(),
+        };
+        let view = Self::create_texture_view_int(
+            database,
+            label.map(|l| format!("{}:texture-view", &l)).as_deref(),
+            texture,
+        );
+        let sampler = SamplerBuilder::new(runtime, database)
+            .with_label(label.map(|l| format!("{}:texture-sampler", &l)).as_deref())
+            .with_settings(sampler)
+            .submit();
+
+        GpuTexture { texture, view, sampler }
+    }
+
     #[must_use]
     fn create_texture_view_int(
         database: &mut GpuObjectDatabase,
@@ -410,7 +932,10 @@ where
     #[tracing::instrument(skip_all)]
     async fn with_deps(deps: &D) -> anyhow::Result<Self> {
         let settings = deps.settings();
-        let runtime = Runtime::new(deps.event_loop(), settings).await?;
+        let runtime = match deps.event_loop() {
+            Some(event_loop) => Runtime::new(event_loop, settings).await?,
+            None => Runtime::new_headless(settings, settings.offscreen_size).await?,
+        };
 
         let mut database = GpuObjectDatabase::default();
 
@@ -418,10 +943,10 @@ where
             .with_label("camera-bind-group-layout")
             .add_bind_group_layout_entry(
                 0,
-                ShaderStages::VERTEX,
+                ShaderStages::VERTEX | ShaderStages::FRAGMENT,
                 BindingType::Buffer {
                     ty: BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
+                    has_dynamic_offset: true,
                     min_binding_size: BufferSize::new(size_of::<CameraUniform>() as _),
                 },
             )
@@ -432,10 +957,52 @@ where
             .add_bind_group_layout_entry(
                 0,
                 ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: BufferSize::new(
+                        (size_of::<LightBufferHeader>() + size_of::<LightUniform>()) as _,
+                    ),
+                },
+            )
+            .add_bind_group_layout_entry(
+                1,
+                ShaderStages::FRAGMENT,
+                BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+            )
+            .add_bind_group_layout_entry(
+                2,
+                ShaderStages::FRAGMENT,
+                BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+            )
+            .submit();
+
+        let shadow_bind_group_layout = BindGroupLayoutBuilder::new(&runtime, &mut database)
+            .with_label("shadow-bind-group-layout")
+            .add_bind_group_layout_entry(
+                0,
+                ShaderStages::VERTEX,
                 BindingType::Buffer {
                     ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: BufferSize::new(size_of::<ShadowUniform>() as _),
+                },
+            )
+            .submit();
+
+        let joint_bind_group_layout = BindGroupLayoutBuilder::new(&runtime, &mut database)
+            .with_label("joint-bind-group-layout")
+            .add_bind_group_layout_entry(
+                0,
+                ShaderStages::VERTEX,
+                BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: true },
                     has_dynamic_offset: false,
-                    min_binding_size: BufferSize::new(size_of::<LightUniform>() as _),
+                    min_binding_size: BufferSize::new(size_of::<[[f32; 4]; 4]>() as _),
                 },
             )
             .submit();
@@ -465,23 +1032,125 @@ where
                     min_binding_size: BufferSize::new(size_of::<MaterialUniform>() as _),
                 },
             )
+            .add_bind_group_layout_entry(
+                3,
+                ShaderStages::FRAGMENT,
+                BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+            )
+            .add_bind_group_layout_entry(
+                4,
+                ShaderStages::FRAGMENT,
+                BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+            )
+            .submit();
+
+        // The source texture, its sampler, the effect parameters and an additional texture, such as
+        // the bloom that is combined with the scene
+        let post_process_bind_group_layout = BindGroupLayoutBuilder::new(&runtime, &mut database)
+            .with_label("post-process-bind-group-layout")
+            .add_bind_group_layout_entry(
+                0,
+                ShaderStages::FRAGMENT,
+                BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+            )
+            .add_bind_group_layout_entry(
+                1,
+                ShaderStages::FRAGMENT,
+                BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            )
+            .add_bind_group_layout_entry(
+                2,
+                ShaderStages::FRAGMENT,
+                BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: BufferSize::new(size_of::<PostProcessUniform>() as _),
+                },
+            )
+            .add_bind_group_layout_entry(
+                3,
+                ShaderStages::FRAGMENT,
+                BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+            )
             .submit();
 
         let depth_texture = Self::create_depth_texture_int(&runtime, &mut database, settings, DEPTH_TEXTURE_LABEL);
         let depth_texture_view = Self::create_texture_view_int(&mut database, DEPTH_TEXTURE_VIEW_LABEL, depth_texture);
 
+        let default_normal_texture = Self::create_gpu_texture_int(
+            &runtime,
+            &mut database,
+            settings,
+            Some("default-normal"),
+            &TextureData::Image(image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+                1,
+                1,
+                image::Rgba([128, 128, 255, 255]),
+            ))),
+            false,
+            &SamplerSettings::default(),
+        );
+        let default_specular_texture = Self::create_gpu_texture_int(
+            &runtime,
+            &mut database,
+            settings,
+            Some("default-specular"),
+            &TextureData::Image(image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+                1,
+                1,
+                image::Rgba([255, 255, 255, 255]),
+            ))),
+            false,
+            &SamplerSettings::default(),
+        );
+
+        let mut internal = InternalRuntimeData {
+            camera_bind_group_layout,
+            light_bind_group_layout,
+            shadow_bind_group_layout,
+            joint_bind_group_layout,
+            material_bind_group_layout,
+            post_process_bind_group_layout,
+            depth_texture,
+            depth_texture_view,
+            default_normal_texture,
+            default_specular_texture,
+            default_material: None,
+            instances: Urn::default(),
+            handles: Vec::new(),
+            shader_variants: HashMap::new(),
+            transient_textures: Vec::new(),
+            gpu_timer: GpuTimer::new(&runtime.device, &runtime.queue),
+        };
+        internal.default_material = Some(Self::create_gpu_material_int(
+            &runtime,
+            &mut database,
+            settings,
+            &internal,
+            &CpuMaterial::with_color(Some("default-material".into()), [1.0, 1.0, 1.0, 1.0]),
+        ));
+
         Ok(Graphics {
             settings: settings.clone(),
             runtime,
             database,
-            internal: InternalRuntimeData {
-                camera_bind_group_layout,
-                light_bind_group_layout,
-                material_bind_group_layout,
-                depth_texture,
-                depth_texture_view,
-                instances: Urn::default(),
-            },
+            internal,
         })
     }
 }
@@ -1,19 +1,83 @@
+use std::collections::HashMap;
+
 use crate::Graphics;
-use crate::assets::cpu_model::CpuModel;
+use crate::assets::cpu_model::{CpuModel, CpuModelSet};
+use crate::assets::gltf_import;
+use crate::assets::primitives::Primitive;
 use crate::base::gpu_model::GpuModel;
-use crate::components::renderable::Renderable;
+use crate::components::renderable::{Renderable, RenderableSource};
 use anyhow::Context;
-use assam::AssetDatabase;
+use assam::{AssetDatabase, AssetId};
 use ecs::Resources;
 
-/// Load a new [`GpuModel`] from an asset known to [`AssetDatabase`]. Automatically instance the model if it is already present in the model database of [`Graphics`] by searching for matching [`Renderable`].
-pub async fn load_instanced_gpu_model(res: &Resources, group: &str, name: &str) -> anyhow::Result<GpuModel> {
+/// Keeps the model sets loaded while instancing meshes of model files that contain several, such
+/// that each file is parsed only once. Drop the cache once the meshes are loaded.
+#[derive(Debug, Default)]
+pub struct ModelSetCache {
+    sets: HashMap<AssetId, CpuModelSet>,
+}
+
+impl ModelSetCache {
+    /// Returns a mesh of a [`CpuModelSet`], which is loaded from the [`AssetDatabase`] on first use
+    pub async fn load_mesh(
+        &mut self,
+        res: &Resources,
+        group: &str,
+        name: &str,
+        mesh: usize,
+    ) -> anyhow::Result<&CpuModel> {
+        let asset_id = AssetId::new(group, name);
+        if !self.sets.contains_key(&asset_id) {
+            let set = res
+                .read::<AssetDatabase>()
+                .load_asset::<CpuModelSet, _>(res, group, name)
+                .await
+                .with_context(|| format!("Loading CpuModelSet from group {} and name {}", group, name))?;
+            self.sets.insert(asset_id.clone(), set);
+        }
+
+        self.sets[&asset_id]
+            .models
+            .get(mesh)
+            .ok_or_else(|| gltf_import::Error::NoSuchMesh(mesh).into())
+    }
+}
+
+/// Load a new [`GpuModel`] from an asset known to [`AssetDatabase`]. Automatically instance the model if it is already present in the model database of [`Graphics`] by searching for matching [`Renderable`]. If `mesh` is specified, only that mesh of a glTF model is loaded.
+pub async fn load_instanced_gpu_model(
+    res: &Resources,
+    group: &str,
+    name: &str,
+    mesh: Option<usize>,
+) -> anyhow::Result<GpuModel> {
+    load_instanced_gpu_model_with_cache(res, group, name, mesh, &mut ModelSetCache::default()).await
+}
+
+/// Like [`load_instanced_gpu_model`], but meshes of glTF models are taken from `cache`, such that
+/// loading many meshes of the same model parses it once
+pub async fn load_instanced_gpu_model_with_cache(
+    res: &Resources,
+    group: &str,
+    name: &str,
+    mesh: Option<usize>,
+    cache: &mut ModelSetCache,
+) -> anyhow::Result<GpuModel> {
     let instancing_candidate = res
         .iter_r::<Renderable>()
-        .find(|(_, ren)| ren.group == group && ren.name == name);
+        .find(|(_, ren)| {
+            matches!(&ren.source, RenderableSource::Asset { group: g, name: n, mesh: m } if g == group && n == name && *m == mesh)
+        });
 
     if let Some((_, ren)) = instancing_candidate {
         Ok(res.write::<Graphics>().create_instanced_gpu_model(&ren.model))
+    } else if let Some(mesh) = mesh {
+        let cpu_model = cache.load_mesh(res, group, name, mesh).await.with_context(|| {
+            format!(
+                "Loading mesh {} of CpuModel from group {} and name {}",
+                mesh, group, name
+            )
+        })?;
+        Ok(res.write::<Graphics>().create_gpu_model(cpu_model))
     } else {
         let cpu_model = res
             .read::<AssetDatabase>()
@@ -23,3 +87,20 @@ pub async fn load_instanced_gpu_model(res: &Resources, group: &str, name: &str)
         Ok(res.write::<Graphics>().create_gpu_model(&cpu_model))
     }
 }
+
+/// Generate a new [`GpuModel`] from a [`Primitive`] without materials. Automatically instance the model if a [`Renderable`] with an equal primitive is already present.
+pub fn load_instanced_primitive(res: &Resources, primitive: &Primitive) -> GpuModel {
+    let instancing_candidate = res
+        .iter_r::<Renderable>()
+        .find(|(_, ren)| matches!(&ren.source, RenderableSource::Primitive { primitive: p } if p == primitive));
+
+    if let Some((_, ren)) = instancing_candidate {
+        res.write::<Graphics>().create_instanced_gpu_model(&ren.model)
+    } else {
+        let cpu_model = CpuModel {
+            mesh: primitive.to_cpu_mesh(),
+            materials: Vec::new(),
+        };
+        res.write::<Graphics>().create_gpu_model(&cpu_model)
+    }
+}
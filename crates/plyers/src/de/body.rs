@@ -23,6 +23,7 @@ use super::{
 };
 
 type PropVals = BTreeMap<PropertyId, (Primitive, Values)>;
+type ListLengths = BTreeMap<PropertyId, Vec<usize>>;
 
 fn ascii_count_fct<'a, E>(_count_type: CountType) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], usize, E>
 where
@@ -236,7 +237,7 @@ fn properties_fct<'a, 'b, F1, F2, P1, P2, E>(
     num_fn: &'a F2,
     properties: &'b BTreeMap<PropertyId, PropertyDescriptor>,
     repetitions: usize,
-) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], PropVals, E> + 'b
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], (PropVals, ListLengths), E> + 'b
 where
     'a: 'b,
     F1: Fn(CountType) -> P1,
@@ -249,49 +250,60 @@ where
 
     context(
         "plyers::de::body::properties_fct",
-        fold_exact(
-            properties.len().checked_mul(repetitions).unwrap_or(0),
-            move |input| {
-                let (&p_id, p_desc) = p_iter.next().unwrap();
+        map(
+            fold_exact(
+                properties.len().checked_mul(repetitions).unwrap_or(0),
+                move |input| {
+                    let (&p_id, p_desc) = p_iter.next().unwrap();
 
-                match p_desc {
-                    PropertyDescriptor::Scalar { data_type, name, .. } => {
-                        tracing::trace!("Parsing property {} as scalar data with type {}", name, data_type);
-                        map(property_scalar_fct(num_fn, *data_type), |p| {
-                            (p_id, Primitive::Single, *data_type, vec![p])
-                        })(input)
-                    }
-                    PropertyDescriptor::List {
-                        count_type,
-                        data_type,
-                        name,
-                        ..
-                    } => {
-                        tracing::trace!(
-                            "Parsing property {} as list data with type {} and count type {}",
-                            name,
+                    match p_desc {
+                        PropertyDescriptor::Scalar { data_type, name, .. } => {
+                            tracing::trace!("Parsing property {} as scalar data with type {}", name, data_type);
+                            map(property_scalar_fct(num_fn, *data_type), |p| {
+                                (p_id, Primitive::Single, *data_type, vec![p])
+                            })(input)
+                        }
+                        PropertyDescriptor::List {
+                            count_type,
                             data_type,
-                            count_type
-                        );
-                        map(property_list_fct(cnt_fn, num_fn, *count_type, *data_type), |ps| {
-                            let prim = Primitive::from(ps.len());
-                            (p_id, prim, *data_type, ps)
-                        })(input)
+                            name,
+                            ..
+                        } => {
+                            tracing::trace!(
+                                "Parsing property {} as list data with type {} and count type {}",
+                                name,
+                                data_type,
+                                count_type
+                            );
+                            map(property_list_fct(cnt_fn, num_fn, *count_type, *data_type), |ps| {
+                                let prim = Primitive::from(ps.len());
+                                (p_id, prim, *data_type, ps)
+                            })(input)
+                        }
                     }
-                }
-            },
-            BTreeMap::<PropertyId, (Primitive, Values)>::new,
-            |mut p_acc, (p_id, prim, dt, p)| {
-                if let std::collections::btree_map::Entry::Vacant(e) = p_acc.entry(p_id) {
-                    e.insert((prim, (dt, p).try_into().unwrap()));
-                } else if let Some((prim_acc, p_acc)) = p_acc.get_mut(&p_id) {
-                    if prim_acc != &prim {
-                        *prim_acc = Primitive::Mixed;
+                },
+                || (PropVals::new(), ListLengths::new()),
+                |(mut p_acc, mut l_acc), (p_id, prim, dt, p)| {
+                    if matches!(properties[&p_id], PropertyDescriptor::List { .. }) {
+                        l_acc.entry(p_id).or_default().push(p.len());
+                    }
+
+                    if let std::collections::btree_map::Entry::Vacant(e) = p_acc.entry(p_id) {
+                        e.insert((prim, (dt, p).try_into().unwrap()));
+                    } else if let Some((prim_acc, p_acc)) = p_acc.get_mut(&p_id) {
+                        if prim_acc != &prim {
+                            *prim_acc = Primitive::Mixed;
+                        }
+                        p_acc.try_extend(p).unwrap();
                     }
-                    p_acc.try_extend(p).unwrap();
-                }
 
-                p_acc
+                    (p_acc, l_acc)
+                },
+            ),
+            |(p_acc, mut l_acc)| {
+                // The lengths of uniform lists follow from their primitive
+                l_acc.retain(|p_id, _| p_acc.get(p_id).is_some_and(|(prim, _)| prim == &Primitive::Mixed));
+                (p_acc, l_acc)
             },
         ),
     )
@@ -301,7 +313,7 @@ fn elements_fct<'a, 'b, F1, F2, P1, P2, E>(
     cnt_fn: &'a F1,
     num_fn: &'a F2,
     elements: &'b BTreeMap<ElementId, ElementDescriptor>,
-) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], PropVals, E> + 'b
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], (PropVals, ListLengths), E> + 'b
 where
     'a: 'b,
     F1: Fn(CountType) -> P1,
@@ -324,10 +336,11 @@ where
                     properties_fct(cnt_fn, num_fn, &e_desc.properties, e_desc.count),
                 )(input)
             },
-            BTreeMap::<PropertyId, (Primitive, Values)>::new,
-            |mut p_acc, e_values| {
+            || (PropVals::new(), ListLengths::new()),
+            |(mut p_acc, mut l_acc), (e_values, e_lengths)| {
                 p_acc.extend(e_values);
-                p_acc
+                l_acc.extend(e_lengths);
+                (p_acc, l_acc)
             },
         ),
     )
@@ -338,7 +351,7 @@ pub fn body_fct<
     E: ParseError<&'a [u8]> + FromExternalError<&'a [u8], ParseNumError> + ContextError<&'a [u8]> + 'a,
 >(
     ply: PlyDescriptor,
-) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], PropVals, E> {
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], (PropVals, ListLengths), E> {
     context("plyers::de::body::body_fct", move |input| match ply.format_type {
         FormatType::Ascii => {
             tracing::debug!("Parsing PLY data as ASCII");
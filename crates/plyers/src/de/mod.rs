@@ -46,11 +46,12 @@ pub fn parse_ply<
             tracing::debug!("Completed PLY header parsing, continuing to the body");
             context(
                 "plyers::de::parse_ply#1",
-                map(body_fct(descriptor.clone()), move |data| {
+                map(body_fct(descriptor.clone()), move |(data, list_lengths)| {
                     tracing::debug!("Completed PLY body parsing, assembling output data");
                     Ply {
                         descriptor: descriptor.clone(),
                         data,
+                        list_lengths,
                     }
                 }),
             )
@@ -114,6 +115,7 @@ mod tests {
                         obj_info: Vec::new()
                     },
                     data: expected_data,
+                    list_lengths: BTreeMap::new(),
                 }
             ))
         );
@@ -184,6 +186,7 @@ mod tests {
                         ]
                     },
                     data: expected_data,
+                    list_lengths: BTreeMap::new(),
                 }
             ))
         );
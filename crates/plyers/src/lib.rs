@@ -792,6 +792,7 @@ mod tests {
             ]
             .into_iter()
             .collect::<BTreeMap<_, _>>(),
+            list_lengths: BTreeMap::new(),
         };
 
         eprintln!("{:#?} \n {:#?}", &ply, &expected);
@@ -799,6 +800,43 @@ mod tests {
         assert_eq!(ply, expected);
     }
 
+    #[test]
+    fn parse_ply_records_the_lengths_of_mixed_lists() {
+        let ply = parse_ply(
+            b"ply
+format ascii 1.0
+element face 3
+property list uchar uint vertex_indices
+end_header
+3 0 1 2
+4 0 1 2 3
+5 0 1 2 3 4
+",
+        )
+        .unwrap();
+
+        assert_eq!(ply.data[&PropertyId(0)].0, Primitive::Mixed);
+        assert_eq!(ply.list_lengths[&PropertyId(0)], vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn parse_ply_omits_the_lengths_of_uniform_lists() {
+        let ply = parse_ply(
+            b"ply
+format ascii 1.0
+element face 2
+property list uchar uint vertex_indices
+end_header
+3 0 1 2
+3 2 1 0
+",
+        )
+        .unwrap();
+
+        assert_eq!(ply.data[&PropertyId(0)].0, Primitive::Triangles);
+        assert!(ply.list_lengths.is_empty());
+    }
+
     #[rstest::rstest]
     fn load_ply_succeeds_for_test_files(#[files("tests/valid/*.ply")] path: PathBuf) {
         if let Err(e) = load_ply(&path) {
@@ -20,6 +20,7 @@ pub const T_PROPERTY: &str = "t";
 pub const U_PROPERTY: &str = "u";
 pub const V_PROPERTY: &str = "v";
 pub const VERTEX_INDICES_LIST_PROPERTY: &str = "vertex_indices";
+pub const MATERIAL_INDEX_PROPERTY: &str = "material_index";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
 #[error("the sequence of values contains inconsistent data types")]
@@ -446,6 +447,9 @@ impl TryFrom<(DataType, Vec<Value>)> for Values {
 pub struct Ply {
     pub descriptor: PlyDescriptor,
     pub data: BTreeMap<PropertyId, (Primitive, Values)>,
+    /// The number of values in each list of the list properties whose primitive is
+    /// [`Primitive::Mixed`]
+    pub list_lengths: BTreeMap<PropertyId, Vec<usize>>,
 }
 
 impl Ply {
@@ -507,6 +511,7 @@ mod tests {
         let _ = Ply {
             descriptor: PlyDescriptor::default(),
             data: BTreeMap::<PropertyId, (Primitive, Values)>::default(),
+            list_lengths: BTreeMap::<PropertyId, Vec<usize>>::default(),
         };
     }
 
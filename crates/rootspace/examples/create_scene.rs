@@ -1,6 +1,7 @@
 use assam::{AssetDatabase, AssetDatabaseDeps};
 use clap::Parser;
 use glamour::vec::Vec4;
+use griffon::assets::primitives::Primitive;
 use griffon::components::renderable::RenderableSource;
 use rootspace::{Camera, Scene, Transform, WithDependencies};
 
@@ -67,9 +68,10 @@ async fn main() -> anyhow::Result<()> {
                 .with_translation(Vec4::new_vector(1.0, 0.0, 0.0))
                 .build(),
         )
-        .with_renderable(RenderableSource {
+        .with_renderable(RenderableSource::Asset {
             group: "models".into(),
             name: "quad.ply".into(),
+            mesh: None,
         })
         .submit();
 
@@ -82,12 +84,31 @@ async fn main() -> anyhow::Result<()> {
                 .with_scale(0.25f32)
                 .build(),
         )
-        .with_renderable(RenderableSource {
+        .with_renderable(RenderableSource::Asset {
             group: "models".into(),
             name: "triangle.ply".into(),
+            mesh: None,
         })
         .submit();
 
+    scene
+        .create_entity()
+        .with_transform(
+            Transform::builder()
+                .with_translation(Vec4::new_vector(0.0, 1.5, 0.0))
+                .build(),
+        )
+        .with_renderable(
+            Primitive::Capsule {
+                radius: 0.25,
+                height: 1.0,
+                segments: 16,
+                rings: 4,
+            }
+            .into(),
+        )
+        .submit();
+
     adb.save_asset(&scene, "scenes", "test.cbor").await?;
 
     Ok(())
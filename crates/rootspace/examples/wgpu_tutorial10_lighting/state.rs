@@ -308,8 +308,8 @@ pub struct Dependencies<'a>(&'a EventLoopWindowTarget<()>, &'a Settings);
 impl GraphicsDeps for Dependencies<'_> {
     type CustomEvent = ();
 
-    fn event_loop(&self) -> &EventLoopWindowTarget<()> {
-        self.0
+    fn event_loop(&self) -> Option<&EventLoopWindowTarget<()>> {
+        Some(self.0)
     }
 
     fn settings(&self) -> &Settings {
@@ -1,11 +1,13 @@
+use anyhow::Context;
 use griffon::winit::event_loop::{EventLoop, EventLoopWindowTarget};
+use std::path::Path;
 use std::sync::Arc;
 use tokio::runtime::{Builder as RuntimeBuilder, Runtime};
 
-use crate::{Orchestrator, OrchestratorDeps, RpcDeps};
-use assam::AssetDatabaseDeps;
-use ecs::Reg;
-use griffon::{GraphicsDeps, Settings};
+use crate::{Orchestrator, OrchestratorDeps, RpcDeps, assets::validation::validate_assets};
+use assam::{AssetDatabase, AssetDatabaseDeps, ValidationReport};
+use ecs::{Reg, WithDependencies};
+use griffon::{GraphicsDeps, PostProcessSettings, Settings, WindowSettings};
 
 #[derive(Debug)]
 pub struct App {
@@ -23,18 +25,65 @@ impl App {
         }
     }
 
+    /// Applies the settings of a TOML file, see [`SettingsFile`]
+    pub fn with_settings_file<P: AsRef<Path>>(self, path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let data =
+            std::fs::read_to_string(path).with_context(|| format!("Loading the settings file '{}'", path.display()))?;
+        let settings: SettingsFile =
+            toml::from_str(&data).with_context(|| format!("Parsing the settings file '{}'", path.display()))?;
+
+        let mut app = self
+            .with_window_settings(settings.window)
+            .with_post_process_settings(settings.post_process);
+        if let Some(vsync) = settings.vsync {
+            app = app.with_vsync(vsync);
+        }
+        Ok(app)
+    }
+
+    pub fn with_window_settings(mut self, window: WindowSettings) -> Self {
+        self.graphics_settings.window = window;
+        self
+    }
+
+    pub fn with_post_process_settings(mut self, post_process: PostProcessSettings) -> Self {
+        self.graphics_settings.post_process = post_process;
+        self
+    }
+
+    /// Chooses between presenting frames synchronized to the display and as fast as possible
+    pub fn with_vsync(mut self, vsync: bool) -> Self {
+        self.graphics_settings.present_mode = if vsync {
+            griffon::wgpu::PresentMode::AutoVsync
+        } else {
+            griffon::wgpu::PresentMode::AutoNoVsync
+        };
+        self
+    }
+
+    pub fn window_settings(&self) -> &WindowSettings {
+        &self.graphics_settings.window
+    }
+
+    pub fn post_process_settings(&self) -> &PostProcessSettings {
+        &self.graphics_settings.post_process
+    }
+
     pub fn run(self) -> anyhow::Result<()> {
         let App {
             name,
             force_init,
-            graphics_settings,
+            mut graphics_settings,
         } = self;
+        // Windows are titled with the name of the game unless configured otherwise
+        graphics_settings.window.title.get_or_insert_with(|| name.clone());
         let rt = Arc::new(RuntimeBuilder::new_multi_thread().enable_all().build()?);
         let event_loop = EventLoop::new()?;
 
         let deps = Deps {
             rt: rt.clone(),
-            event_loop: &event_loop,
+            event_loop: Some(&event_loop),
             name: &name,
             force_init,
             graphics_settings: &graphics_settings,
@@ -52,12 +101,75 @@ impl App {
         event_loop.run(state.start())?;
         Ok(())
     }
+
+    /// Renders the given number of frames headless, without opening a window, and returns the
+    /// last frame
+    pub fn render(self, frames: usize) -> anyhow::Result<griffon::image::RgbaImage> {
+        let App {
+            name,
+            force_init,
+            graphics_settings,
+        } = self;
+        let rt = Arc::new(RuntimeBuilder::new_multi_thread().enable_all().build()?);
+
+        let deps = Deps {
+            rt: rt.clone(),
+            event_loop: None,
+            name: &name,
+            force_init,
+            graphics_settings: &graphics_settings,
+        };
+
+        rt.block_on(async move {
+            let mut state = Orchestrator::with_dependencies::<Reg![], Reg![], Reg![], Reg![], _>(&deps).await?;
+            state.run_frames(frames).await;
+            state.read_frame()
+        })
+    }
+
+    /// Validates the asset tree of the game without opening a window or starting the engine
+    pub fn validate(self) -> anyhow::Result<ValidationReport> {
+        let rt = RuntimeBuilder::new_current_thread().enable_all().build()?;
+        let deps = ValidationDeps {
+            name: &self.name,
+            force_init: self.force_init,
+        };
+
+        rt.block_on(async move {
+            let adb = AssetDatabase::with_deps(&deps).await?;
+            validate_assets(&adb, deps.scene_group()).await
+        })
+    }
+}
+
+/// The user-editable settings of an [`App`], for example:
+///
+/// ```toml
+/// vsync = false
+///
+/// [window]
+/// mode = "windowed"
+/// title = "Rootspace"
+/// inner_size = { width = 1280, height = 720 }
+/// position = { x = 100, y = 100 }
+/// resizable = true
+/// monitor = 0
+///
+/// [post_process.bloom]
+/// intensity = 0.2
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SettingsFile {
+    pub window: WindowSettings,
+    pub vsync: Option<bool>,
+    pub post_process: PostProcessSettings,
 }
 
 #[derive(Debug)]
 struct Deps<'a> {
     rt: Arc<Runtime>,
-    event_loop: &'a EventLoop<()>,
+    event_loop: Option<&'a EventLoop<()>>,
     name: &'a str,
     force_init: bool,
     graphics_settings: &'a Settings,
@@ -66,8 +178,8 @@ struct Deps<'a> {
 impl<'a> GraphicsDeps for Deps<'a> {
     type CustomEvent = ();
 
-    fn event_loop(&self) -> &EventLoopWindowTarget<Self::CustomEvent> {
-        self.event_loop
+    fn event_loop(&self) -> Option<&EventLoopWindowTarget<Self::CustomEvent>> {
+        self.event_loop.map(|el| &**el)
     }
 
     fn settings(&self) -> &Settings {
@@ -100,3 +212,102 @@ impl<'a> OrchestratorDeps for Deps<'a> {
 }
 
 impl<'a> RpcDeps for Deps<'a> {}
+
+#[derive(Debug)]
+struct ValidationDeps<'a> {
+    name: &'a str,
+    force_init: bool,
+}
+
+impl ValidationDeps<'_> {
+    fn scene_group(&self) -> &str {
+        "scenes"
+    }
+}
+
+impl<'a> AssetDatabaseDeps for ValidationDeps<'a> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn force_init(&self) -> bool {
+        self.force_init
+    }
+
+    fn within_repo(&self) -> bool {
+        cfg!(debug_assertions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use griffon::WindowMode;
+
+    fn settings_file(contents: &str) -> tempfile::NamedTempFile {
+        let file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        std::fs::write(file.path(), contents).unwrap();
+        file
+    }
+
+    #[test]
+    fn settings_file_overrides_the_window_and_post_process_settings() {
+        let file = settings_file(
+            r#"
+            vsync = false
+
+            [window]
+            mode = "exclusive"
+            monitor = 1
+
+            [post_process.fxaa]
+            enabled = false
+            "#,
+        );
+
+        let app = App::new("test").with_settings_file(file.path()).unwrap();
+        assert_eq!(app.window_settings().mode, WindowMode::Exclusive);
+        assert_eq!(app.window_settings().monitor, Some(1));
+        assert_eq!(
+            app.graphics_settings.present_mode,
+            griffon::wgpu::PresentMode::AutoNoVsync
+        );
+        assert!(!app.post_process_settings().fxaa.enabled);
+        assert!(app.post_process_settings().bloom.enabled);
+    }
+
+    #[test]
+    fn omitted_settings_fall_back_to_their_defaults() {
+        let file = settings_file("[window]\ntitle = \"Test\"\n");
+        let defaults = Settings::default();
+
+        let app = App::new("test").with_settings_file(file.path()).unwrap();
+        assert_eq!(
+            app.window_settings(),
+            &WindowSettings {
+                title: Some(String::from("Test")),
+                ..defaults.window
+            }
+        );
+        assert_eq!(app.graphics_settings.present_mode, defaults.present_mode);
+        assert_eq!(app.post_process_settings(), &defaults.post_process);
+    }
+
+    #[test]
+    fn missing_or_malformed_settings_files_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("settings.toml");
+        let error = App::new("test").with_settings_file(&missing).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Loading the settings file '{}'", missing.display())
+        );
+
+        let file = settings_file("[window]\nmode = \"floating\"\n");
+        let error = App::new("test").with_settings_file(file.path()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Parsing the settings file '{}'", file.path().display())
+        );
+    }
+}
@@ -1,9 +1,15 @@
 use std::{collections::BTreeMap, path::Path};
 
-use crate::components::{camera::Camera, debug_animate::DebugAnimate, info::Info, transform::Transform};
+use crate::components::{
+    animation::Animation, camera::Camera, debug_animate::DebugAnimate, info::Info, skeleton::Skeleton,
+    transform::Transform,
+};
 use anyhow::{Context, anyhow};
-use assam::{AssetDatabase, LoadAsset, SaveAsset};
+use assam::{AssetDatabase, AssetDependencies, AssetId, LoadAsset, SaveAsset};
 use ecs::{Entities, Entity, Index, Resources, Storage};
+use glamour::{mat::Mat4, quat::Quat, vec::Vec4};
+use griffon::ModelSetCache;
+use griffon::assets::gltf_import::CpuNodeTree;
 use griffon::components::light::{Light, LightSource};
 use griffon::components::renderable::{Renderable, RenderableSource};
 use griffon::resources::Graphics;
@@ -19,6 +25,10 @@ pub struct Scene {
     transforms: BTreeMap<Index, Transform>,
     renderables: BTreeMap<Index, RenderableSource>,
     lights: BTreeMap<Index, LightSource>,
+    #[serde(default)]
+    skeletons: BTreeMap<Index, Skeleton>,
+    #[serde(default)]
+    animations: BTreeMap<Index, Animation>,
 }
 
 impl Scene {
@@ -49,15 +59,7 @@ impl Scene {
             renderables: res
                 .read_components::<Renderable>()
                 .indexed_iter()
-                .map(|(i, r)| {
-                    (
-                        i,
-                        RenderableSource {
-                            group: r.group.clone(),
-                            name: r.name.clone(),
-                        },
-                    )
-                })
+                .map(|(i, r)| (i, r.source.clone()))
                 .collect(),
             lights: res
                 .read_components::<Light>()
@@ -74,17 +76,135 @@ impl Scene {
                             specular_color: r.specular_color,
                             ambient_intensity: r.ambient_intensity,
                             point_intensity: r.point_intensity,
+                            kind: r.kind,
+                            direction: r.direction,
+                            attenuation: r.attenuation,
+                            inner_cone_angle: r.inner_cone_angle,
+                            outer_cone_angle: r.outer_cone_angle,
+                            casts_shadows: r.casts_shadows,
+                            shadow_bias: r.shadow_bias,
+                            shadow_extent: r.shadow_extent,
+                            shadow_range: r.shadow_range,
                         },
                     )
                 })
                 .collect(),
+            skeletons: res
+                .read_components::<Skeleton>()
+                .indexed_iter()
+                .map(|(i, s)| (i, s.clone()))
+                .collect(),
+            animations: res
+                .read_components::<Animation>()
+                .indexed_iter()
+                .map(|(i, a)| (i, a.clone()))
+                .collect(),
         }
     }
 
+    /// Returns the assets referenced by the renderables and lights of this scene. Procedural
+    /// renderables do not refer to any asset.
+    pub fn asset_dependencies(&self) -> Vec<AssetId> {
+        self.renderables
+            .values()
+            .filter_map(|r| match r {
+                RenderableSource::Asset { group, name, .. } => Some(AssetId::new(group, name)),
+                RenderableSource::Primitive { .. } => None,
+            })
+            .chain(self.lights.values().map(|l| AssetId::new(&l.group, &l.name)))
+            .collect()
+    }
+
     pub fn create_entity(&mut self) -> EntityBuilder<'_> {
         EntityBuilder::new(self)
     }
 
+    /// Expands the node hierarchy of a glTF model into a subtree of entities with their local
+    /// transforms. Nodes with a mesh become renderables of that mesh, and skinned meshes receive
+    /// a skeleton of their joint entities. The animations of the model are attached to the root
+    /// entity, which plays the first one. Returns the root entity of the subtree.
+    pub fn add_node_tree<S: AsRef<str>>(
+        &mut self,
+        parent: Option<Index>,
+        group: S,
+        name: S,
+        tree: &CpuNodeTree,
+    ) -> Entity {
+        let (group, name) = (group.as_ref(), name.as_ref());
+
+        let mut root = self
+            .create_entity()
+            .with_info(Info::new(tree.label.as_deref().unwrap_or(name), ""))
+            .with_transform(Transform::builder().build());
+        if let Some(parent) = parent {
+            root = root.with_parent(parent);
+        }
+        let root = root.submit();
+
+        let mut entities: Vec<Entity> = Vec::with_capacity(tree.nodes.len());
+        for (i, node) in tree.nodes.iter().enumerate() {
+            let [sx, sy, sz] = node.scale;
+            if (sx - sy).abs() > f32::EPSILON || (sx - sz).abs() > f32::EPSILON {
+                tracing::warn!(
+                    "Node {} of {}/{} has a non-uniform scale, which is approximated by its mean",
+                    i,
+                    group,
+                    name
+                );
+            }
+
+            let [x, y, z] = node.translation;
+            let [qi, qj, qk, qw] = node.rotation;
+            let transform = Transform::builder()
+                .with_translation(Vec4::new_point(x, y, z))
+                .with_orientation(Quat::new(qw, qi, qj, qk))
+                .with_scale((sx + sy + sz) / 3.0)
+                .build();
+
+            let node_parent = node.parent.map_or(root, |p| entities[p]);
+            let mut builder = self
+                .create_entity()
+                .with_parent(node_parent.idx())
+                .with_info(Info::new(
+                    node.name.clone().unwrap_or_else(|| format!("node-{i}")),
+                    String::new(),
+                ))
+                .with_transform(transform);
+            if let Some(mesh) = node.mesh {
+                builder = builder.with_renderable(RenderableSource::Asset {
+                    group: group.to_string(),
+                    name: name.to_string(),
+                    mesh: Some(mesh),
+                });
+            }
+
+            entities.push(builder.submit());
+        }
+
+        for (node, entity) in tree.nodes.iter().zip(&entities) {
+            let Some(skin) = node.skin.and_then(|s| tree.skins.get(s)) else {
+                continue;
+            };
+            let joints = skin.joints.iter().map(|&j| entities[j].idx()).collect();
+            let inverse_bind_matrices = skin
+                .inverse_bind_matrices
+                .iter()
+                // Transpose from column-major (glTF) to row-major
+                .map(|&m| Mat4::from(m).t())
+                .collect();
+            self.skeletons
+                .insert(entity.idx(), Skeleton::new(joints, inverse_bind_matrices));
+        }
+
+        if !tree.animations.is_empty() {
+            let mut animation = Animation::new(tree.animations.clone(), entities.iter().map(|e| e.idx()).collect());
+            animation.play(0);
+            self.animations.insert(root.idx(), animation);
+        }
+
+        root
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn submit<S>(mut self, res: &Resources, group: S, name: S) -> anyhow::Result<()>
     where
@@ -116,27 +236,27 @@ impl Scene {
             scene: &Scene,
             entities: &mut Entities,
             hierarchy: &mut Hierarchy<Index>,
-        ) -> BTreeMap<Index, Index> {
-            let mut map: BTreeMap<Index, Index> = BTreeMap::new();
-
+            map: &mut BTreeMap<Index, Index>,
+        ) -> anyhow::Result<()> {
             for i_prev in scene.hierarchy.bfs_iter() {
-                if let Some(anc_prev) = scene.hierarchy.ancestors(i_prev).nth(1) {
-                    let i_new = entities.create().idx();
-                    map.insert(i_prev, i_new);
-
-                    let anc_new = map
-                        .get(&anc_prev)
-                        .expect("A (parent) scene-based entity has no corresponding world entity");
+                let i_new = entities.create().idx();
+                map.insert(i_prev, i_new);
 
+                if let Some(anc_prev) = scene.hierarchy.ancestors(i_prev).nth(1) {
+                    let anc_new = remap(map, anc_prev).context("Looking up the parent of a scene-based entity")?;
                     hierarchy.insert_child(anc_new, i_new);
                 } else {
-                    let i_new = entities.create().idx();
-                    map.insert(i_prev, i_new);
                     hierarchy.insert(i_new);
                 }
             }
 
-            map
+            Ok(())
+        }
+
+        fn remap(map: &BTreeMap<Index, Index>, i_prev: Index) -> anyhow::Result<Index> {
+            map.get(&i_prev)
+                .copied()
+                .ok_or_else(|| anyhow!("The scene-based entity {i_prev} has no corresponding world entity"))
         }
 
         #[tracing::instrument(skip_all)]
@@ -145,6 +265,8 @@ impl Scene {
             map: &BTreeMap<Index, Index>,
             res: &Resources,
         ) -> anyhow::Result<()> {
+            // Renderables of the same glTF model share the parsed model
+            let mut model_sets = ModelSetCache::default();
             for (&i_prev, &i_new) in map {
                 if let Some(info) = scene.infos.get(&i_prev).cloned() {
                     res.write_components::<Info>().insert(i_new, info);
@@ -171,7 +293,7 @@ impl Scene {
                 }
 
                 if let Some(source) = scene.renderables.get(&i_prev) {
-                    let renderable = Renderable::new(res, source).await?;
+                    let renderable = Renderable::with_cache(res, source, &mut model_sets).await?;
                     res.write_components::<Renderable>().insert(i_new, renderable);
                 }
 
@@ -179,6 +301,20 @@ impl Scene {
                     let light = Light::new(res, source).await?;
                     res.write_components::<Light>().insert(i_new, light);
                 }
+
+                if let Some(mut skeleton) = scene.skeletons.get(&i_prev).cloned() {
+                    skeleton
+                        .remap_joints(|j| remap(map, j))
+                        .context("Remapping the joints of a skeleton")?;
+                    res.write_components::<Skeleton>().insert(i_new, skeleton);
+                }
+
+                if let Some(mut animation) = scene.animations.get(&i_prev).cloned() {
+                    animation
+                        .remap_targets(|t| remap(map, t))
+                        .context("Remapping the targets of an animation")?;
+                    res.write_components::<Animation>().insert(i_new, animation);
+                }
             }
 
             Ok(())
@@ -186,7 +322,11 @@ impl Scene {
 
         register_origin(&mut self, group.as_ref(), name.as_ref());
 
-        let map = load_hierarchy_additive(&self, &mut res.write(), &mut res.write());
+        let mut map = BTreeMap::new();
+        if let Err(e) = load_hierarchy_additive(&self, &mut res.write(), &mut res.write(), &mut map) {
+            error_recovery(res, map.values());
+            return Err(e).context("Adding the scene's hierarchy to the existing hierarchy");
+        }
 
         if let Err(e) = load_components_additive(&self, &map, res).await {
             error_recovery(res, map.values());
@@ -214,6 +354,17 @@ impl LoadAsset for Scene {
     }
 }
 
+impl AssetDependencies for Scene {
+    async fn dependencies(_asset: &AssetId, path: &Path) -> anyhow::Result<Vec<AssetId>> {
+        let file = std::fs::File::open(path).with_context(|| format!("Opening the file '{}'", path.display()))?;
+        let reader = std::io::BufReader::new(file);
+
+        let scene = ciborium::de::from_reader::<Scene, _>(reader).context("Loading the Scene")?;
+
+        Ok(scene.asset_dependencies())
+    }
+}
+
 impl SaveAsset for Scene {
     async fn to_path(&self, path: &Path) -> anyhow::Result<()> {
         let file = std::fs::File::create(path).with_context(|| format!("Creating the file '{}'", path.display()))?;
@@ -234,6 +385,8 @@ pub struct EntityBuilder<'a> {
     transform: Option<Transform>,
     renderable: Option<RenderableSource>,
     light: Option<LightSource>,
+    skeleton: Option<Skeleton>,
+    animation: Option<Animation>,
 }
 
 impl<'a> EntityBuilder<'a> {
@@ -247,6 +400,8 @@ impl<'a> EntityBuilder<'a> {
             transform: None,
             renderable: None,
             light: None,
+            skeleton: None,
+            animation: None,
         }
     }
 
@@ -285,6 +440,18 @@ impl<'a> EntityBuilder<'a> {
         self
     }
 
+    /// The joints of the skeleton must be entities of the same scene
+    pub fn with_skeleton(mut self, skel: Skeleton) -> Self {
+        self.skeleton = Some(skel);
+        self
+    }
+
+    /// The targets of the animation must be entities of the same scene
+    pub fn with_animation(mut self, anim: Animation) -> Self {
+        self.animation = Some(anim);
+        self
+    }
+
     pub fn submit(self) -> Entity {
         let e = self.scene.entities.create();
         let i = e.idx();
@@ -319,6 +486,99 @@ impl<'a> EntityBuilder<'a> {
             self.scene.lights.insert(i, light);
         }
 
+        if let Some(skeleton) = self.skeleton {
+            self.scene.skeletons.insert(i, skeleton);
+        }
+
+        if let Some(animation) = self.animation {
+            self.scene.animations.insert(i, animation);
+        }
+
         e
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::transform::GlobalTransforms;
+    use griffon::assets::gltf_import::CpuNode;
+    use griffon::assets::primitives::Primitive;
+
+    #[test]
+    fn nested_nodes_are_transformed_by_their_ancestors() {
+        let node = |parent: Option<usize>, translation: [f32; 3], mesh: Option<usize>| CpuNode {
+            name: None,
+            parent,
+            translation,
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0; 3],
+            mesh,
+            skin: None,
+        };
+        let tree = CpuNodeTree {
+            label: None,
+            nodes: vec![
+                node(None, [1.0, 0.0, 0.0], None),
+                node(Some(0), [0.0, 2.0, 0.0], Some(0)),
+            ],
+            skins: Vec::new(),
+            animations: Vec::new(),
+        };
+
+        let mut scene = Scene::default();
+        let root = scene.add_node_tree(None, "models", "nested.gltf", &tree);
+        // Moving the root of the subtree moves all of its nodes
+        scene.transforms.insert(
+            root.idx(),
+            Transform::builder()
+                .with_translation(Vec4::new_point(10.0, 0.0, 0.0))
+                .build(),
+        );
+
+        let mut transforms = <Transform as ecs::Component>::Storage::default();
+        for (&idx, transform) in &scene.transforms {
+            transforms.insert(idx, transform.clone());
+        }
+        let globals = GlobalTransforms::new(&scene.hierarchy, &transforms);
+
+        let (&nested, _) = scene.renderables.iter().next().unwrap();
+        let matrix = globals.matrix(nested, &scene.transforms[&nested]);
+        assert_eq!([matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]], [11.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn procedural_renderables_survive_serialization_and_have_no_asset_dependencies() {
+        let mut scene = Scene::default();
+        scene
+            .create_entity()
+            .with_renderable(RenderableSource::Asset {
+                group: "models".into(),
+                name: "cube.ply".into(),
+                mesh: None,
+            })
+            .submit();
+        scene
+            .create_entity()
+            .with_renderable(
+                Primitive::Torus {
+                    major_radius: 1.0,
+                    minor_radius: 0.25,
+                    major_segments: 24,
+                    minor_segments: 12,
+                }
+                .into(),
+            )
+            .submit();
+
+        let mut buffer = Vec::new();
+        ciborium::ser::into_writer(&scene, &mut buffer).unwrap();
+        let loaded: Scene = ciborium::de::from_reader(buffer.as_slice()).unwrap();
+
+        assert_eq!(
+            loaded.renderables.values().collect::<Vec<_>>(),
+            scene.renderables.values().collect::<Vec<_>>()
+        );
+        assert_eq!(loaded.asset_dependencies(), vec![AssetId::new("models", "cube.ply")]);
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::{Orchestrator, assets::scene::Scene, systems::renderer};
+use assam::{AssetDatabase, AssetDependencies, AssetId, DependencyGraph, ValidationReport};
+use griffon::assets::cpu_material::{CpuMaterial, MATERIAL_ASSET_GROUP};
+use griffon::assets::cpu_model::{CpuModel, MODEL_ASSET_GROUP, MODEL_FILE_EXTENSIONS};
+use griffon::assets::shader_source::{SHADER_ASSET_GROUP, ShaderSource};
+
+/// Walks every scene in `scene_group` as well as the built-in scenes and shaders, resolves all
+/// models, their companion files, materials, textures and shader includes they depend on, and
+/// compares the resulting dependency graph with the contents of the asset tree. Does not require a
+/// graphics device.
+#[tracing::instrument(skip(adb))]
+pub async fn validate_assets(adb: &AssetDatabase, scene_group: &str) -> anyhow::Result<ValidationReport> {
+    let mut graph = DependencyGraph::default();
+    let mut queue: VecDeque<AssetId> = VecDeque::new();
+
+    for scene in adb.list_assets(scene_group).await? {
+        graph.insert_asset(scene.clone());
+        queue.push_back(scene);
+    }
+
+    let builtin_dependencies = Orchestrator::builtin_scene()
+        .asset_dependencies()
+        .into_iter()
+        .chain(Orchestrator::editor_builtin_scene().asset_dependencies())
+        .chain(
+            renderer::SHADER_ASSETS
+                .iter()
+                .map(|name| AssetId::new(SHADER_ASSET_GROUP, name)),
+        );
+    for dependency in builtin_dependencies {
+        graph.insert_asset(dependency.clone());
+        queue.push_back(dependency);
+    }
+
+    let mut visited: BTreeSet<AssetId> = BTreeSet::new();
+    let mut invalid: BTreeMap<AssetId, String> = BTreeMap::new();
+    while let Some(asset) = queue.pop_front() {
+        if !visited.insert(asset.clone()) {
+            continue;
+        }
+
+        // Assets with invalid names cannot be present and are reported as missing
+        let Ok(path) = adb.find_asset(&asset.group, &asset.name) else {
+            continue;
+        };
+        if !path.is_file() {
+            continue;
+        }
+
+        let is_model = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| MODEL_FILE_EXTENSIONS.contains(&ext));
+
+        let dependencies = if asset.group == scene_group {
+            Scene::dependencies(&asset, &path).await
+        } else if asset.group == MODEL_ASSET_GROUP && is_model {
+            CpuModel::dependencies(&asset, &path).await
+        } else if asset.group == MATERIAL_ASSET_GROUP {
+            CpuMaterial::dependencies(&asset, &path).await
+        } else if asset.group == SHADER_ASSET_GROUP {
+            ShaderSource::dependencies(&asset, &path).await
+        } else {
+            Ok(Vec::new())
+        };
+
+        let dependencies = match dependencies {
+            Ok(dependencies) => dependencies,
+            Err(e) => {
+                invalid.insert(asset, format!("{e:#}"));
+                continue;
+            }
+        };
+
+        for dependency in dependencies {
+            graph.insert_dependency(asset.clone(), dependency.clone());
+            queue.push_back(dependency);
+        }
+    }
+
+    let present = adb.list_all_assets().await?;
+
+    let mut report = ValidationReport::new(&graph, present);
+    report.invalid = invalid;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assam::AssetDatabaseDeps;
+    use ecs::WithDependencies;
+
+    #[derive(Debug)]
+    struct TDeps;
+
+    impl AssetDatabaseDeps for TDeps {
+        fn name(&self) -> &str {
+            "rootspace"
+        }
+
+        fn force_init(&self) -> bool {
+            false
+        }
+
+        fn within_repo(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn shader_includes_are_validated() {
+        let adb = AssetDatabase::with_deps(&TDeps).await.unwrap();
+        let report = validate_assets(&adb, "scenes").await.unwrap();
+
+        let shader_issues = report
+            .missing
+            .keys()
+            .chain(report.unreferenced.iter())
+            .chain(report.invalid.keys())
+            .filter(|asset| asset.group == SHADER_ASSET_GROUP)
+            .collect::<Vec<_>>();
+        assert!(shader_issues.is_empty(), "{shader_issues:?}");
+    }
+}
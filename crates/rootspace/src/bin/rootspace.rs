@@ -1,12 +1,84 @@
 #![recursion_limit = "256"]
 
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use griffon::WindowMode;
+use griffon::winit::dpi::{PhysicalPosition, PhysicalSize};
 use rootspace::App;
 
 #[derive(Debug, Parser)]
 struct Args {
     #[arg(short, long, help = "Select the game to run", default_value = "rootspace")]
     game: String,
+    #[arg(short, long, help = "Read window and display settings from a TOML file")]
+    settings: Option<PathBuf>,
+    #[command(flatten)]
+    window: WindowArgs,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+// Command line flags take precedence over the settings file
+#[derive(Debug, ClapArgs)]
+struct WindowArgs {
+    #[arg(long, help = "Window mode: windowed, borderless or exclusive")]
+    window_mode: Option<WindowMode>,
+    #[arg(long, help = "Title of the window")]
+    title: Option<String>,
+    #[arg(long, value_parser = parse_pair::<u32, 'x'>, help = "Initial size of the window contents, e.g. 1280x720")]
+    size: Option<(u32, u32)>,
+    #[arg(long, value_parser = parse_pair::<i32, ','>, allow_hyphen_values = true, help = "Initial position of the window on its monitor, e.g. 100,100")]
+    position: Option<(i32, i32)>,
+    #[arg(long, help = "Prevent resizing the window")]
+    fixed_size: bool,
+    #[arg(long, help = "Index of the monitor to open the window on")]
+    monitor: Option<usize>,
+    #[arg(long, help = "Present frames synchronized to the display (true or false)")]
+    vsync: Option<bool>,
+}
+
+impl WindowArgs {
+    fn apply(self, app: App) -> App {
+        let mut window = app.window_settings().clone();
+        window.mode = self.window_mode.unwrap_or(window.mode);
+        window.title = self.title.or(window.title);
+        if let Some((width, height)) = self.size {
+            window.inner_size = Some(PhysicalSize::new(width, height));
+        }
+        if let Some((x, y)) = self.position {
+            window.position = Some(PhysicalPosition::new(x, y));
+        }
+        window.resizable &= !self.fixed_size;
+        window.monitor = self.monitor.or(window.monitor);
+
+        let app = app.with_window_settings(window);
+        match self.vsync {
+            Some(vsync) => app.with_vsync(vsync),
+            None => app,
+        }
+    }
+}
+
+fn parse_pair<T: std::str::FromStr, const SEP: char>(s: &str) -> Result<(T, T), String> {
+    s.split_once(SEP)
+        .and_then(|(a, b)| Some((a.trim().parse().ok()?, b.trim().parse().ok()?)))
+        .ok_or_else(|| format!("Expected two numbers separated by '{SEP}'"))
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the game (default)
+    Run,
+    /// Render a number of frames without opening a window and save the last one as an image
+    Render {
+        #[arg(short, long, help = "Number of frames to render", default_value_t = 1)]
+        frames: usize,
+        #[arg(help = "Path of the image file to write")]
+        output: PathBuf,
+    },
+    /// Check the asset tree for missing or unreferenced assets and dependency cycles
+    Validate,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -16,7 +88,21 @@ fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
 
     let args = Args::parse();
-    let app = App::new(&args.game);
-    app.run()?;
+    let mut app = App::new(&args.game);
+    if let Some(settings) = &args.settings {
+        app = app.with_settings_file(settings)?;
+    }
+    let app = args.window.apply(app);
+    match args.command.unwrap_or(Command::Run) {
+        Command::Run => app.run()?,
+        Command::Render { frames, output } => app.render(frames)?.save(&output)?,
+        Command::Validate => {
+            let report = app.validate()?;
+            print!("{report}");
+            if !report.is_ok() {
+                anyhow::bail!("The asset tree of game '{}' failed validation", args.game);
+            }
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+
+use ecs::{Component, Index, VecStorage};
+use griffon::assets::animation_clip::{AnimationClip, NodePose};
+
+/// Playback state of a single clip
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnimationLayer {
+    /// Index into the clips of the [`Animation`]
+    pub clip: usize,
+    /// Playback position in seconds
+    pub time: f32,
+    /// Playback rate, where negative values play the clip backwards
+    pub speed: f32,
+    /// Influence of the layer relative to the other layers that animate the same property
+    pub weight: f32,
+    /// Wraps around at the end of the clip instead of holding its last pose
+    pub looping: bool,
+}
+
+impl AnimationLayer {
+    pub fn new(clip: usize) -> Self {
+        AnimationLayer {
+            clip,
+            time: 0.0,
+            speed: 1.0,
+            weight: 1.0,
+            looping: true,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+}
+
+/// Plays back clips that animate the transforms of a set of target entities, such as the joints
+/// of a [`Skeleton`](super::skeleton::Skeleton). Clips refer to their targets by node index, see
+/// [`AnimationChannel::node`](griffon::assets::animation_clip::AnimationChannel::node).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Animation {
+    clips: Vec<AnimationClip>,
+    /// The entity of each node index
+    targets: Vec<Index>,
+    layers: Vec<AnimationLayer>,
+}
+
+impl Animation {
+    pub fn new(clips: Vec<AnimationClip>, targets: Vec<Index>) -> Self {
+        Animation {
+            clips,
+            targets,
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn clips(&self) -> &[AnimationClip] {
+        &self.clips
+    }
+
+    /// Returns the index of the first clip with the given name
+    pub fn find_clip(&self, name: &str) -> Option<usize> {
+        self.clips.iter().position(|c| c.name.as_deref() == Some(name))
+    }
+
+    pub fn targets(&self) -> &[Index] {
+        &self.targets
+    }
+
+    /// Replaces the target entities, for example after they have been recreated in another world.
+    /// Leaves the targets unchanged if `f` fails for any of them.
+    pub fn remap_targets<E, F: FnMut(Index) -> Result<Index, E>>(&mut self, f: F) -> Result<(), E> {
+        self.targets = self.targets.iter().copied().map(f).collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    pub fn layers(&self) -> &[AnimationLayer] {
+        &self.layers
+    }
+
+    pub fn layers_mut(&mut self) -> &mut Vec<AnimationLayer> {
+        &mut self.layers
+    }
+
+    /// Replaces all layers by a single one that loops the clip
+    pub fn play(&mut self, clip: usize) {
+        self.layers = vec![AnimationLayer::new(clip)];
+    }
+
+    pub fn stop(&mut self) {
+        self.layers.clear();
+    }
+
+    /// Moves the playback position of every layer forward by `dt` seconds
+    pub fn advance(&mut self, dt: f32) {
+        for layer in &mut self.layers {
+            let duration = self.clips.get(layer.clip).map_or(0.0, |c| c.duration);
+            layer.time += dt * layer.speed;
+            layer.time = if layer.looping && duration > 0.0 {
+                layer.time.rem_euclid(duration)
+            } else {
+                layer.time.clamp(0.0, duration)
+            };
+        }
+    }
+
+    /// Samples all layers and blends their poses per target entity. Translations and scales are
+    /// averaged by weight, rotations are blended by normalized linear interpolation.
+    pub fn pose(&self) -> Vec<(Index, NodePose)> {
+        #[derive(Default)]
+        struct Accumulator {
+            translation: ([f32; 3], f32),
+            rotation: ([f32; 4], f32),
+            scale: ([f32; 3], f32),
+        }
+
+        fn accumulate<const N: usize>(acc: &mut ([f32; N], f32), value: [f32; N], weight: f32) {
+            for (a, v) in acc.0.iter_mut().zip(value) {
+                *a += v * weight;
+            }
+            acc.1 += weight;
+        }
+
+        fn resolve<const N: usize>((sum, weight): ([f32; N], f32)) -> Option<[f32; N]> {
+            (weight > 0.0).then(|| sum.map(|c| c / weight))
+        }
+
+        let mut accumulators: Vec<Accumulator> = Vec::new();
+        accumulators.resize_with(self.targets.len(), Default::default);
+        for layer in self.layers.iter().filter(|l| l.weight > 0.0) {
+            let Some(clip) = self.clips.get(layer.clip) else {
+                continue;
+            };
+
+            for (node, pose) in clip.sample(layer.time) {
+                let Some(acc) = accumulators.get_mut(node) else {
+                    continue;
+                };
+                if let Some(t) = pose.translation {
+                    accumulate(&mut acc.translation, t, layer.weight);
+                }
+                if let Some(mut q) = pose.rotation {
+                    // Keep the quaternions on the same hemisphere so they do not cancel out
+                    let dot: f32 = acc.rotation.0.iter().zip(q).map(|(a, b)| a * b).sum();
+                    if dot < 0.0 {
+                        q = q.map(|c| -c);
+                    }
+                    accumulate(&mut acc.rotation, q, layer.weight);
+                }
+                if let Some(s) = pose.scale {
+                    accumulate(&mut acc.scale, s, layer.weight);
+                }
+            }
+        }
+
+        self.targets
+            .iter()
+            .zip(accumulators)
+            .filter_map(|(&target, acc)| {
+                let pose = NodePose {
+                    translation: resolve(acc.translation),
+                    rotation: resolve(acc.rotation).and_then(|q| {
+                        let norm = q.iter().map(|c| c * c).sum::<f32>().sqrt();
+                        (norm > 0.0).then(|| q.map(|c| c / norm))
+                    }),
+                    scale: resolve(acc.scale),
+                };
+                (pose != NodePose::default()).then_some((target, pose))
+            })
+            .collect()
+    }
+}
+
+impl Component for Animation {
+    type Storage = VecStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use griffon::assets::animation_clip::{AnimationChannel, Interpolation, Keyframes};
+
+    fn translation_clip(node: usize, from: [f32; 3], to: [f32; 3]) -> AnimationClip {
+        AnimationClip::new(
+            None,
+            vec![AnimationChannel {
+                node,
+                translation: Some(Keyframes {
+                    times: vec![0.0, 2.0],
+                    values: vec![from, to],
+                    interpolation: Interpolation::Linear,
+                }),
+                rotation: None,
+                scale: None,
+            }],
+        )
+    }
+
+    #[test]
+    fn looping_layers_wrap_around_and_others_hold_the_last_pose() {
+        let mut animation = Animation::new(vec![translation_clip(0, [0.0; 3], [2.0; 3])], vec![Index::new(7)]);
+        animation.layers_mut().push(AnimationLayer::new(0));
+        animation.layers_mut().push(AnimationLayer::new(0).with_looping(false));
+
+        animation.advance(2.5);
+
+        assert_eq!(animation.layers()[0].time, 0.5);
+        assert_eq!(animation.layers()[1].time, 2.0);
+    }
+
+    #[test]
+    fn layers_are_blended_by_weight() {
+        let mut animation = Animation::new(
+            vec![
+                translation_clip(0, [0.0; 3], [4.0, 0.0, 0.0]),
+                translation_clip(0, [0.0, 4.0, 0.0], [0.0; 3]),
+            ],
+            vec![Index::new(3), Index::new(5)],
+        );
+        animation.layers_mut().push(AnimationLayer::new(0).with_weight(3.0));
+        animation.layers_mut().push(AnimationLayer::new(1).with_weight(1.0));
+        animation.advance(1.0);
+
+        let pose = animation.pose();
+
+        // Only the first node is animated
+        assert_eq!(pose.len(), 1);
+        assert_eq!(pose[0].0, Index::new(3));
+        assert_eq!(pose[0].1.translation, Some([1.5, 0.5, 0.0]));
+        assert_eq!(pose[0].1.rotation, None);
+    }
+}
@@ -1,12 +1,27 @@
 use ecs::{Component, VecStorage};
 use glamour::{mat::Mat4, ortho::Ortho, persp::Persp};
 
+use self::{projection::Projection, target::CameraTarget, viewport::Viewport};
+
 pub mod projection;
+pub mod target;
+pub mod viewport;
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Camera {
     persp: Persp<f32>,
     ortho: Ortho<f32>,
+    #[serde(default)]
+    projection: Projection,
+    #[serde(default)]
+    viewport: Viewport,
+    /// Cameras with the same target are rendered in ascending order of priority
+    #[serde(default)]
+    priority: i32,
+    #[serde(default = "default_active")]
+    active: bool,
+    #[serde(default)]
+    target: CameraTarget,
 }
 
 impl Camera {
@@ -14,9 +29,76 @@ impl Camera {
         Camera {
             persp: Persp::new(height as f32 / width as f32, fov_y, frustum_z.0, frustum_z.1),
             ortho: Ortho::new(width as f32, height as f32, frustum_z.0, frustum_z.1),
+            projection: Projection::Perspective,
+            viewport: Viewport::FULL,
+            priority: 0,
+            active: true,
+            target: CameraTarget::Surface,
+        }
+    }
+
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_active(mut self, active: bool) -> Self {
+        self.active = active;
+        self
+    }
+
+    /// Render into a texture instead of the surface. The projection takes on the aspect ratio of
+    /// the texture.
+    pub fn with_target(mut self, target: CameraTarget) -> Self {
+        self.target = target;
+        if let CameraTarget::Texture { width, height } = target {
+            self.set_dimensions(width, height);
+        }
+        self
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// Returns the projection matrix that corresponds to the camera's projection mode
+    pub fn projection_matrix(&self) -> &Mat4<f32> {
+        match self.projection {
+            Projection::Perspective => self.as_persp_matrix(),
+            Projection::Orthographic | Projection::ScreenSpace => self.as_ortho_matrix(),
         }
     }
 
+    pub fn viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    pub fn target(&self) -> CameraTarget {
+        self.target
+    }
+
     pub fn as_persp_matrix(&self) -> &Mat4<f32> {
         self.persp.as_matrix()
     }
@@ -29,6 +111,21 @@ impl Camera {
         self.persp.set_aspect(height as f32 / width as f32);
         self.ortho.set_dimensions(width as f32, height as f32);
     }
+
+    /// Adapts the projection to the size of the viewport on the surface. Cameras that render into
+    /// a texture keep the dimensions of the texture.
+    pub fn fit_to_surface(&mut self, surface_width: u32, surface_height: u32) {
+        let (width, height) = match self.target {
+            CameraTarget::Surface => (surface_width, surface_height),
+            CameraTarget::Texture { width, height } => (width, height),
+        };
+        let [_, _, vw, vh] = self.viewport.to_pixels(width, height);
+        self.set_dimensions(vw as u32, vh as u32);
+    }
+}
+
+fn default_active() -> bool {
+    true
 }
 
 impl Default for Camera {
@@ -36,6 +133,11 @@ impl Default for Camera {
         Camera {
             persp: Persp::new(1.0, std::f32::consts::PI / 4.0, 0.1, 1000.0),
             ortho: Ortho::new(800.0, 600.0, 0.0, 10.0),
+            projection: Projection::Perspective,
+            viewport: Viewport::FULL,
+            priority: 0,
+            active: true,
+            target: CameraTarget::Surface,
         }
     }
 }
@@ -52,4 +154,32 @@ mod tests {
     fn implements_default() {
         let _: Camera = Default::default();
     }
+
+    #[test]
+    fn fit_to_surface_uses_the_viewport_dimensions() {
+        let mut split = Camera::default().with_viewport(Viewport::new(0.0, 0.0, 0.5, 1.0));
+        split.fit_to_surface(800, 600);
+        let mut expected = Camera::default();
+        expected.set_dimensions(400, 600);
+        assert_eq!(split.as_persp_matrix(), expected.as_persp_matrix());
+    }
+
+    #[test]
+    fn projection_matrix_follows_the_projection_mode() {
+        let persp = Camera::default();
+        assert_eq!(persp.projection_matrix(), persp.as_persp_matrix());
+        let ui = Camera::default().with_projection(Projection::ScreenSpace);
+        assert_eq!(ui.projection_matrix(), ui.as_ortho_matrix());
+    }
+
+    #[test]
+    fn texture_cameras_keep_the_texture_dimensions() {
+        let mut minimap = Camera::default().with_target(CameraTarget::Texture {
+            width: 256,
+            height: 256,
+        });
+        let before = minimap.clone();
+        minimap.fit_to_surface(800, 600);
+        assert_eq!(minimap, before);
+    }
 }
@@ -1,9 +1,15 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Projection {
+    /// Renders the 3D scene with perspective foreshortening
+    #[default]
     Perspective,
+    /// Renders the 3D scene with parallel projection, one world unit per pixel
     Orthographic,
+    /// Renders the user interface (entities with a UI [`Transform`](crate::components::transform::Transform))
+    /// with parallel projection in pixels, centered on the viewport
+    ScreenSpace,
 }
 
 impl std::fmt::Display for Projection {
@@ -11,6 +17,7 @@ impl std::fmt::Display for Projection {
         match self {
             Projection::Perspective => f.write_str("Perspective"),
             Projection::Orthographic => f.write_str("Orthographic"),
+            Projection::ScreenSpace => f.write_str("Screen space"),
         }
     }
 }
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// Specifies where a camera renders to
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CameraTarget {
+    /// The window surface (or the offscreen texture of headless graphics)
+    #[default]
+    Surface,
+    /// A texture of the given dimensions, which can be sampled after rendering (e.g. for mirrors
+    /// and minimaps). See [`RenderTargets`](crate::resources::render_targets::RenderTargets).
+    Texture { width: u32, height: u32 },
+}
+
+impl std::fmt::Display for CameraTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CameraTarget::Surface => f.write_str("Surface"),
+            CameraTarget::Texture { width, height } => write!(f, "Texture ({width}x{height})"),
+        }
+    }
+}
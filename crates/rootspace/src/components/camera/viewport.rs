@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+/// The rectangle of the render target that a camera renders into, in fractions of the target
+/// dimensions with the origin at the top left corner
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub const FULL: Viewport = Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Viewport { x, y, width, height }
+    }
+
+    /// Returns the viewport in pixels (x, y, width, height) for a render target of the given
+    /// dimensions. The result is clamped to the render target and covers at least one pixel.
+    pub fn to_pixels(&self, target_width: u32, target_height: u32) -> [f32; 4] {
+        let (tw, th) = (target_width.max(1) as f32, target_height.max(1) as f32);
+        let x = (self.x.clamp(0.0, 1.0) * tw).min(tw - 1.0);
+        let y = (self.y.clamp(0.0, 1.0) * th).min(th - 1.0);
+        let width = (self.width.clamp(0.0, 1.0) * tw).clamp(1.0, tw - x);
+        let height = (self.height.clamp(0.0, 1.0) * th).clamp(1.0, th - y);
+        [x, y, width, height]
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport::FULL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_viewport_covers_the_target() {
+        assert_eq!(Viewport::FULL.to_pixels(800, 600), [0.0, 0.0, 800.0, 600.0]);
+    }
+
+    #[test]
+    fn to_pixels_clamps_to_the_target() {
+        let vp = Viewport::new(0.75, 0.5, 0.5, 0.0);
+        assert_eq!(vp.to_pixels(800, 600), [600.0, 300.0, 200.0, 1.0]);
+    }
+}
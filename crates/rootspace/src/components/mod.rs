@@ -1,4 +1,6 @@
+pub mod animation;
 pub mod camera;
 pub mod debug_animate;
 pub mod info;
+pub mod skeleton;
 pub mod transform;
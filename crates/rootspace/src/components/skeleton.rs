@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+
+use super::transform::{GlobalTransforms, Transform};
+use ecs::{Component, Index, Storage, VecStorage};
+use glamour::mat::Mat4;
+
+/// Deforms the skinned mesh of its entity by a set of joint entities. The joints are arranged in
+/// the order of the joint indices of the mesh vertices, see
+/// [`VertexSkin`](griffon::base::skinned_vertex::VertexSkin).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Skeleton {
+    joints: Vec<Index>,
+    inverse_bind_matrices: Vec<Mat4<f32>>,
+}
+
+impl Skeleton {
+    /// `inverse_bind_matrices` transform model space positions into the local space of each
+    /// joint in the bind pose of the mesh
+    pub fn new(joints: Vec<Index>, inverse_bind_matrices: Vec<Mat4<f32>>) -> Self {
+        assert_eq!(
+            joints.len(),
+            inverse_bind_matrices.len(),
+            "Developer error: each joint requires exactly one inverse bind matrix"
+        );
+
+        Skeleton {
+            joints,
+            inverse_bind_matrices,
+        }
+    }
+
+    pub fn joints(&self) -> &[Index] {
+        &self.joints
+    }
+
+    /// Replaces the joint entities, for example after they have been recreated in another world.
+    /// Leaves the joints unchanged if `f` fails for any of them.
+    pub fn remap_joints<E, F: FnMut(Index) -> Result<Index, E>>(&mut self, f: F) -> Result<(), E> {
+        self.joints = self.joints.iter().copied().map(f).collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// Returns the matrices that move the vertices of the mesh from the bind pose into the
+    /// current pose of the joints, in the model space of the mesh at `mesh`. The renderer places
+    /// the mesh with the same global transforms, such that the joints end up where they are in
+    /// the world.
+    pub fn joint_matrices(
+        &self,
+        mesh: Index,
+        globals: &GlobalTransforms,
+        transforms: &<Transform as Component>::Storage,
+    ) -> Vec<Mat4<f32>> {
+        let global_matrix = |idx: Index| {
+            transforms
+                .get(idx)
+                .map_or_else(|| globals.parent_matrix(idx), |t| globals.matrix(idx, t))
+        };
+
+        let world_to_mesh = similarity_inverse(&global_matrix(mesh));
+        self.joints
+            .iter()
+            .zip(&self.inverse_bind_matrices)
+            .map(|(&joint, ibm)| world_to_mesh * global_matrix(joint) * ibm)
+            .collect()
+    }
+}
+
+impl Component for Skeleton {
+    type Storage = VecStorage<Self>;
+}
+
+/// Inverts a matrix composed of a rotation, a uniform scale and a translation, as produced by
+/// [`Affine`](glamour::affine::Affine), without a general matrix inversion
+fn similarity_inverse(m: &Mat4<f32>) -> Mat4<f32> {
+    let scale_sq = (0..3).map(|r| m[(r, 0)] * m[(r, 0)]).sum::<f32>();
+    let mut inv = Mat4::identity();
+    if scale_sq <= 0.0 {
+        return inv;
+    }
+
+    for r in 0..3 {
+        for c in 0..3 {
+            inv[(r, c)] = m[(c, r)] / scale_sq;
+        }
+    }
+    for r in 0..3 {
+        inv[(r, 3)] = -(0..3).map(|k| inv[(r, k)] * m[(k, 3)]).sum::<f32>();
+    }
+
+    inv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ecs::Entities;
+    use glamour::{num::ToMatrix, quat::Quat, vec::Vec4};
+    use rose_tree::hierarchy::Hierarchy;
+
+    fn assert_approx_eq(a: &Mat4<f32>, b: &Mat4<f32>) {
+        for r in 0..4 {
+            for c in 0..4 {
+                assert!((a[(r, c)] - b[(r, c)]).abs() < 1e-5, "{a:?} != {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn similarity_inverse_undoes_rotation_scale_and_translation() {
+        let m = Transform::builder()
+            .with_translation(Vec4::new_point(1.0, -2.0, 3.0))
+            .with_orientation(Quat::with_axis_angle(Vec4::y(), 0.7))
+            .with_scale(2.5)
+            .build()
+            .to_matrix();
+
+        assert_approx_eq(&(similarity_inverse(&m) * m), &Mat4::identity());
+    }
+
+    #[test]
+    fn joint_matrices_follow_the_joints_relative_to_the_mesh() {
+        let mut entities = Entities::default();
+        let (root, mesh, joint) = (
+            entities.create().idx(),
+            entities.create().idx(),
+            entities.create().idx(),
+        );
+        let mut hierarchy: Hierarchy<Index> = Hierarchy::default();
+        hierarchy.insert(root);
+        hierarchy.insert_child(root, mesh);
+        hierarchy.insert_child(root, joint);
+
+        let translated = |x: f32| {
+            Transform::builder()
+                .with_translation(Vec4::new_point(x, 0.0, 0.0))
+                .build()
+        };
+        let mut transforms = <Transform as Component>::Storage::default();
+        transforms.insert(root, translated(10.0));
+        transforms.insert(mesh, translated(1.0));
+        transforms.insert(joint, translated(3.0));
+
+        // The joint was bound where it is now, so the mesh is not deformed
+        let bind_pose = translated(-2.0).to_matrix();
+        let skeleton = Skeleton::new(vec![joint], vec![bind_pose]);
+        let globals = GlobalTransforms::new(&hierarchy, &transforms);
+        assert_approx_eq(
+            &skeleton.joint_matrices(mesh, &globals, &transforms)[0],
+            &Mat4::identity(),
+        );
+
+        // Moving the joint by one unit moves the vertices it influences along with it
+        transforms.insert(joint, translated(4.0));
+        let globals = GlobalTransforms::new(&hierarchy, &transforms);
+        assert_approx_eq(
+            &skeleton.joint_matrices(mesh, &globals, &transforms)[0],
+            &translated(1.0).to_matrix(),
+        );
+    }
+
+    #[test]
+    fn skinned_meshes_under_a_moved_and_rotated_parent_follow_their_joints() {
+        let mut entities = Entities::default();
+        let (root, parent, mesh, joint) = (
+            entities.create().idx(),
+            entities.create().idx(),
+            entities.create().idx(),
+            entities.create().idx(),
+        );
+        let mut hierarchy: Hierarchy<Index> = Hierarchy::default();
+        hierarchy.insert(root);
+        hierarchy.insert_child(root, parent);
+        hierarchy.insert_child(parent, mesh);
+        hierarchy.insert_child(root, joint);
+
+        let transform = |x: f32, angle: f32| {
+            Transform::builder()
+                .with_translation(Vec4::new_point(x, 0.0, 0.0))
+                .with_orientation(Quat::with_axis_angle(Vec4::y(), angle))
+                .build()
+        };
+        let mut transforms = <Transform as Component>::Storage::default();
+        transforms.insert(root, transform(10.0, 0.5));
+        transforms.insert(parent, transform(-4.0, 1.2));
+        transforms.insert(mesh, transform(1.0, 0.0));
+        transforms.insert(joint, transform(3.0, 0.0));
+        let globals = GlobalTransforms::new(&hierarchy, &transforms);
+
+        let inverse_bind_matrix = transform(-2.0, 0.3).to_matrix();
+        let skeleton = Skeleton::new(vec![joint], vec![inverse_bind_matrix]);
+
+        // The renderer places the mesh with its global transform, and the vertex shader applies
+        // the joint matrix in the model space of the mesh
+        let model = globals.matrix(mesh, &transforms[mesh]);
+        let joint_matrix = skeleton.joint_matrices(mesh, &globals, &transforms)[0];
+        let joint_global = globals.matrix(joint, &transforms[joint]);
+        assert_approx_eq(&(model * joint_matrix), &(joint_global * inverse_bind_matrix));
+    }
+
+    #[test]
+    fn failed_remapping_leaves_the_joints_unchanged() {
+        let joints = vec![Index::new(1), Index::new(2)];
+        let mut skeleton = Skeleton::new(joints.clone(), vec![Mat4::identity(); 2]);
+
+        let result = skeleton.remap_joints(|j| if j == Index::new(1) { Ok(Index::new(5)) } else { Err(j) });
+
+        assert_eq!(result, Err(Index::new(2)));
+        assert_eq!(skeleton.joints(), joints.as_slice());
+
+        skeleton
+            .remap_joints(|j| Ok::<_, ()>(Index::new(j.idx() + 10)))
+            .unwrap();
+        assert_eq!(skeleton.joints(), &[Index::new(11), Index::new(12)]);
+    }
+}
@@ -1,6 +1,8 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
-use ecs::{Component, VecStorage};
+use ecs::{Component, Index, Storage, VecStorage};
 use glamour::{
     affine::{Affine, builder::AffineBuilder},
     mat::Mat4,
@@ -9,6 +11,7 @@ use glamour::{
     unit::Unit,
     vec::Vec4,
 };
+use rose_tree::hierarchy::Hierarchy;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Transform {
@@ -88,6 +91,47 @@ impl std::fmt::Display for Transform {
     }
 }
 
+/// The transforms of all ancestors of each entity in the hierarchy, concatenated parents first
+#[derive(Debug, Default, Clone)]
+pub struct GlobalTransforms {
+    parents: HashMap<Index, Mat4<f32>>,
+}
+
+impl GlobalTransforms {
+    /// Walks the hierarchy once, from the roots to the leaves. Entities without a transform
+    /// leave the transform of their ancestors unchanged.
+    pub fn new(hierarchy: &Hierarchy<Index>, transforms: &<Transform as Component>::Storage) -> Self {
+        let mut parents: HashMap<Index, Mat4<f32>> = HashMap::new();
+        let mut globals: HashMap<Index, Mat4<f32>> = HashMap::new();
+        for idx in hierarchy.bfs_iter() {
+            let parent = hierarchy
+                .ancestors(idx)
+                .nth(1)
+                .and_then(|p| globals.get(&p).copied())
+                .unwrap_or_else(Mat4::identity);
+            let global = match transforms.get(idx) {
+                Some(t) => parent * t.to_matrix(),
+                None => parent,
+            };
+            parents.insert(idx, parent);
+            globals.insert(idx, global);
+        }
+
+        GlobalTransforms { parents }
+    }
+
+    /// Returns the concatenated transforms of the ancestors of an entity, or the identity for
+    /// entities outside of the hierarchy
+    pub fn parent_matrix(&self, idx: Index) -> Mat4<f32> {
+        self.parents.get(&idx).copied().unwrap_or_else(Mat4::identity)
+    }
+
+    /// Returns the transform of an entity relative to the world, given its local transform
+    pub fn matrix(&self, idx: Index, local: &Transform) -> Mat4<f32> {
+        self.parent_matrix(idx) * local.to_matrix()
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Clone)]
 pub struct TransformBuilder {
     affine_builder: AffineBuilder<f32>,
@@ -1,5 +1,13 @@
+use griffon::{PostProcessSettings, WindowMode};
+
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 pub enum EngineEvent {
     Exit,
+    /// Switches the window between windowed and fullscreen modes
+    SetWindowMode(WindowMode),
+    /// Enables or disables presenting frames synchronized to the display
+    SetVsync(bool),
+    /// Replaces the post-processing effects applied to the scene
+    SetPostProcess(PostProcessSettings),
 }
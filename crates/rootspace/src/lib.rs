@@ -1,5 +1,5 @@
 //#![warn(clippy::unwrap_used)]
-#![recursion_limit = "256"]
+#![recursion_limit = "512"]
 
 pub mod app;
 pub mod assets;
@@ -20,4 +20,4 @@ pub use crate::{
     systems::rpc::service::RpcServiceClient,
 };
 pub use ecs::{Element, End, Resource, WithDependencies};
-pub use griffon::components::renderable::RenderableSource;
+pub use griffon::{assets::primitives::Primitive, components::renderable::RenderableSource};
@@ -6,7 +6,7 @@ use std::{
 use super::registry::{FUSRegistry, MSRegistry};
 use crate::{
     assets::scene::Scene,
-    components::{camera::Camera, info::Info, transform::Transform},
+    components::{animation::Animation, camera::Camera, info::Info, skeleton::Skeleton, transform::Transform},
     events::engine_event::EngineEvent,
     registry::{RRegistry, USRegistry},
     resources::{rpc_settings::RpcDeps, statistics::Statistics},
@@ -18,7 +18,7 @@ use ecs::{
     WithDependencies, WithResources, World, WorldEvent,
 };
 use glamour::{quat::Quat, unit::Unit, vec::Vec4};
-use griffon::components::light::LightSource;
+use griffon::components::light::{Light, LightSource};
 use griffon::components::renderable::{Renderable, RenderableSource};
 use griffon::winit::{
     event::{Event, WindowEvent},
@@ -71,7 +71,9 @@ impl Orchestrator {
         let world_event_receiver = world.get_mut::<EventQueue<WorldEvent>>().subscribe::<Self>();
         let engine_event_receiver = world.get_mut::<EventQueue<EngineEvent>>().subscribe::<Self>();
 
-        Self::load_builtins(world.resources()).await?;
+        if deps.load_builtins() {
+            Self::load_builtins(world.resources()).await?;
+        }
 
         if let Some(main_scene) = deps.main_scene() {
             world
@@ -110,6 +112,38 @@ impl Orchestrator {
         }
     }
 
+    /// Drives a fixed number of frames without an operating system event loop, which is meant
+    /// for headless [`Graphics`]. The clock is deterministic: every frame advances the engine
+    /// state by exactly one fixed time interval with [`Orchestrator::advance`](Orchestrator::advance),
+    /// followed by the same maintenance as [`Orchestrator::maintain`](Orchestrator::maintain),
+    /// minus window handling. Returns early with [`LoopControl::Abort`] if the world requests to
+    /// exit.
+    #[tracing::instrument(skip(self))]
+    pub async fn run_frames(&mut self, frames: usize) -> LoopControl {
+        // Without a window, nobody else announces the dimensions of the render target
+        let size = self.world.read::<Graphics>().window_inner_size();
+        self.world
+            .get_mut::<EventQueue<WindowEvent>>()
+            .send(WindowEvent::Resized(size));
+
+        for _ in 0..frames {
+            self.advance(self.timers.delta_time).await;
+            self.update_maintenance_statistics();
+            self.process_events();
+            if let LoopControl::Abort = self.world.maintain().await {
+                self.on_exiting();
+                return LoopControl::Abort;
+            }
+        }
+
+        LoopControl::Continue
+    }
+
+    /// Copies the most recently rendered frame of headless [`Graphics`] back to the CPU
+    pub fn read_frame(&self) -> anyhow::Result<griffon::image::RgbaImage> {
+        self.world.read::<Graphics>().read_frame()
+    }
+
     /// Handles an event from `winit` and the operating system, by:
     /// 1. Initiating window redrawing with
     ///    [`Orchestrator::redraw`](Orchestrator::redraw)
@@ -140,7 +174,7 @@ impl Orchestrator {
             Event::WindowEvent {
                 window_id,
                 event: window_event,
-            } if main_window_id == window_id => match window_event {
+            } if main_window_id == Some(window_id) => match window_event {
                 WindowEvent::RedrawRequested => self.redraw().await,
                 e => self.world.get_mut::<EventQueue<WindowEvent>>().send(e),
             },
@@ -155,6 +189,16 @@ impl Orchestrator {
         }
     }
 
+    /// Measures the time since the last redraw and advances the engine state accordingly with
+    /// [`Orchestrator::advance`](Orchestrator::advance)
+    #[tracing::instrument(skip_all)]
+    async fn redraw(&mut self) {
+        // Assess the duration of the last frame
+        let loop_time = std::cmp::min(self.timers.last_redraw.elapsed(), self.timers.max_loop_duration);
+        self.timers.last_redraw = Instant::now();
+        self.advance(loop_time).await;
+    }
+
     /// Update and render the engine state:
     /// 1. Call [`World::fixed_update`](World::fixed_update) with fixed
     ///    time intervals by guaranteeing that missed updates are caught up with.
@@ -162,10 +206,7 @@ impl Orchestrator {
     /// 3. Call [`World::render`](World::render) once per redraw event.
     /// 4. Update performance statistics in [`Statistics`](Statistics)
     #[tracing::instrument(skip_all)]
-    async fn redraw(&mut self) {
-        // Assess the duration of the last frame
-        let loop_time = std::cmp::min(self.timers.last_redraw.elapsed(), self.timers.max_loop_duration);
-        self.timers.last_redraw = Instant::now();
+    async fn advance(&mut self, loop_time: Duration) {
         self.timers.accumulator += loop_time;
         self.timers.dynamic_game_time += loop_time;
 
@@ -192,11 +233,7 @@ impl Orchestrator {
     /// have been handled.
     #[tracing::instrument(skip_all)]
     async fn maintain(&mut self, event_loop_window_target: &EventLoopWindowTarget<()>) {
-        // Update maintenance statistics
-        self.world
-            .get_mut::<Statistics>()
-            .update_maintenance_intervals(self.timers.last_maintenance.elapsed());
-        self.timers.last_maintenance = Instant::now();
+        self.update_maintenance_statistics();
 
         // Process window events
         #[cfg(feature = "editor")]
@@ -230,28 +267,7 @@ impl Orchestrator {
             }
         }
 
-        // Process world events
-        let events = self
-            .world
-            .get_mut::<EventQueue<WorldEvent>>()
-            .receive(&self.world_event_receiver);
-        for event in events {
-            if let WorldEvent::EntityDestroyed(e) = event {
-                self.on_entity_destroyed(e);
-            }
-        }
-
-        // Process engine events
-        let events = self
-            .world
-            .get_mut::<EventQueue<EngineEvent>>()
-            .receive(&self.engine_event_receiver);
-        for event in events {
-            #[allow(irrefutable_let_patterns)]
-            if let EngineEvent::Exit = event {
-                self.on_exit();
-            }
-        }
+        self.process_events();
 
         #[cfg(feature = "editor")]
         if window_interaction_received {
@@ -278,6 +294,43 @@ impl Orchestrator {
         event_loop_window_target.set_control_flow(ControlFlow::Poll);
     }
 
+    fn update_maintenance_statistics(&mut self) {
+        self.world
+            .get_mut::<Statistics>()
+            .update_maintenance_intervals(self.timers.last_maintenance.elapsed());
+        self.timers.last_maintenance = Instant::now();
+    }
+
+    /// Handles world and engine events that do not depend on a window
+    fn process_events(&mut self) {
+        let events = self
+            .world
+            .get_mut::<EventQueue<WorldEvent>>()
+            .receive(&self.world_event_receiver);
+        let mut entities_destroyed = false;
+        for event in events {
+            if let WorldEvent::EntityDestroyed(e) = event {
+                self.on_entity_destroyed(e);
+                entities_destroyed = true;
+            }
+        }
+
+        // Release the GPU objects of all destroyed entities at once
+        if entities_destroyed {
+            self.world.get_mut::<Graphics>().collect_garbage();
+        }
+
+        let events = self
+            .world
+            .get_mut::<EventQueue<EngineEvent>>()
+            .receive(&self.engine_event_receiver);
+        for event in events {
+            if let EngineEvent::Exit = event {
+                self.on_exit();
+            }
+        }
+    }
+
     #[tracing::instrument(skip_all)]
     fn on_entity_destroyed(&mut self, entity: Entity) {
         tracing::trace!("Removing entity from components");
@@ -285,6 +338,9 @@ impl Orchestrator {
         self.world.get_components_mut::<Info>().remove(entity);
         self.world.get_components_mut::<Transform>().remove(entity);
         self.world.get_components_mut::<Renderable>().remove(entity);
+        self.world.get_components_mut::<Light>().remove(entity);
+        self.world.get_components_mut::<Skeleton>().remove(entity);
+        self.world.get_components_mut::<Animation>().remove(entity);
     }
 
     #[tracing::instrument(skip_all)]
@@ -305,6 +361,21 @@ impl Orchestrator {
         #[cfg(feature = "editor")]
         Self::load_editor_builtins(res).await?;
 
+        Self::builtin_scene().submit(res, "builtin", "main").await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "editor")]
+    #[tracing::instrument(skip_all)]
+    async fn load_editor_builtins(res: &Resources) -> anyhow::Result<()> {
+        Self::editor_builtin_scene().submit(res, "builtin", "editor").await?;
+
+        Ok(())
+    }
+
+    /// Creates the scene that is always loaded on startup
+    pub(crate) fn builtin_scene() -> Scene {
         let mut builtins_scene = Scene::default();
         builtins_scene
             .create_entity()
@@ -331,9 +402,7 @@ impl Orchestrator {
                 position: [2.0, 2.0, 2.0, 1.0].into(),
                 ambient_color: [0.5, 0.5, 0.5, 1.0].into(),
                 diffuse_color: [0.34, 0.34, 0.87, 1.0].into(),
-                specular_color: [1.0, 1.0, 1.0, 1.0].into(),
-                ambient_intensity: 0.05,
-                point_intensity: 1.0,
+                ..Default::default()
             })
             .submit();
         // builtins_scene
@@ -374,9 +443,10 @@ impl Orchestrator {
                         name: format!("cube-{i}x{j}"),
                         ..Default::default()
                     })
-                    .with_renderable(RenderableSource {
+                    .with_renderable(RenderableSource::Asset {
                         group: "models".into(),
                         name: "textured-cube.ply".into(),
+                        mesh: None,
                     })
                     .with_transform(
                         Transform::builder()
@@ -395,9 +465,10 @@ impl Orchestrator {
                 name: "floor".to_string(),
                 ..Default::default()
             })
-            .with_renderable(RenderableSource {
+            .with_renderable(RenderableSource::Asset {
                 group: "models".into(),
                 name: "quad.ply".into(),
+                mesh: None,
             })
             .with_transform(
                 Transform::builder()
@@ -408,14 +479,11 @@ impl Orchestrator {
             )
             .submit();
 
-        builtins_scene.submit(res, "builtin", "main").await?;
-
-        Ok(())
+        builtins_scene
     }
 
-    #[cfg(feature = "editor")]
-    #[tracing::instrument(skip_all)]
-    async fn load_editor_builtins(res: &Resources) -> anyhow::Result<()> {
+    /// Creates the scene that is loaded on startup in editor builds
+    pub(crate) fn editor_builtin_scene() -> Scene {
         let mut editor_scene = Scene::default();
         editor_scene
             .create_entity()
@@ -423,11 +491,18 @@ impl Orchestrator {
                 name: "coordinate-diag-ortho".into(),
                 ..Default::default()
             })
-            .with_renderable(RenderableSource {
+            .with_renderable(RenderableSource::Asset {
                 group: "models".into(),
                 name: "coordinate-diag.ply".into(),
+                mesh: None,
             })
-            .with_transform(Transform::builder().with_ui(true).with_scale(0.1).build())
+            .with_transform(
+                Transform::builder()
+                    .with_ui(true)
+                    .with_translation(Vec4::new_point(0.0, 0.0, -5.0))
+                    .with_scale(20.0)
+                    .build(),
+            )
             .submit();
         editor_scene
             .create_entity()
@@ -435,15 +510,14 @@ impl Orchestrator {
                 name: "coordinate-diag-persp".into(),
                 ..Default::default()
             })
-            .with_renderable(RenderableSource {
+            .with_renderable(RenderableSource::Asset {
                 group: "models".into(),
                 name: "coordinate-diag.ply".into(),
+                mesh: None,
             })
             .with_transform(Transform::default())
             .submit();
-        editor_scene.submit(res, "builtin", "editor").await?;
-
-        Ok(())
+        editor_scene
     }
 }
 
@@ -456,6 +530,11 @@ pub trait OrchestratorDeps {
         None
     }
 
+    /// Specifies whether the builtin scenes are loaded before the main scene
+    fn load_builtins(&self) -> bool {
+        true
+    }
+
     /// Specifies the name of the asset group scenes are stored in
     fn scene_group(&self) -> &str {
         "scenes"
@@ -2,11 +2,17 @@ use griffon::winit::event::WindowEvent;
 
 use super::systems::debug_animator::DebugAnimator;
 use crate::{
-    components::{camera::Camera, debug_animate::DebugAnimate, info::Info, transform::Transform},
+    components::{
+        animation::Animation, camera::Camera, debug_animate::DebugAnimate, info::Info, skeleton::Skeleton,
+        transform::Transform,
+    },
     events::engine_event::EngineEvent,
-    resources::{rpc_settings::RpcSettings, statistics::Statistics},
+    resources::{
+        debug_draw::DebugDraw, render_targets::RenderTargets, rpc_settings::RpcSettings, statistics::Statistics,
+    },
     systems::{
-        camera_controller::CameraController, camera_manager::CameraManager, force_shutdown::ForceShutdown, rpc::Rpc,
+        animator::Animator, camera_controller::CameraController, camera_manager::CameraManager,
+        force_shutdown::ForceShutdown, rpc::Rpc,
     },
 };
 use assam::AssetDatabase;
@@ -23,17 +29,21 @@ pub type RRegistry<S> = RegAdd![
     <Renderable as Component>::Storage,
     <Light as Component>::Storage,
     <DebugAnimate as Component>::Storage,
+    <Skeleton as Component>::Storage,
+    <Animation as Component>::Storage,
     AssetDatabase,
     EventQueue<WindowEvent>,
     EventQueue<EngineEvent>,
     Graphics,
     Hierarchy<Index>,
     Statistics,
+    RenderTargets,
     RpcSettings,
+    DebugDraw,
     S
 ];
 
-pub type FUSRegistry<D> = RegAdd![DebugAnimator, CameraController, D];
+pub type FUSRegistry<D> = RegAdd![DebugAnimator, Animator, CameraController, D];
 
 pub type USRegistry<D> = RegAdd![
     CameraManager,
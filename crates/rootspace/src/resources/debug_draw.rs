@@ -0,0 +1,364 @@
+use ecs::{Resource, WithDependencies};
+use glamour::mat::Mat4;
+use glamour::vec::Vec4;
+use griffon::base::line_vertex::LineVertex;
+use num_traits::Inv;
+
+/// The number of line segments that approximate a circle of a sphere
+const CIRCLE_SEGMENTS: usize = 32;
+/// The width of a glyph relative to its height
+const GLYPH_ASPECT: f32 = 0.6;
+/// The distance between the origins of consecutive glyphs relative to their height
+const GLYPH_ADVANCE: f32 = 0.9;
+/// The distance between consecutive lines of text relative to the glyph height
+const LINE_HEIGHT: f32 = 1.4;
+
+/// Collects lines that visualise the state of the world for a single frame, such as colliders,
+/// camera frusta or light ranges. Any system may add lines, which the
+/// [`Renderer`](crate::systems::renderer::Renderer) draws on top of the scene and then clears.
+///
+/// World lines are drawn by every camera that renders the scene, screen lines and text on top of
+/// the surface, in pixels from its top left corner.
+#[derive(Debug)]
+pub struct DebugDraw {
+    enabled: bool,
+    /// Pairs of end points in world space
+    world: Vec<LineVertex>,
+    /// Pairs of end points in pixels of the surface
+    screen: Vec<LineVertex>,
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        DebugDraw {
+            enabled: true,
+            world: Vec::new(),
+            screen: Vec::new(),
+        }
+    }
+}
+
+impl DebugDraw {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Disabling debug drawing discards all lines, including those added later on
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.clear();
+        }
+    }
+
+    pub fn line<P: Into<Vec4<f32>>, Q: Into<Vec4<f32>>>(&mut self, from: P, to: Q, color: Vec4<f32>) {
+        if !self.enabled {
+            return;
+        }
+
+        let color: [f32; 4] = color.into();
+        for p in [from.into(), to.into()] {
+            self.world.push(LineVertex {
+                position: [p.x, p.y, p.z],
+                color,
+            });
+        }
+    }
+
+    /// Draws the edges of an axis-aligned bounding box
+    pub fn aabb<P: Into<Vec4<f32>>, Q: Into<Vec4<f32>>>(&mut self, min: P, max: Q, color: Vec4<f32>) {
+        let (min, max) = (min.into(), max.into());
+        let corners: Vec<Vec4<f32>> = (0..8)
+            .map(|i| {
+                Vec4::new_point(
+                    if i & 1 == 0 { min.x } else { max.x },
+                    if i & 2 == 0 { min.y } else { max.y },
+                    if i & 4 == 0 { min.z } else { max.z },
+                )
+            })
+            .collect();
+        self.box_edges(&corners, color);
+    }
+
+    /// Draws a great circle around each axis
+    pub fn sphere<P: Into<Vec4<f32>>>(&mut self, center: P, radius: f32, color: Vec4<f32>) {
+        let c = center.into();
+        let point = |axis: usize, angle: f32| {
+            let (sin, cos) = angle.sin_cos();
+            match axis {
+                0 => Vec4::new_point(c.x, c.y + radius * cos, c.z + radius * sin),
+                1 => Vec4::new_point(c.x + radius * sin, c.y, c.z + radius * cos),
+                _ => Vec4::new_point(c.x + radius * cos, c.y + radius * sin, c.z),
+            }
+        };
+
+        let step = std::f32::consts::TAU / CIRCLE_SEGMENTS as f32;
+        for axis in 0..3 {
+            for i in 0..CIRCLE_SEGMENTS {
+                self.line(point(axis, i as f32 * step), point(axis, (i + 1) as f32 * step), color);
+            }
+        }
+    }
+
+    /// Draws the edges of the volume that a camera sees, given its world-to-clip transform in
+    /// OpenGL clip space, i.e. the projection matrix times the view matrix
+    pub fn frustum(&mut self, world_to_clip: &Mat4<f32>, color: Vec4<f32>) {
+        let clip_to_world = world_to_clip.inv();
+        let corners: Vec<Vec4<f32>> = (0..8)
+            .map(|i| {
+                let ndc = Vec4::new_point(
+                    if i & 1 == 0 { -1.0 } else { 1.0 },
+                    if i & 2 == 0 { -1.0 } else { 1.0 },
+                    if i & 4 == 0 { -1.0 } else { 1.0 },
+                );
+                let p = clip_to_world * ndc;
+                Vec4::new_point(p.x / p.w, p.y / p.w, p.z / p.w)
+            })
+            .collect();
+        self.box_edges(&corners, color);
+    }
+
+    /// Draws the x, y and z axes of a model-to-world transform in red, green and blue
+    pub fn axes(&mut self, model: &Mat4<f32>, length: f32) {
+        let origin = model * Vec4::new_point(0.0, 0.0, 0.0);
+        let colors = [
+            Vec4::new(1.0, 0.0, 0.0, 1.0),
+            Vec4::new(0.0, 1.0, 0.0, 1.0),
+            Vec4::new(0.0, 0.0, 1.0, 1.0),
+        ];
+        for (axis, color) in colors.into_iter().enumerate() {
+            let mut end = [0.0; 3];
+            end[axis] = length;
+            self.line(origin, model * Vec4::new_point(end[0], end[1], end[2]), color);
+        }
+    }
+
+    /// Draws a line on top of the surface, in pixels from its top left corner
+    pub fn screen_line(&mut self, from: [f32; 2], to: [f32; 2], color: Vec4<f32>) {
+        if !self.enabled {
+            return;
+        }
+
+        let color: [f32; 4] = color.into();
+        for [x, y] in [from, to] {
+            self.screen.push(LineVertex {
+                position: [x, y, 0.0],
+                color,
+            });
+        }
+    }
+
+    /// Writes text on top of the surface with a segmented font, where `position` is the top left
+    /// corner of the first glyph in pixels and `size` the height of the glyphs. Letters are shown
+    /// in upper case, unsupported characters as question marks.
+    pub fn text(&mut self, position: [f32; 2], size: f32, text: &str, color: Vec4<f32>) {
+        let [mut x, mut y] = position;
+        let scale = [size * GLYPH_ASPECT / 2.0, size / 2.0];
+        for c in text.chars() {
+            if c == '\n' {
+                x = position[0];
+                y += size * LINE_HEIGHT;
+                continue;
+            }
+
+            for segment in glyph(c).chars() {
+                let [x0, y0, x1, y1] = segment_end_points(segment);
+                self.screen_line(
+                    [x + x0 * scale[0], y + y0 * scale[1]],
+                    [x + x1 * scale[0], y + y1 * scale[1]],
+                    color,
+                );
+            }
+            x += size * GLYPH_ADVANCE;
+        }
+    }
+
+    /// Returns the pairs of end points of the lines in world space
+    pub fn world_lines(&self) -> &[LineVertex] {
+        &self.world
+    }
+
+    /// Returns the pairs of end points of the lines in pixels of the surface
+    pub fn screen_lines(&self) -> &[LineVertex] {
+        &self.screen
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.world.is_empty() && self.screen.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.world.clear();
+        self.screen.clear();
+    }
+
+    /// Connects eight corners, indexed by their bits for x (1), y (2) and z (4), into a box
+    fn box_edges(&mut self, corners: &[Vec4<f32>], color: Vec4<f32>) {
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    self.line(corners[i], corners[i | bit], color);
+                }
+            }
+        }
+    }
+}
+
+impl Resource for DebugDraw {}
+
+impl<D> WithDependencies<D> for DebugDraw {
+    #[tracing::instrument(skip_all)]
+    async fn with_deps(_: &D) -> anyhow::Result<Self> {
+        Ok(DebugDraw::default())
+    }
+}
+
+/// Returns the segments of a glyph, see [`segment_end_points`]
+fn glyph(c: char) -> &'static str {
+    match c.to_ascii_uppercase() {
+        ' ' => "",
+        '0' => "abcdefghnt",
+        '1' => "cd",
+        '2' => "abcpugfe",
+        '3' => "abcdefp",
+        '4' => "hupcd",
+        '5' | 'S' => "abhupdef",
+        '6' => "abhgfedpu",
+        '7' => "abcd",
+        '8' => "abcdefghup",
+        '9' => "abcdefhup",
+        'A' => "abcdghup",
+        'B' => "abcdefmsp",
+        'C' => "abhgfe",
+        'D' => "abcdefms",
+        'E' => "abhgfeu",
+        'F' => "abhgu",
+        'G' => "abhgfedp",
+        'H' => "hgcdup",
+        'I' => "abmsfe",
+        'J' => "cdefg",
+        'K' => "hgunr",
+        'L' => "hgfe",
+        'M' => "hgcdkn",
+        'N' => "hgcdkr",
+        'O' => "abcdefgh",
+        'P' => "abchgup",
+        'Q' => "abcdefghr",
+        'R' => "abchgupr",
+        'T' => "abms",
+        'U' => "hgfedc",
+        'V' => "hgtn",
+        'W' => "hgcdtr",
+        'X' => "kntr",
+        'Y' => "kns",
+        'Z' => "abntfe",
+        '-' => "up",
+        '+' => "upms",
+        '*' => "kntrmsup",
+        '=' => "upfe",
+        '_' => "fe",
+        '/' => "nt",
+        '\\' => "kr",
+        '|' => "ms",
+        '(' | '<' => "nr",
+        ')' | '>' => "kt",
+        '[' => "ahgf",
+        ']' => "bcde",
+        '\'' => "m",
+        '"' => "hm",
+        '.' => "y",
+        ',' => "yt",
+        ':' => "xy",
+        '!' => "my",
+        _ => "abcpy",
+    }
+}
+
+/// Returns the end points of a segment of a glyph that is two units wide and two units high, with
+/// the origin in the top left corner. The segments are those of a sixteen-segment display, where
+/// the horizontal ones are split in half, plus two dots.
+fn segment_end_points(segment: char) -> [f32; 4] {
+    match segment {
+        // The outline, clockwise from the top left corner
+        'a' => [0.0, 0.0, 1.0, 0.0],
+        'b' => [1.0, 0.0, 2.0, 0.0],
+        'c' => [2.0, 0.0, 2.0, 1.0],
+        'd' => [2.0, 1.0, 2.0, 2.0],
+        'e' => [1.0, 2.0, 2.0, 2.0],
+        'f' => [0.0, 2.0, 1.0, 2.0],
+        'g' => [0.0, 1.0, 0.0, 2.0],
+        'h' => [0.0, 0.0, 0.0, 1.0],
+        // The spokes from the center, clockwise from the top left corner
+        'k' => [0.0, 0.0, 1.0, 1.0],
+        'm' => [1.0, 0.0, 1.0, 1.0],
+        'n' => [2.0, 0.0, 1.0, 1.0],
+        'p' => [1.0, 1.0, 2.0, 1.0],
+        'r' => [1.0, 1.0, 2.0, 2.0],
+        's' => [1.0, 1.0, 1.0, 2.0],
+        't' => [1.0, 1.0, 0.0, 2.0],
+        'u' => [0.0, 1.0, 1.0, 1.0],
+        // The dots of colons and full stops
+        'x' => [1.0, 0.55, 1.0, 0.7],
+        'y' => [1.0, 1.85, 1.0, 2.0],
+        _ => unreachable!("unknown glyph segment '{segment}'"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_are_drawn_as_line_lists() {
+        let mut dd = DebugDraw::default();
+        let white = Vec4::new(1.0, 1.0, 1.0, 1.0);
+
+        dd.aabb([-1.0, -1.0, -1.0, 1.0], [1.0, 1.0, 1.0, 1.0], white);
+        assert_eq!(dd.world_lines().len(), 24);
+
+        dd.axes(&Mat4::identity(), 2.0);
+        let x_axis = &dd.world_lines()[24..26];
+        assert_eq!(x_axis[0].position, [0.0, 0.0, 0.0]);
+        assert_eq!(x_axis[1].position, [2.0, 0.0, 0.0]);
+        assert_eq!(x_axis[1].color, [1.0, 0.0, 0.0, 1.0]);
+
+        dd.sphere([0.0, 0.0, 0.0, 1.0], 1.0, white);
+        assert_eq!(dd.world_lines().len(), 30 + 3 * CIRCLE_SEGMENTS * 2);
+        assert!(dd.screen_lines().is_empty());
+
+        dd.clear();
+        assert!(dd.is_empty());
+    }
+
+    #[test]
+    fn frustum_of_the_identity_is_the_clip_space_cube() {
+        let mut dd = DebugDraw::default();
+        dd.frustum(&Mat4::identity(), Vec4::new(1.0, 1.0, 0.0, 1.0));
+
+        assert_eq!(dd.world_lines().len(), 24);
+        assert!(
+            dd.world_lines()
+                .iter()
+                .all(|v| v.position.iter().all(|c| c.abs() == 1.0))
+        );
+    }
+
+    #[test]
+    fn text_is_laid_out_in_pixels() {
+        let mut dd = DebugDraw::default();
+        dd.text([10.0, 20.0], 10.0, "l1\n-", Vec4::new(1.0, 1.0, 1.0, 1.0));
+
+        // 'L' has four segments, '1' two and '-' two
+        let lines = dd.screen_lines();
+        assert_eq!(lines.len(), 2 * (4 + 2 + 2));
+        assert_eq!(lines[0].position, [10.0, 20.0, 0.0]);
+        // The second glyph starts one advance to the right
+        assert_eq!(lines[8].position, [10.0 + 9.0 + 6.0, 20.0, 0.0]);
+        // The third glyph starts on the next line, at half its height
+        assert_eq!(lines[12].position, [10.0, 20.0 + 14.0 + 5.0, 0.0]);
+
+        dd.set_enabled(false);
+        dd.text([0.0, 0.0], 10.0, "ignored", Vec4::new(1.0, 1.0, 1.0, 1.0));
+        assert!(dd.is_empty());
+    }
+}
@@ -1,2 +1,4 @@
+pub mod debug_draw;
+pub mod render_targets;
 pub mod rpc_settings;
 pub mod statistics;
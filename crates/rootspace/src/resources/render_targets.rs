@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use ecs::{Index, Resource, WithDependencies};
+use griffon::base::ids::{TextureId, TextureViewId};
+
+/// The textures of a camera that renders into a texture
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraRenderTarget {
+    pub width: u32,
+    pub height: u32,
+    pub texture: TextureId,
+    pub view: TextureViewId,
+    pub depth_texture: TextureId,
+    pub depth_view: TextureViewId,
+}
+
+/// Holds the render target textures of all cameras with a
+/// [`CameraTarget::Texture`](crate::components::camera::target::CameraTarget::Texture), indexed by
+/// the camera entity. The [`Renderer`](crate::systems::renderer::Renderer) creates the textures on
+/// demand, other systems may bind the views to sample the rendered image.
+#[derive(Debug, Default)]
+pub struct RenderTargets {
+    targets: HashMap<Index, CameraRenderTarget>,
+}
+
+impl RenderTargets {
+    pub fn get(&self, camera: Index) -> Option<&CameraRenderTarget> {
+        self.targets.get(&camera)
+    }
+
+    /// Returns the color texture view of the camera's render target
+    pub fn view(&self, camera: Index) -> Option<TextureViewId> {
+        self.targets.get(&camera).map(|t| t.view)
+    }
+
+    pub fn insert(&mut self, camera: Index, target: CameraRenderTarget) -> Option<CameraRenderTarget> {
+        self.targets.insert(camera, target)
+    }
+
+    pub fn remove(&mut self, camera: Index) -> Option<CameraRenderTarget> {
+        self.targets.remove(&camera)
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Removes and returns the render targets of cameras for which `f` returns `false`
+    pub fn retain<F: FnMut(Index) -> bool>(&mut self, mut f: F) -> Vec<CameraRenderTarget> {
+        self.targets.extract_if(|idx, _| !f(*idx)).map(|(_, t)| t).collect()
+    }
+}
+
+impl Resource for RenderTargets {}
+
+impl<D> WithDependencies<D> for RenderTargets {
+    #[tracing::instrument(skip_all)]
+    async fn with_deps(_: &D) -> anyhow::Result<Self> {
+        Ok(RenderTargets::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ecs::{End, Reg, ResourceRegistry, World};
+
+    #[test]
+    fn render_targets_reg_macro() {
+        type _RR = Reg![RenderTargets];
+    }
+
+    #[test]
+    fn render_targets_resource_registry() {
+        let _rr = ResourceRegistry::push(End, RenderTargets::default());
+    }
+
+    #[tokio::test]
+    async fn render_targets_world() {
+        let _w = World::with_dependencies::<Reg![RenderTargets], Reg![], Reg![], (), Reg![], _>(&())
+            .await
+            .unwrap();
+    }
+}
@@ -8,16 +8,22 @@ use std::{
 use serde::{Deserialize, Serialize};
 
 use ecs::{Resource, WithDependencies};
+use griffon::base::gpu_timer::PassTiming;
 
 const WINDOW_SIZE: usize = 10;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Statistics {
     draw_calls: VecDeque<usize>,
+    drawn_instances: VecDeque<usize>,
+    culled_instances: VecDeque<usize>,
     render_durations: VecDeque<Duration>,
     render_prepare_durations: VecDeque<Duration>,
     render_draw_durations: VecDeque<Duration>,
     render_submit_durations: VecDeque<Duration>,
+    /// The GPU time of each render pass per frame, only recorded if the device supports timestamp
+    /// queries
+    gpu_pass_durations: VecDeque<Vec<PassTiming>>,
     redraw_intervals: VecDeque<Duration>,
     maintenance_intervals: VecDeque<Duration>,
 }
@@ -27,6 +33,14 @@ impl Statistics {
         self.draw_calls.iter().sum::<usize>() as f32 / WINDOW_SIZE as f32
     }
 
+    pub fn mean_drawn_instances(&self) -> f32 {
+        self.drawn_instances.iter().sum::<usize>() as f32 / WINDOW_SIZE as f32
+    }
+
+    pub fn mean_culled_instances(&self) -> f32 {
+        self.culled_instances.iter().sum::<usize>() as f32 / WINDOW_SIZE as f32
+    }
+
     pub fn mean_render_duration(&self) -> Duration {
         self.render_durations
             .iter()
@@ -55,6 +69,30 @@ impl Statistics {
             .div_f32(WINDOW_SIZE as f32)
     }
 
+    /// Returns the mean GPU time of all render passes of a frame, or zero without GPU timings
+    pub fn mean_gpu_duration(&self) -> Duration {
+        self.mean_gpu_pass_durations().iter().map(|p| p.duration).sum()
+    }
+
+    /// Returns the mean GPU time per frame of each render pass, in the order the passes first
+    /// appear. Passes with the same label, such as the opaque passes of several cameras, are
+    /// added up.
+    pub fn mean_gpu_pass_durations(&self) -> Vec<PassTiming> {
+        let mut means: Vec<PassTiming> = Vec::new();
+        for pass in self.gpu_pass_durations.iter().flatten() {
+            match means.iter_mut().find(|m| m.label == pass.label) {
+                Some(mean) => mean.duration += pass.duration,
+                None => means.push(pass.clone()),
+            }
+        }
+
+        let frames = self.gpu_pass_durations.len().max(1) as f32;
+        for mean in &mut means {
+            mean.duration = mean.duration.div_f32(frames);
+        }
+        means
+    }
+
     pub fn mean_redraw_interval(&self) -> Duration {
         self.redraw_intervals
             .iter()
@@ -100,6 +138,28 @@ impl Statistics {
         }
     }
 
+    /// Records the GPU time of each render pass of one frame, which is available a few frames
+    /// after it was submitted
+    pub fn update_gpu_stats(&mut self, pass_durations: Vec<PassTiming>) {
+        self.gpu_pass_durations.push_front(pass_durations);
+        if self.gpu_pass_durations.len() > WINDOW_SIZE {
+            self.gpu_pass_durations.truncate(WINDOW_SIZE);
+        }
+    }
+
+    /// Records the number of instances that passed and failed frustum culling in one frame
+    pub fn update_culling_stats(&mut self, drawn_instances: usize, culled_instances: usize) {
+        self.drawn_instances.push_front(drawn_instances);
+        self.culled_instances.push_front(culled_instances);
+
+        if self.drawn_instances.len() > WINDOW_SIZE {
+            self.drawn_instances.truncate(WINDOW_SIZE);
+        }
+        if self.culled_instances.len() > WINDOW_SIZE {
+            self.culled_instances.truncate(WINDOW_SIZE);
+        }
+    }
+
     pub fn update_redraw_intervals(&mut self, redraw_interval: Duration) {
         self.redraw_intervals.push_front(redraw_interval);
         if self.redraw_intervals.len() > WINDOW_SIZE {
@@ -119,10 +179,13 @@ impl Default for Statistics {
     fn default() -> Self {
         Statistics {
             draw_calls: VecDeque::with_capacity(WINDOW_SIZE),
+            drawn_instances: VecDeque::with_capacity(WINDOW_SIZE),
+            culled_instances: VecDeque::with_capacity(WINDOW_SIZE),
             render_durations: VecDeque::with_capacity(WINDOW_SIZE),
             render_prepare_durations: VecDeque::with_capacity(WINDOW_SIZE),
             render_draw_durations: VecDeque::with_capacity(WINDOW_SIZE),
             render_submit_durations: VecDeque::with_capacity(WINDOW_SIZE),
+            gpu_pass_durations: VecDeque::with_capacity(WINDOW_SIZE),
             redraw_intervals: VecDeque::with_capacity(WINDOW_SIZE),
             maintenance_intervals: VecDeque::with_capacity(WINDOW_SIZE),
         }
@@ -135,20 +198,30 @@ impl Display for Statistics {
             f,
             r#"Loop and Render Stats:
 Draw calls (mean): {}
+Drawn instances (mean): {}
+Culled instances (mean): {}
 Render duration (mean): {}
 Prepare duration (mean): {}
 Draw duration (mean): {}
 Submit duration (mean): {}
+GPU duration (mean): {}
 Redraw interval (mean): {}
 Maintenance interval (mean): {}"#,
             self.mean_draw_calls(),
+            self.mean_drawn_instances(),
+            self.mean_culled_instances(),
             format_duration(self.mean_render_duration()),
             format_duration(self.mean_render_prepare_duration()),
             format_duration(self.mean_render_draw_duration()),
             format_duration(self.mean_render_submit_duration()),
+            format_duration(self.mean_gpu_duration()),
             format_duration(self.mean_redraw_interval()),
             format_duration(self.mean_maintenance_interval()),
-        )
+        )?;
+        for pass in self.mean_gpu_pass_durations() {
+            writeln!(f, "  {} (mean): {}", pass.label, format_duration(pass.duration))?;
+        }
+        Ok(())
     }
 }
 
@@ -176,6 +249,40 @@ mod tests {
         let _rr = ResourceRegistry::push(End, Statistics::default());
     }
 
+    #[test]
+    fn culling_stats_are_averaged_over_the_window() {
+        let mut stats = Statistics::default();
+        for _ in 0..WINDOW_SIZE + 5 {
+            stats.update_culling_stats(3, 7);
+        }
+        assert_eq!(stats.mean_drawn_instances(), 3.0);
+        assert_eq!(stats.mean_culled_instances(), 7.0);
+    }
+
+    #[test]
+    fn gpu_pass_durations_are_added_up_per_label() {
+        let pass = |label: &str, micros: u64| PassTiming {
+            label: label.into(),
+            duration: Duration::from_micros(micros),
+        };
+
+        let mut stats = Statistics::default();
+        assert!(stats.mean_gpu_pass_durations().is_empty());
+        stats.update_gpu_stats(vec![
+            pass("shadow-pass", 100),
+            pass("opaque-pass", 200),
+            pass("opaque-pass", 100),
+        ]);
+        stats.update_gpu_stats(vec![pass("shadow-pass", 300), pass("opaque-pass", 500)]);
+
+        assert_eq!(
+            stats.mean_gpu_pass_durations(),
+            vec![pass("shadow-pass", 200), pass("opaque-pass", 400)]
+        );
+        assert_eq!(stats.mean_gpu_duration(), Duration::from_micros(600));
+        assert!(stats.to_string().contains("opaque-pass (mean): 400us"));
+    }
+
     #[tokio::test]
     async fn statistics_world() {
         let _w = World::with_dependencies::<Reg![Statistics], Reg![], Reg![], (), Reg![], _>(&())
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::components::{animation::Animation, transform::Transform};
+use ecs::{Resources, Storage, System, WithResources};
+use glamour::{quat::Quat, unit::Unit, vec::Vec4};
+
+/// Advances every [`Animation`] and applies the blended pose to the transforms of its targets
+#[derive(Debug)]
+pub struct Animator;
+
+impl WithResources for Animator {
+    #[tracing::instrument(skip_all)]
+    async fn with_res(_res: &Resources) -> anyhow::Result<Self> {
+        Ok(Animator)
+    }
+}
+
+#[async_trait]
+impl System for Animator {
+    #[tracing::instrument(skip_all)]
+    async fn run(&mut self, res: &Resources, _t: Duration, dt: Duration) {
+        let poses: Vec<_> = res
+            .iter_w::<Animation>()
+            .flat_map(|(_, animation)| {
+                animation.advance(dt.as_secs_f32());
+                animation.pose()
+            })
+            .collect();
+
+        let mut transforms = res.write_components::<Transform>();
+        for (target, pose) in poses {
+            let Some(transform) = transforms.get_mut(target) else {
+                continue;
+            };
+
+            if let Some([x, y, z]) = pose.translation {
+                transform.affine.t = Vec4::new_point(x, y, z);
+            }
+            if let Some([qi, qj, qk, qw]) = pose.rotation {
+                transform.affine.o = Unit::from(Quat::new(qw, qi, qj, qk));
+            }
+            if let Some([sx, sy, sz]) = pose.scale {
+                // Transforms only support uniform scales
+                transform.affine.s = (sx + sy + sz) / 3.0;
+            }
+        }
+    }
+}
@@ -28,7 +28,7 @@ impl CameraManager {
 
         res.write_components::<Camera>()
             .iter_mut()
-            .for_each(|c| c.set_dimensions(width, height));
+            .for_each(|c| c.fit_to_surface(width, height));
     }
 }
 
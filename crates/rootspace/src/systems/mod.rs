@@ -1,3 +1,4 @@
+pub mod animator;
 pub mod camera_controller;
 pub mod camera_manager;
 pub mod debug_animator;
@@ -0,0 +1,212 @@
+use glamour::mat::Mat4;
+use griffon::assets::mesh_processing::BoundingBox;
+
+/// A plane `normal · p + distance = 0`, with the normal pointing into the half-space that is kept
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: [f32; 3],
+    pub distance: f32,
+}
+
+impl Plane {
+    fn from_row(r: [f32; 4]) -> Self {
+        let len = (r[0] * r[0] + r[1] * r[1] + r[2] * r[2]).sqrt().max(f32::EPSILON);
+        Plane {
+            normal: [r[0] / len, r[1] / len, r[2] / len],
+            distance: r[3] / len,
+        }
+    }
+
+    fn signed_distance(&self, p: [f32; 3]) -> f32 {
+        self.normal[0] * p[0] + self.normal[1] * p[1] + self.normal[2] * p[2] + self.distance
+    }
+}
+
+/// The view volume of a camera in world space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the frustum planes from a world-to-clip matrix (row-major) that maps depth to the
+    /// range 0 to 1, as expected by wgpu
+    pub fn with_matrix(m: &Mat4<f32>) -> Self {
+        let r = m.0;
+        let add = |a: [f32; 4], b: [f32; 4]| [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]];
+        let sub = |a: [f32; 4], b: [f32; 4]| [a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]];
+
+        Frustum {
+            planes: [
+                Plane::from_row(add(r[3], r[0])),
+                Plane::from_row(sub(r[3], r[0])),
+                Plane::from_row(add(r[3], r[1])),
+                Plane::from_row(sub(r[3], r[1])),
+                Plane::from_row(r[2]),
+                Plane::from_row(sub(r[3], r[2])),
+            ],
+        }
+    }
+
+    /// Returns `false` if the bounding box, transformed to world space by `model`, lies entirely
+    /// outside of the frustum. The test is conservative: boxes close to the frustum corners may be
+    /// reported as intersecting.
+    pub fn intersects(&self, model: &Mat4<f32>, bounds: &BoundingBox) -> bool {
+        let (center, extents) = world_box(model, bounds);
+        self.planes.iter().all(|p| {
+            let radius =
+                p.normal[0].abs() * extents[0] + p.normal[1].abs() * extents[1] + p.normal[2].abs() * extents[2];
+            p.signed_distance(center) >= -radius
+        })
+    }
+}
+
+/// The outcome of [`cull_and_sort`], as indices into the list of objects
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Visibility {
+    /// Objects inside at least one frustum with their squared distance to the viewer, nearest first
+    pub visible: Vec<(usize, f32)>,
+    /// Objects outside of all frustums, in their original order
+    pub culled: Vec<usize>,
+}
+
+/// Determines which objects, given by their model matrix and local bounding box, intersect the
+/// view frustum of at least one camera, and sorts the visible objects front to back by the
+/// distance of their center from the `viewer` position in world space. Without a viewer, the
+/// visible objects keep their original order.
+pub fn cull_and_sort(
+    frustums: &[Frustum],
+    viewer: Option<[f32; 3]>,
+    objects: &[(Mat4<f32>, BoundingBox)],
+) -> Visibility {
+    let mut visibility = Visibility::default();
+    for (i, (model, bounds)) in objects.iter().enumerate() {
+        if frustums.iter().any(|f| f.intersects(model, bounds)) {
+            let distance = viewer.map_or(0.0, |v| {
+                let c = transformed_center(model, bounds);
+                (c[0] - v[0]).powi(2) + (c[1] - v[1]).powi(2) + (c[2] - v[2]).powi(2)
+            });
+            visibility.visible.push((i, distance));
+        } else {
+            visibility.culled.push(i);
+        }
+    }
+
+    visibility.visible.sort_by(|a, b| a.1.total_cmp(&b.1));
+    visibility
+}
+
+/// Returns the center of the bounding box in world space
+pub fn transformed_center(model: &Mat4<f32>, bounds: &BoundingBox) -> [f32; 3] {
+    world_box(model, bounds).0
+}
+
+/// Returns the center and the half extents of the axis-aligned box that encloses the transformed
+/// bounding box
+fn world_box(model: &Mat4<f32>, bounds: &BoundingBox) -> ([f32; 3], [f32; 3]) {
+    let m = model.0;
+    let c = bounds.center();
+    let e = bounds.half_extents();
+
+    let mut center = [0.0; 3];
+    let mut extents = [0.0; 3];
+    for i in 0..3 {
+        center[i] = m[i][0] * c[0] + m[i][1] * c[1] + m[i][2] * c[2] + m[i][3];
+        extents[i] = m[i][0].abs() * e[0] + m[i][1].abs() * e[1] + m[i][2].abs() * e[2];
+    }
+    (center, extents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIT_BOX: BoundingBox = BoundingBox {
+        min: [-0.5, -0.5, -0.5],
+        max: [0.5, 0.5, 0.5],
+    };
+
+    fn translation(x: f32, y: f32, z: f32) -> Mat4<f32> {
+        Mat4::new([
+            [1.0, 0.0, 0.0, x],
+            [0.0, 1.0, 0.0, y],
+            [0.0, 0.0, 1.0, z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// An orthographic view of the box from -10 to 10 along x and y, and from 0 to 10 along z
+    fn box_frustum() -> Frustum {
+        Frustum::with_matrix(&Mat4::new([
+            [0.1, 0.0, 0.0, 0.0],
+            [0.0, 0.1, 0.0, 0.0],
+            [0.0, 0.0, 0.1, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]))
+    }
+
+    #[test]
+    fn boxes_inside_and_on_the_boundary_intersect() {
+        let f = box_frustum();
+        assert!(f.intersects(&translation(0.0, 0.0, 5.0), &UNIT_BOX));
+        assert!(f.intersects(&translation(10.3, 0.0, 5.0), &UNIT_BOX));
+        assert!(f.intersects(&translation(0.0, 0.0, -0.4), &UNIT_BOX));
+    }
+
+    #[test]
+    fn boxes_outside_do_not_intersect() {
+        let f = box_frustum();
+        assert!(!f.intersects(&translation(11.0, 0.0, 5.0), &UNIT_BOX));
+        assert!(!f.intersects(&translation(0.0, -11.0, 5.0), &UNIT_BOX));
+        assert!(!f.intersects(&translation(0.0, 0.0, -1.0), &UNIT_BOX));
+        assert!(!f.intersects(&translation(0.0, 0.0, 11.0), &UNIT_BOX));
+    }
+
+    #[test]
+    fn model_scale_enlarges_the_box() {
+        let mut scaled = translation(12.0, 0.0, 5.0);
+        for row in scaled.0.iter_mut().take(3) {
+            for v in row.iter_mut().take(3) {
+                *v *= 5.0;
+            }
+        }
+        assert!(box_frustum().intersects(&scaled, &UNIT_BOX));
+    }
+
+    #[test]
+    fn cull_and_sort_orders_visible_objects_front_to_back() {
+        let objects = [
+            (translation(0.0, 0.0, 8.0), UNIT_BOX),
+            (translation(50.0, 0.0, 5.0), UNIT_BOX),
+            (translation(0.0, 0.0, 2.0), UNIT_BOX),
+            (translation(0.0, 0.0, 5.0), UNIT_BOX),
+        ];
+        let v = cull_and_sort(&[box_frustum()], Some([0.0, 0.0, 0.0]), &objects);
+        assert_eq!(v.visible.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![2, 3, 0]);
+        assert_eq!(v.culled, vec![1]);
+    }
+
+    #[test]
+    fn objects_are_visible_if_any_frustum_contains_them() {
+        let shifted = Frustum::with_matrix(&Mat4::new([
+            [0.1, 0.0, 0.0, -5.0],
+            [0.0, 0.1, 0.0, 0.0],
+            [0.0, 0.0, 0.1, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]));
+        let objects = [(translation(50.0, 0.0, 5.0), UNIT_BOX)];
+        assert_eq!(cull_and_sort(&[box_frustum()], None, &objects).culled, vec![0]);
+        assert_eq!(
+            cull_and_sort(&[box_frustum(), shifted], None, &objects).visible,
+            vec![(0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn without_frustums_everything_is_culled() {
+        let objects = [(translation(0.0, 0.0, 5.0), UNIT_BOX)];
+        let v = cull_and_sort(&[], None, &objects);
+        assert!(v.visible.is_empty());
+        assert_eq!(v.culled, vec![0]);
+    }
+}
@@ -0,0 +1,1666 @@
+use std::{
+    collections::HashMap,
+    mem::size_of,
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    components::{
+        camera::{Camera, projection::Projection, target::CameraTarget},
+        skeleton::Skeleton,
+        transform::{GlobalTransforms, Transform},
+    },
+    events::engine_event::EngineEvent,
+    resources::{
+        debug_draw::DebugDraw,
+        render_targets::{CameraRenderTarget, RenderTargets},
+        statistics::Statistics,
+    },
+};
+use anyhow::Context;
+use assam::AssetDatabase;
+use async_trait::async_trait;
+use ecs::{EventQueue, Index, ReceiverId, Resources, Storage, System, WithResources};
+use glamour::affine::Affine;
+use glamour::num::ToMatrix;
+use glamour::ortho::Ortho;
+use glamour::persp::Persp;
+use glamour::vec::Vec4;
+use glamour::{affine::builder::AffineBuilder, mat::Mat4};
+use griffon::assets::cpu_mesh::Submesh;
+use griffon::assets::shader_source::{SHADER_ASSET_GROUP, ShaderSource};
+use griffon::base::blend_mode::BlendMode;
+use griffon::base::camera_uniform::CameraUniform;
+use griffon::base::encoder::RenderPass;
+use griffon::base::gpu_material::GpuMaterial;
+use griffon::base::ids::{BindGroupId, BufferId, PipelineId, SamplerId, TextureViewId};
+use griffon::base::instance::{Instance, NO_SKELETON};
+use griffon::base::light_uniform::{LightBufferHeader, LightUniform, ShadowUniform};
+use griffon::base::line_vertex::LineVertex;
+use griffon::base::post_process_settings::HDR_TEXTURE_FORMAT;
+use griffon::base::post_process_uniform::PostProcessUniform;
+use griffon::base::render_graph::{RenderGraph, TextureHandle};
+use griffon::base::sampler_builder::SamplerSettings;
+use griffon::base::skinned_vertex::SkinnedVertex;
+use griffon::base::vertex::Vertex;
+use griffon::components::light::{Light, LightKind};
+use griffon::components::renderable::Renderable;
+use griffon::resources::Graphics;
+use griffon::wgpu::{BufferUsages, CompareFunction, PrimitiveTopology, SurfaceError};
+use griffon::winit::{dpi::PhysicalSize, event::WindowEvent};
+use itertools::Itertools;
+use num_traits::Inv;
+use rose_tree::hierarchy::Hierarchy;
+use tracing::warn;
+
+use self::culling::Frustum;
+use self::post_process::{PostProcessPipelines, PostProcessTargets};
+
+pub mod culling;
+pub mod post_process;
+
+const LIGHT_DEBUG_SHADER: &str = "light_debug.wgsl";
+const WITH_CAMERA_AND_MATERIAL_SHADER: &str = "with_camera_and_material.wgsl";
+const SHADOW_SHADER: &str = "shadow.wgsl";
+const BLOOM_SHADER: &str = "bloom.wgsl";
+const TONEMAPPING_SHADER: &str = "tonemapping.wgsl";
+const FXAA_SHADER: &str = "fxaa.wgsl";
+const DEBUG_LINES_SHADER: &str = "debug_lines.wgsl";
+/// Lists the shader assets loaded by the [`Renderer`]
+pub const SHADER_ASSETS: &[&str] = &[
+    LIGHT_DEBUG_SHADER,
+    WITH_CAMERA_AND_MATERIAL_SHADER,
+    SHADOW_SHADER,
+    BLOOM_SHADER,
+    TONEMAPPING_SHADER,
+    FXAA_SHADER,
+    DEBUG_LINES_SHADER,
+];
+
+/// The initial capacity of the vertex buffer of the [`DebugDraw`] lines
+const INITIAL_DEBUG_VERTEX_CAPACITY: u64 = 1024;
+
+/// The near plane distance of shadow map projections
+const SHADOW_NEAR_Z: f32 = 0.1;
+
+/// Maps OpenGL clip space depth (-1 to 1) as produced by [`Persp`] and [`Ortho`] to the wgpu
+/// depth range (0 to 1), such that camera and shadow map projections keep their entire frustum
+const GL_TO_WGPU_DEPTH: Mat4<f32> = Mat4::new([
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 0.5, 0.5],
+    [0.0, 0.0, 0.0, 1.0],
+]);
+
+#[derive(Debug)]
+pub struct Renderer {
+    window_receiver: ReceiverId<WindowEvent>,
+    engine_receiver: ReceiverId<EngineEvent>,
+    renderer_enabled: bool,
+    camera_buffer: BufferId,
+    camera_bind_group: BindGroupId,
+    camera_uniform_stride: u32,
+    light_buffer: BufferId,
+    light_bind_group: BindGroupId,
+    default_material_bind_group: BindGroupId,
+    shadow_atlas_view: TextureViewId,
+    shadow_buffer: BufferId,
+    shadow_bind_group: BindGroupId,
+    shadow_uniform_stride: u32,
+    shadow_casters_skipped: bool,
+    joint_buffer: BufferId,
+    joint_bind_group: BindGroupId,
+    joints_skipped: bool,
+    pipeline_ldb: PipelineId,
+    pipelines_wcm: HashMap<BlendMode, PipelineId>,
+    /// Variants of the scene pipelines that render into the HDR texture of the post-processing
+    pipeline_ldb_hdr: PipelineId,
+    pipelines_wcm_hdr: HashMap<BlendMode, PipelineId>,
+    /// Variants of the scene pipelines for skinned meshes
+    pipelines_wcm_skinned: HashMap<BlendMode, PipelineId>,
+    pipelines_wcm_skinned_hdr: HashMap<BlendMode, PipelineId>,
+    pipeline_shadow: PipelineId,
+    pipeline_shadow_skinned: PipelineId,
+    pipeline_shadow_alpha_test: PipelineId,
+    pipeline_shadow_alpha_test_skinned: PipelineId,
+    /// Draw the world lines of [`DebugDraw`] into the surface or the HDR texture, and its screen
+    /// lines into the surface
+    pipeline_debug: PipelineId,
+    pipeline_debug_hdr: PipelineId,
+    pipeline_debug_screen: PipelineId,
+    debug_vertex_buffer: BufferId,
+    post_process_buffer: BufferId,
+    post_process_sampler: SamplerId,
+    post_process_pipelines: PostProcessPipelines,
+    /// Only present while post-processing is enabled
+    post_process_targets: Option<PostProcessTargets>,
+}
+
+impl Renderer {
+    #[tracing::instrument(skip_all)]
+    fn handle_events(&mut self, res: &Resources) {
+        res.write::<EventQueue<WindowEvent>>()
+            .receive_cb(&self.window_receiver, |e| {
+                if let WindowEvent::Resized(ps) = e {
+                    self.on_window_resized(res, *ps)
+                }
+            });
+
+        res.write::<EventQueue<EngineEvent>>()
+            .receive_cb(&self.engine_receiver, |e| match e {
+                EngineEvent::Exit => self.renderer_enabled = false,
+                EngineEvent::SetWindowMode(mode) => res.write::<Graphics>().set_window_mode(*mode),
+                EngineEvent::SetVsync(vsync) => res.write::<Graphics>().set_vsync(*vsync),
+                EngineEvent::SetPostProcess(settings) => res.write::<Graphics>().set_post_process_settings(*settings),
+            });
+    }
+
+    /// Creates the textures of cameras that render into a texture and releases those of cameras
+    /// that no longer do
+    #[tracing::instrument(skip_all)]
+    fn update_render_targets(&mut self, res: &Resources) {
+        let cameras = res.read_components::<Camera>();
+        let mut targets = res.write::<RenderTargets>();
+        let removed = targets.retain(|idx| {
+            cameras
+                .get(idx)
+                .is_some_and(|c| matches!(c.target(), CameraTarget::Texture { .. }))
+        });
+
+        let mut gfx = res.write::<Graphics>();
+        for target in removed {
+            Self::release_render_target(&mut gfx, target);
+        }
+        for (idx, cam) in cameras.indexed_iter() {
+            let CameraTarget::Texture { width, height } = cam.target() else {
+                continue;
+            };
+            if targets.get(idx).is_some_and(|t| t.width == width && t.height == height) {
+                continue;
+            }
+
+            tracing::debug!("Creating a {width}x{height} render target for camera {idx}");
+            let texture = gfx
+                .create_texture()
+                .with_label(Some("camera-target"))
+                .with_render_target()
+                .with_size(width, height)
+                .submit();
+            let view = gfx.create_texture_view(Some("camera-target-view"), texture);
+            let depth_texture = gfx
+                .create_texture()
+                .with_label(Some("camera-target-depth"))
+                .with_depth_texture()
+                .with_size(width, height)
+                .submit();
+            let depth_view = gfx.create_texture_view(Some("camera-target-depth-view"), depth_texture);
+            let replaced = targets.insert(
+                idx,
+                CameraRenderTarget {
+                    width,
+                    height,
+                    texture,
+                    view,
+                    depth_texture,
+                    depth_view,
+                },
+            );
+            if let Some(target) = replaced {
+                Self::release_render_target(&mut gfx, target);
+            }
+        }
+    }
+
+    /// Creates the textures of the post-processing passes if it is enabled, and recreates them
+    /// when the surface is resized
+    #[tracing::instrument(skip_all)]
+    fn update_post_process_targets(&mut self, res: &Resources) {
+        let mut gfx = res.write::<Graphics>();
+        let settings = *gfx.post_process_settings();
+        let size = gfx.surface_size();
+        if self
+            .post_process_targets
+            .as_ref()
+            .is_some_and(|t| settings.enabled && t.matches(size, settings.bloom.mip_levels))
+        {
+            return;
+        }
+
+        if let Some(targets) = self.post_process_targets.take() {
+            targets.release(&mut gfx);
+        }
+        if settings.enabled {
+            tracing::debug!("Creating the {}x{} post-processing targets", size.width, size.height);
+            self.post_process_targets = Some(PostProcessTargets::new(
+                &mut gfx,
+                size,
+                settings.bloom.mip_levels,
+                self.post_process_buffer,
+                self.post_process_sampler,
+            ));
+        }
+    }
+
+    fn release_render_target(gfx: &mut Graphics, target: CameraRenderTarget) {
+        gfx.remove_texture_view(target.view);
+        gfx.remove_texture(target.texture);
+        gfx.remove_texture_view(target.depth_view);
+        gfx.remove_texture(target.depth_texture);
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn prepare<'a>(&mut self, res: &'a Resources) -> DrawData<'a> {
+        let gfx = res.read::<Graphics>();
+        let hier = res.read::<Hierarchy<Index>>();
+        let transforms = res.read_components::<Transform>();
+        let globals = GlobalTransforms::new(&hier, &transforms);
+        let skeletons = res.read_components::<Skeleton>();
+
+        // 1. Perform validation for cameras and lights
+        // 2. For each active camera, write the view and projection matrices to its slot of the
+        //    camera uniform buffer
+        // 3. For each instance, write the model matrix to the instance buffer, and the joint
+        //    matrices of skinned instances to the joint buffer
+        // 4. Do the same as step 3 for each light
+        // 5. Cull instances outside of all camera frustums and sort the rest front to back
+
+        // Validate the number of cameras and light sources
+        let max_cameras = gfx.max_cameras() as usize;
+        let num_cameras = res.read_components::<Camera>().len();
+        if num_cameras > max_cameras {
+            panic!("Too many cameras: have {num_cameras}, expected only {max_cameras}.");
+        }
+
+        let max_lights = gfx.max_lights() as usize;
+        let num_lights = res.read_components::<Light>().len();
+        if num_lights > max_lights {
+            panic!("Too many light sources: have {num_lights}, expected only {max_lights}.");
+        }
+
+        // Calculate all camera transforms and the respective buffer offset. Cameras that render
+        // into textures come first, such that their images are up to date when the surface
+        // cameras sample them. On each target, the user interface is drawn on top of the scene.
+        let render_targets = res.read::<RenderTargets>();
+        let surface_size = gfx.window_inner_size();
+        let mut camera_buffer_data: Vec<u8> = Vec::new();
+        let mut frustums: Vec<Frustum> = Vec::new();
+        let mut viewer: Option<[f32; 3]> = None;
+        let mut camera_draw_data: Vec<CameraDrawData> = res
+            .iter_rr::<Camera, Transform>()
+            .filter(|(_, cam, _)| cam.is_active())
+            .sorted_by_key(|(idx, cam, _)| {
+                (
+                    cam.target() == CameraTarget::Surface,
+                    cam.projection() == Projection::ScreenSpace,
+                    cam.priority(),
+                    *idx,
+                )
+            })
+            .filter_map(|(idx, cam, trf)| {
+                let (target, width, height) = match cam.target() {
+                    CameraTarget::Surface => (None, surface_size.width, surface_size.height),
+                    CameraTarget::Texture { .. } => {
+                        let t = render_targets.get(idx)?;
+                        (Some((t.view, t.depth_view)), t.width, t.height)
+                    }
+                };
+
+                // The transform of a camera is its view matrix, which undoes the transforms of its
+                // ancestors after its own
+                let camera_view = trf.to_matrix() * globals.parent_matrix(idx).inv();
+                let uniform_offset = self.push_camera_uniform(&mut camera_buffer_data, cam, camera_view);
+
+                // Instances are sorted by their distance from the first camera that renders the
+                // scene onto the surface
+                if cam.projection() != Projection::ScreenSpace {
+                    frustums.push(Frustum::with_matrix(
+                        &(GL_TO_WGPU_DEPTH * cam.projection_matrix() * camera_view),
+                    ));
+                    if viewer.is_none() && cam.target() == CameraTarget::Surface {
+                        let position = camera_view.inv();
+                        viewer = Some([position.0[0][3], position.0[1][3], position.0[2][3]]);
+                    }
+                }
+
+                let ui = cam.projection() == Projection::ScreenSpace;
+                Some(CameraDrawData {
+                    uniform_offset,
+                    viewport: cam.viewport().to_pixels(width, height),
+                    target,
+                    ui,
+                    hdr: target.is_none() && !ui && self.post_process_targets.is_some(),
+                    view: camera_view,
+                    transparent: Vec::new(),
+                })
+            })
+            .collect();
+
+        // Without a screen space camera on the surface, the user interface is drawn across the
+        // entire surface
+        if !camera_draw_data.iter().any(|c| c.ui && c.target.is_none()) {
+            let mut ui_camera = Camera::default().with_projection(Projection::ScreenSpace);
+            ui_camera.fit_to_surface(surface_size.width, surface_size.height);
+            let uniform_offset = self.push_camera_uniform(&mut camera_buffer_data, &ui_camera, Mat4::identity());
+            camera_draw_data.push(CameraDrawData {
+                uniform_offset,
+                viewport: ui_camera.viewport().to_pixels(surface_size.width, surface_size.height),
+                target: None,
+                ui: true,
+                hdr: false,
+                view: Mat4::identity(),
+                transparent: Vec::new(),
+            });
+        }
+
+        // Iterate through all entities with a renderable and transform
+        // Group them by instance buffer ID, in the order in which the groups are first encountered
+        // Within each group, order the instances such that those inside a camera frustum come
+        // first, nearest first, followed by the culled instances and the user interface instances
+        // Convert the transforms to instances
+        let mut groups: Vec<(BufferId, Vec<GroupMember>)> = Vec::new();
+        let mut group_indices: HashMap<BufferId, usize> = HashMap::new();
+        for (idx, ren, trf) in res.iter_rr::<Renderable, Transform>() {
+            let instance_buffer = ren.model.mesh.instance_buffer;
+            let i = *group_indices.entry(instance_buffer).or_insert_with(|| {
+                groups.push((instance_buffer, Vec::new()));
+                groups.len() - 1
+            });
+            groups[i].1.push((idx, ren, trf));
+        }
+
+        // Skinned instances that do not fit into the joint buffer are drawn in their bind pose
+        let max_joints = gfx.max_joints() as usize;
+        let mut joint_buffer_data: Vec<[[f32; 4]; 4]> = Vec::new();
+        let mut joints_skipped = 0usize;
+        let mut push_joint_matrices = |idx: Index| -> u32 {
+            let Some(skeleton) = skeletons.get(idx) else {
+                return NO_SKELETON;
+            };
+            if joint_buffer_data.len() + skeleton.joints().len() > max_joints {
+                joints_skipped += skeleton.joints().len();
+                return NO_SKELETON;
+            }
+
+            let joint_offset = joint_buffer_data.len() as u32;
+            joint_buffer_data.extend(
+                skeleton
+                    .joint_matrices(idx, &globals, &transforms)
+                    .iter()
+                    // Transpose the matrix to go from row-major (CPU) to column-major (GPU).
+                    .map(|m| m.t().0),
+            );
+            joint_offset
+        };
+
+        let mut drawn_instances = 0;
+        let mut culled_instances = 0;
+        let mut sorted_draw_data: Vec<(f32, InstanceDrawData)> = Vec::new();
+        let mut instance_buffer_data: HashMap<BufferId, Vec<Instance>> = HashMap::new();
+        for (instance_buffer, members) in groups {
+            let (_, ren, _) = members[0];
+            let (ui_members, world_members): (Vec<_>, Vec<_>) = members
+                .into_iter()
+                .sorted_by_key(|(_, ren, _)| ren.model.mesh.instance_id)
+                .partition(|(_, _, trf)| trf.ui);
+
+            let objects: Vec<_> = world_members
+                .iter()
+                .map(|(idx, ren, trf)| (globals.matrix(*idx, trf), ren.model.mesh.bounds))
+                .collect();
+            let visibility = culling::cull_and_sort(&frustums, viewer, &objects);
+            let nearest = visibility.visible.first().map_or(f32::INFINITY, |(_, d)| *d);
+            drawn_instances += visibility.visible.len();
+            culled_instances += visibility.culled.len();
+
+            let ordered = visibility
+                .visible
+                .iter()
+                .map(|(i, _)| *i)
+                .chain(visibility.culled.iter().copied())
+                .map(|i| (world_members[i].0, world_members[i].1, objects[i].0))
+                .chain(
+                    ui_members
+                        .iter()
+                        .map(|(idx, ren, trf)| (*idx, *ren, globals.matrix(*idx, trf))),
+                );
+
+            let mut centers = Vec::new();
+            let instance_data: Vec<_> = ordered
+                .inspect(|(_, ren, model)| centers.push(culling::transformed_center(model, &ren.model.mesh.bounds)))
+                .map(|(idx, ren, model)| Instance {
+                    // Transpose the matrix to go from row-major (CPU) to column-major (GPU).
+                    model: model.t().0,
+                    // The correct normal matrix is the inverse-transpose of the model matrix. But we can elide the transpose operation thanks to the change from row-major (CPU) to column-major (GPU).
+                    normal: model.inv().0,
+                    with_material: if ren.model.materials.is_empty() { 0.0 } else { 1.0 },
+                    joint_offset: if ren.model.mesh.skinned {
+                        push_joint_matrices(idx)
+                    } else {
+                        NO_SKELETON
+                    },
+                })
+                .collect();
+
+            let to_u32 = |n: usize| {
+                u32::try_from(n).unwrap_or_else(|e| panic!("at most {} instances are supported: {e}", u32::MAX))
+            };
+            let visible = to_u32(visibility.visible.len());
+            let world_instances = to_u32(world_members.len());
+            let instances = to_u32(instance_data.len());
+
+            let idd = InstanceDrawData {
+                vertex_buffer: ren.model.mesh.vertex_buffer,
+                instance_buffer,
+                index_buffer: ren.model.mesh.index_buffer,
+                skinned: ren.model.mesh.skinned,
+                submeshes: ren.model.mesh.submeshes.as_slice(),
+                materials: &ren.model.materials,
+                instance_indexes: 0..visible,
+                shadow_instance_indexes: 0..world_instances,
+                ui_instance_indexes: world_instances..instances,
+                centers,
+            };
+
+            sorted_draw_data.push((nearest, idd));
+
+            instance_buffer_data.insert(instance_buffer, instance_data);
+        }
+
+        if joints_skipped > 0 && !self.joints_skipped {
+            warn!(
+                "Only {max_joints} joint matrices fit into the joint buffer, drawing {joints_skipped} joints in their bind pose"
+            );
+        }
+        self.joints_skipped = joints_skipped > 0;
+
+        // Draw the groups front to back as well, by their nearest visible instance
+        sorted_draw_data.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let instance_draw_data: Vec<InstanceDrawData> = sorted_draw_data.into_iter().map(|(_, idd)| idd).collect();
+
+        // Transparent submeshes are drawn one instance at a time, sorted back to front by their
+        // depth in the view of each camera
+        for camera in &mut camera_draw_data {
+            for (i, instance_data) in instance_draw_data.iter().enumerate() {
+                let instance_indexes = instance_data.indexes_for(camera.ui);
+                for (j, submesh) in instance_data.submeshes.iter().enumerate() {
+                    if !instance_data.blend_mode(submesh).is_transparent() {
+                        continue;
+                    }
+                    for instance in instance_indexes.clone() {
+                        let [x, y, z] = instance_data.centers[instance as usize];
+                        let v = camera.view.0[2];
+                        camera.transparent.push(TransparentDrawData {
+                            instance_data: i,
+                            submesh: j,
+                            instance,
+                            depth: -(v[0] * x + v[1] * y + v[2] * z + v[3]),
+                        });
+                    }
+                }
+            }
+            camera.transparent.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+        }
+
+        // Shadow maps are assigned to tiles of the shadow atlas in the order of the lights
+        let atlas_size = gfx.shadow_atlas_size();
+        let map_size = gfx.shadow_map_size();
+        let max_shadow_casters = gfx.max_shadow_casters() as usize;
+        let tiles_per_row = atlas_size / map_size.max(1);
+        let tile_extent = map_size as f32 / atlas_size as f32;
+
+        let mut light_draw_data: Vec<LightDrawData> = Vec::new();
+        let mut light_buffer_data: Vec<LightUniform> = Vec::new();
+        let mut shadow_draw_data: Vec<ShadowDrawData> = Vec::new();
+        let mut shadow_buffer_data: Vec<u8> = Vec::new();
+        let mut shadow_casters_skipped = 0usize;
+        res.iter_r::<Light>().for_each(|(_, lght)| {
+            let ldd = LightDrawData {
+                vertex_buffer: lght.model.mesh.vertex_buffer,
+                index_buffer: lght.model.mesh.index_buffer,
+                num_indices: lght.model.mesh.num_indices,
+            };
+
+            let model = AffineBuilder::default()
+                .with_translation(lght.position)
+                .build()
+                .to_matrix();
+
+            let mut shadow_world_to_clip = Mat4::<f32>::identity();
+            let mut shadow_atlas_rect = [0.0; 4];
+            let mut casts_shadows = false;
+            if lght.casts_shadows && lght.kind != LightKind::Point {
+                let caster = shadow_draw_data.len();
+                if caster < max_shadow_casters {
+                    let world_to_clip = GL_TO_WGPU_DEPTH * shadow_projection(lght);
+                    let (column, row) = (caster as u32 % tiles_per_row, caster as u32 / tiles_per_row);
+                    shadow_atlas_rect = [
+                        column as f32 * tile_extent,
+                        row as f32 * tile_extent,
+                        tile_extent,
+                        tile_extent,
+                    ];
+                    shadow_world_to_clip = world_to_clip;
+                    casts_shadows = true;
+
+                    shadow_draw_data.push(ShadowDrawData {
+                        uniform_offset: caster as u32 * self.shadow_uniform_stride,
+                        viewport: [
+                            (column * map_size) as f32,
+                            (row * map_size) as f32,
+                            map_size as f32,
+                            map_size as f32,
+                        ],
+                    });
+                    let su = ShadowUniform {
+                        // Transpose the matrix to go from row-major (CPU) to column-major (GPU).
+                        world_to_clip: world_to_clip.t().0,
+                    };
+                    shadow_buffer_data.extend_from_slice(bytemuck::bytes_of(&su));
+                    shadow_buffer_data.resize(shadow_draw_data.len() * self.shadow_uniform_stride as usize, 0);
+                } else {
+                    shadow_casters_skipped += 1;
+                }
+            }
+
+            let lu = LightUniform {
+                // Transpose the matrix to go from row-major (CPU) to column-major (GPU).
+                model: model.t().0,
+                direction: lght.direction.into(),
+                ambient_color: lght.ambient_color.into(),
+                diffuse_color: lght.diffuse_color.into(),
+                specular_color: lght.specular_color.into(),
+                attenuation: [
+                    lght.attenuation.constant,
+                    lght.attenuation.linear,
+                    lght.attenuation.quadratic,
+                ],
+                kind: lght.kind as u32,
+                ambient_intensity: lght.ambient_intensity,
+                point_intensity: lght.point_intensity,
+                cos_inner_cone_angle: lght.inner_cone_angle.cos(),
+                cos_outer_cone_angle: lght.outer_cone_angle.cos(),
+                // Transpose the matrix to go from row-major (CPU) to column-major (GPU).
+                shadow_world_to_clip: shadow_world_to_clip.t().0,
+                shadow_atlas_rect,
+                shadow_bias: lght.shadow_bias,
+                casts_shadows: casts_shadows as u32,
+                _padding: [0; 2],
+            };
+
+            light_draw_data.push(ldd);
+            light_buffer_data.push(lu);
+        });
+
+        if shadow_casters_skipped > 0 && !self.shadow_casters_skipped {
+            warn!(
+                "Only {max_shadow_casters} shadow maps fit into the shadow atlas, skipping the shadows of {shadow_casters_skipped} lights"
+            );
+        }
+        self.shadow_casters_skipped = shadow_casters_skipped > 0;
+
+        // Write the camera uniform data to the corresponding uniform buffer
+        if !camera_buffer_data.is_empty() {
+            gfx.write_buffer(self.camera_buffer, &camera_buffer_data);
+        }
+
+        // Write the light count and the light data to the corresponding storage buffer
+        let header = LightBufferHeader {
+            num_lights: light_buffer_data.len() as u32,
+            shadow_pcf_radius: gfx.shadow_pcf_radius(),
+            shadow_texel_size: 1.0 / atlas_size as f32,
+            ..Default::default()
+        };
+        let mut light_buffer_bytes = bytemuck::bytes_of(&header).to_vec();
+        light_buffer_bytes.extend_from_slice(bytemuck::cast_slice(&light_buffer_data));
+        gfx.write_buffer(self.light_buffer, &light_buffer_bytes);
+
+        if !shadow_buffer_data.is_empty() {
+            gfx.write_buffer(self.shadow_buffer, &shadow_buffer_data);
+        }
+
+        if !joint_buffer_data.is_empty() {
+            gfx.write_buffer(self.joint_buffer, &joint_buffer_data);
+        }
+
+        // World lines are followed by screen lines, which are converted to normalized device
+        // coordinates
+        let (debug_vertices, debug_world_vertices, debug_screen_vertices) = {
+            let mut debug_draw = res.write::<DebugDraw>();
+            let size = gfx.surface_size();
+            let (width, height) = (size.width.max(1) as f32, size.height.max(1) as f32);
+            let mut vertices = debug_draw.world_lines().to_vec();
+            let world = 0..vertices.len() as u32;
+            vertices.extend(debug_draw.screen_lines().iter().map(|v| LineVertex {
+                position: [
+                    2.0 * v.position[0] / width - 1.0,
+                    1.0 - 2.0 * v.position[1] / height,
+                    0.0,
+                ],
+                color: v.color,
+            }));
+            let screen = world.end..vertices.len() as u32;
+            debug_draw.clear();
+            (vertices, world, screen)
+        };
+
+        if self.post_process_targets.is_some() {
+            let uniform = gfx.post_process_settings().uniform(gfx.surface_format().is_srgb());
+            gfx.write_buffer(self.post_process_buffer, &[uniform]);
+        }
+
+        DrawData {
+            cameras: camera_draw_data,
+            shadows: shadow_draw_data,
+            lights: light_draw_data,
+            instances: instance_draw_data,
+            instance_buffers: instance_buffer_data,
+            debug_vertices,
+            debug_world_vertices,
+            debug_screen_vertices,
+            drawn_instances,
+            culled_instances,
+        }
+    }
+
+    /// Renders the depth of all instances from the view of each shadow-casting light into its tile
+    /// of the shadow atlas
+    #[tracing::instrument(skip_all)]
+    fn draw_shadows(&self, draw_data: &DrawData, rp: &mut RenderPass) {
+        for shadow in &draw_data.shadows {
+            let [x, y, width, height] = shadow.viewport;
+            rp.set_viewport(x, y, width, height);
+
+            // User interface instances do not cast shadows
+            for instance_data in draw_data
+                .instances
+                .iter()
+                .filter(|i| !i.shadow_instance_indexes.is_empty())
+            {
+                rp.set_bind_group(0, self.shadow_bind_group, &[shadow.uniform_offset])
+                    .set_bind_group(1, self.joint_bind_group, &[])
+                    .set_vertex_buffer(0, instance_data.vertex_buffer)
+                    .set_vertex_buffer(1, instance_data.instance_buffer)
+                    .set_index_buffer(instance_data.index_buffer);
+
+                // Transparent submeshes do not cast shadows, alpha-tested submeshes discard the
+                // fragments of their cut-outs
+                for submesh in instance_data.submeshes {
+                    let blend_mode = instance_data.blend_mode(submesh);
+                    if blend_mode.is_transparent() {
+                        continue;
+                    }
+                    let alpha_test = blend_mode == BlendMode::AlphaTest;
+                    rp.set_pipeline(self.pipeline_shadow(instance_data.skinned, alpha_test));
+                    if alpha_test {
+                        rp.set_bind_group(2, self.material_bind_group(instance_data, submesh), &[]);
+                    }
+                    rp.draw_indexed(
+                        submesh.indices.clone(),
+                        0,
+                        instance_data.shadow_instance_indexes.clone(),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Appends the uniform of a camera to the camera buffer data and returns its offset
+    fn push_camera_uniform(&self, camera_buffer_data: &mut Vec<u8>, cam: &Camera, camera_view: Mat4<f32>) -> u32 {
+        let cu = CameraUniform {
+            // Transpose the matrices to go from row-major (CPU) to column-major (GPU).
+            view: camera_view.t().0,
+            projection: (GL_TO_WGPU_DEPTH * cam.projection_matrix()).t().0,
+        };
+
+        let uniform_offset = camera_buffer_data.len() as u32;
+        camera_buffer_data.extend_from_slice(bytemuck::bytes_of(&cu));
+        camera_buffer_data.resize((uniform_offset + self.camera_uniform_stride) as usize, 0);
+        uniform_offset
+    }
+
+    /// Renders the opaque geometry of the scene, or the user interface for screen space cameras,
+    /// from the view of a single camera
+    #[tracing::instrument(skip_all)]
+    fn draw(&self, draw_data: &DrawData, camera: &CameraDrawData, rp: &mut RenderPass) {
+        let [x, y, width, height] = camera.viewport;
+        rp.set_viewport(x, y, width, height);
+
+        for instance_data in &draw_data.instances {
+            let instance_indexes = instance_data.indexes_for(camera.ui);
+            if instance_indexes.is_empty() {
+                continue;
+            }
+
+            rp.set_bind_group(0, self.camera_bind_group, &[camera.uniform_offset])
+                .set_bind_group(1, self.light_bind_group, &[])
+                .set_vertex_buffer(0, instance_data.vertex_buffer)
+                .set_vertex_buffer(1, instance_data.instance_buffer)
+                .set_index_buffer(instance_data.index_buffer);
+            if instance_data.skinned {
+                rp.set_bind_group(3, self.joint_bind_group, &[]);
+            }
+
+            for submesh in instance_data.submeshes {
+                let blend_mode = instance_data.blend_mode(submesh);
+                if blend_mode.is_transparent() {
+                    continue;
+                }
+
+                rp.set_pipeline(self.pipelines_wcm(camera, instance_data.skinned)[&blend_mode])
+                    .set_bind_group(2, self.material_bind_group(instance_data, submesh), &[])
+                    .draw_indexed(submesh.indices.clone(), 0, instance_indexes.clone());
+            }
+        }
+
+        if !camera.ui {
+            // The instance index selects the light in the light storage buffer
+            for (i, light) in (0u32..).zip(&draw_data.lights) {
+                rp.set_pipeline(self.pipeline_ldb(camera))
+                    .set_bind_group(0, self.camera_bind_group, &[camera.uniform_offset])
+                    .set_bind_group(1, self.light_bind_group, &[])
+                    .set_vertex_buffer(0, light.vertex_buffer)
+                    .set_index_buffer(light.index_buffer)
+                    .draw_indexed(0..light.num_indices, 0, i..i + 1);
+            }
+
+            if !draw_data.debug_world_vertices.is_empty() {
+                rp.set_pipeline(self.pipeline_debug(camera))
+                    .set_bind_group(0, self.camera_bind_group, &[camera.uniform_offset])
+                    .set_vertex_buffer(0, self.debug_vertex_buffer)
+                    .draw(draw_data.debug_world_vertices.clone(), 0..1);
+            }
+        }
+    }
+
+    /// Renders the screen lines of [`DebugDraw`] on top of everything else
+    #[tracing::instrument(skip_all)]
+    fn draw_debug_screen(&self, draw_data: &DrawData, rp: &mut RenderPass) {
+        rp.set_pipeline(self.pipeline_debug_screen)
+            .set_vertex_buffer(0, self.debug_vertex_buffer)
+            .draw(draw_data.debug_screen_vertices.clone(), 0..1);
+    }
+
+    /// Renders the transparent submeshes seen by a camera on top of its opaque geometry, from
+    /// back to front
+    #[tracing::instrument(skip_all)]
+    fn draw_transparent(&self, draw_data: &DrawData, camera: &CameraDrawData, rp: &mut RenderPass) {
+        let [x, y, width, height] = camera.viewport;
+        rp.set_viewport(x, y, width, height);
+
+        let mut bound_instance_data = None;
+        for transparent in &camera.transparent {
+            let instance_data = &draw_data.instances[transparent.instance_data];
+            if bound_instance_data != Some(transparent.instance_data) {
+                bound_instance_data = Some(transparent.instance_data);
+                rp.set_bind_group(0, self.camera_bind_group, &[camera.uniform_offset])
+                    .set_bind_group(1, self.light_bind_group, &[])
+                    .set_vertex_buffer(0, instance_data.vertex_buffer)
+                    .set_vertex_buffer(1, instance_data.instance_buffer)
+                    .set_index_buffer(instance_data.index_buffer);
+                if instance_data.skinned {
+                    rp.set_bind_group(3, self.joint_bind_group, &[]);
+                }
+            }
+
+            let submesh = &instance_data.submeshes[transparent.submesh];
+            rp.set_pipeline(self.pipelines_wcm(camera, instance_data.skinned)[&instance_data.blend_mode(submesh)])
+                .set_bind_group(2, self.material_bind_group(instance_data, submesh), &[])
+                .draw_indexed(
+                    submesh.indices.clone(),
+                    0,
+                    transparent.instance..transparent.instance + 1,
+                );
+        }
+    }
+
+    /// Declares the passes of a frame: the shadow pass, followed by an opaque and a transparent
+    /// pass for each camera. Cameras that share a render target draw on top of each other, in
+    /// the order of [`DrawData::cameras`]. With post-processing, the scene cameras on the surface
+    /// render into an HDR texture instead, which is post-processed before the user interface. The
+    /// screen lines of [`DebugDraw`] are drawn on top of the surface last.
+    fn build_render_graph<'a>(&'a self, gfx: &Graphics, draw_data: &'a DrawData) -> RenderGraph<'a> {
+        let mut graph = RenderGraph::new();
+
+        let mut shadow_atlas = graph.import_texture("shadow-atlas", self.shadow_atlas_view);
+        if !draw_data.shadows.is_empty() {
+            let mut pass = graph.add_pass("shadow-pass");
+            shadow_atlas = pass.with_depth_attachment(shadow_atlas, Some(1.0));
+            pass.submit(move |rp: &mut RenderPass| self.draw_shadows(draw_data, rp));
+        }
+
+        let mut targets: Vec<TargetVersions> = Vec::new();
+        for camera in &draw_data.cameras {
+            if camera.target.is_none() && !camera.hdr {
+                self.add_post_process_passes(gfx, &mut graph, &mut targets);
+            }
+
+            // The color of each render target is cleared by the first camera that renders into it
+            let (i, clear_color) = match targets
+                .iter()
+                .position(|t| t.target == camera.target && t.hdr == camera.hdr)
+            {
+                Some(i) => (i, None),
+                None => {
+                    let (color, depth) = match camera.target {
+                        Some((color, depth)) => (
+                            graph.import_texture("camera-target", color),
+                            graph.import_texture("camera-target-depth", depth),
+                        ),
+                        None => {
+                            let color = if camera.hdr {
+                                graph.import_texture("hdr", self.post_process_targets().hdr_view())
+                            } else {
+                                graph.import_surface()
+                            };
+                            (color, graph.import_texture("depth-stencil", gfx.depth_texture_view()))
+                        }
+                    };
+                    targets.push(TargetVersions {
+                        target: camera.target,
+                        hdr: camera.hdr,
+                        color,
+                        depth,
+                    });
+                    (targets.len() - 1, Some(gfx.clear_color()))
+                }
+            };
+            let TargetVersions { color, depth, .. } = &mut targets[i];
+
+            let mut pass = graph.add_pass(if camera.ui { "ui-pass" } else { "opaque-pass" });
+            pass.read_texture(shadow_atlas);
+            *color = pass.add_color_attachment(*color, clear_color);
+            *depth = pass.with_depth_attachment(*depth, Some(1.0));
+            pass.submit(move |rp: &mut RenderPass| self.draw(draw_data, camera, rp));
+
+            if !camera.transparent.is_empty() {
+                let mut pass = graph.add_pass("transparent-pass");
+                pass.read_texture(shadow_atlas);
+                *color = pass.add_color_attachment(*color, None);
+                *depth = pass.with_depth_attachment(*depth, None);
+                pass.submit(move |rp: &mut RenderPass| self.draw_transparent(draw_data, camera, rp));
+            }
+        }
+        self.add_post_process_passes(gfx, &mut graph, &mut targets);
+
+        let surface = match targets.iter().find(|t| t.target.is_none() && !t.hdr) {
+            Some(t) => t.color,
+            None => {
+                // Without a camera on the surface, it is merely cleared
+                let surface = graph.import_surface();
+                let mut pass = graph.add_pass("clear-pass");
+                let surface = pass.add_color_attachment(surface, Some(gfx.clear_color()));
+                pass.submit(|_: &mut RenderPass| ());
+                surface
+            }
+        };
+
+        if !draw_data.debug_screen_vertices.is_empty() {
+            let mut pass = graph.add_pass("debug-screen-pass");
+            pass.add_color_attachment(surface, None);
+            pass.submit(move |rp: &mut RenderPass| self.draw_debug_screen(draw_data, rp));
+        }
+
+        graph
+    }
+
+    /// Post-processes the HDR texture into the surface, once all scene cameras on the surface have
+    /// rendered into it. The user interface is drawn on top afterwards and shares the depth
+    /// texture.
+    fn add_post_process_passes(&self, gfx: &Graphics, graph: &mut RenderGraph, targets: &mut Vec<TargetVersions>) {
+        let Some(hdr) = targets.iter().find(|t| t.hdr) else {
+            return;
+        };
+        if targets.iter().any(|t| t.target.is_none() && !t.hdr) {
+            return;
+        }
+
+        let depth = hdr.depth;
+        let color = self.post_process_targets().add_passes(
+            graph,
+            &self.post_process_pipelines,
+            gfx.post_process_settings(),
+            hdr.color,
+        );
+        targets.push(TargetVersions {
+            target: None,
+            hdr: false,
+            color,
+            depth,
+        });
+    }
+
+    fn post_process_targets(&self) -> &PostProcessTargets {
+        self.post_process_targets
+            .as_ref()
+            .expect("Developer error: cameras render into the HDR texture only while post-processing is enabled")
+    }
+
+    /// Returns the pipelines that match the color format of the camera's render target and the
+    /// vertex format of the mesh
+    fn pipelines_wcm(&self, camera: &CameraDrawData, skinned: bool) -> &HashMap<BlendMode, PipelineId> {
+        match (camera.hdr, skinned) {
+            (false, false) => &self.pipelines_wcm,
+            (true, false) => &self.pipelines_wcm_hdr,
+            (false, true) => &self.pipelines_wcm_skinned,
+            (true, true) => &self.pipelines_wcm_skinned_hdr,
+        }
+    }
+
+    fn pipeline_shadow(&self, skinned: bool, alpha_test: bool) -> PipelineId {
+        match (skinned, alpha_test) {
+            (false, false) => self.pipeline_shadow,
+            (true, false) => self.pipeline_shadow_skinned,
+            (false, true) => self.pipeline_shadow_alpha_test,
+            (true, true) => self.pipeline_shadow_alpha_test_skinned,
+        }
+    }
+
+    fn pipeline_ldb(&self, camera: &CameraDrawData) -> PipelineId {
+        if camera.hdr {
+            self.pipeline_ldb_hdr
+        } else {
+            self.pipeline_ldb
+        }
+    }
+
+    fn pipeline_debug(&self, camera: &CameraDrawData) -> PipelineId {
+        if camera.hdr {
+            self.pipeline_debug_hdr
+        } else {
+            self.pipeline_debug
+        }
+    }
+
+    fn material_bind_group(&self, instance_data: &InstanceDrawData, submesh: &Submesh) -> BindGroupId {
+        instance_data
+            .material(submesh)
+            .map_or(self.default_material_bind_group, |m| m.bind_group)
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn on_window_resized(&self, res: &Resources, ps: PhysicalSize<u32>) {
+        tracing::trace!("Resizing surface");
+        res.write::<Graphics>().resize(ps)
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn on_surface_outdated(&self, res: &Resources) {
+        tracing::trace!("Surface is outdated");
+        res.write::<Graphics>().reconfigure()
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn on_out_of_memory(&self, res: &Resources) {
+        tracing::error!("surface is out of memory");
+        res.write::<EventQueue<EngineEvent>>().send(EngineEvent::Exit)
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn on_timeout(&self) {
+        tracing::warn!("Surface timed out")
+    }
+
+    async fn load_shader(res: &Resources, name: &str) -> anyhow::Result<ShaderSource> {
+        res.read::<AssetDatabase>()
+            .load_asset::<ShaderSource, _>(res, SHADER_ASSET_GROUP, name)
+            .await
+            .with_context(|| format!("Loading the shader '{name}'"))
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn crp_light_debug(shader: &ShaderSource, gfx: &mut Graphics, hdr: bool) -> anyhow::Result<PipelineId> {
+        let shader_module = gfx.create_shader_variant(shader, &[])?;
+
+        let cbl = gfx.camera_bind_group_layout();
+        let lbl = gfx.light_bind_group_layout();
+
+        let mut builder = gfx
+            .create_render_pipeline()
+            .with_label(if hdr {
+                "light-debug:hdr:pipeline"
+            } else {
+                "light-debug:pipeline"
+            })
+            .add_bind_group_layout(cbl)
+            .add_bind_group_layout(lbl)
+            .with_vertex_shader_module(shader_module, "vertex_main")
+            .with_fragment_shader_module(shader_module, "fragment_main")
+            .add_vertex_buffer_layout::<Vertex>();
+        if hdr {
+            builder = builder.add_color_target(HDR_TEXTURE_FORMAT, Some(BlendMode::Opaque.blend_state()));
+        }
+
+        Ok(builder.submit())
+    }
+
+    /// Creates a depth-only shadow pipeline. The alpha-tested variants bind the joints in group 1
+    /// regardless of skinning, followed by the material in group 2.
+    #[tracing::instrument(skip_all)]
+    fn crp_shadow(
+        shader: &ShaderSource,
+        gfx: &mut Graphics,
+        skinned: bool,
+        alpha_test: bool,
+    ) -> anyhow::Result<PipelineId> {
+        let mut flags = Vec::new();
+        if skinned {
+            flags.push("SKINNING");
+        }
+        if alpha_test {
+            flags.push("ALPHA_TEST");
+        }
+        let shader_module = gfx.create_shader_variant(shader, &flags)?;
+
+        let sbl = gfx.shadow_bind_group_layout();
+        let jbl = gfx.joint_bind_group_layout();
+        let mbl = gfx.material_bind_group_layout();
+
+        let mut builder = gfx
+            .create_render_pipeline()
+            .with_label(match (skinned, alpha_test) {
+                (false, false) => "shadow:pipeline",
+                (true, false) => "shadow:skinned:pipeline",
+                (false, true) => "shadow:alpha-test:pipeline",
+                (true, true) => "shadow:alpha-test:skinned:pipeline",
+            })
+            .add_bind_group_layout(sbl)
+            .with_vertex_shader_module(shader_module, "vertex_main")
+            .with_depth_bias(2, 2.0);
+        if skinned || alpha_test {
+            builder = builder.add_bind_group_layout(jbl);
+        }
+        // The shadow pass only writes depth, hence only alpha-tested pipelines have a fragment stage
+        if alpha_test {
+            builder = builder
+                .add_bind_group_layout(mbl)
+                .with_fragment_shader_module(shader_module, "fragment_main")
+                .without_color_targets();
+        }
+        builder = if skinned {
+            builder.add_vertex_buffer_layout::<SkinnedVertex>()
+        } else {
+            builder.add_vertex_buffer_layout::<Vertex>()
+        };
+
+        Ok(builder.add_vertex_buffer_layout::<Instance>().submit())
+    }
+
+    /// Creates the pipeline of the world lines of [`DebugDraw`], which are depth-tested against the
+    /// scene without writing depth
+    #[tracing::instrument(skip_all)]
+    fn crp_debug_lines(shader: &ShaderSource, gfx: &mut Graphics, hdr: bool) -> anyhow::Result<PipelineId> {
+        let shader_module = gfx.create_shader_variant(shader, &[])?;
+
+        let cbl = gfx.camera_bind_group_layout();
+
+        let mut builder = gfx
+            .create_render_pipeline()
+            .with_label(if hdr {
+                "debug-lines:hdr:pipeline"
+            } else {
+                "debug-lines:pipeline"
+            })
+            .add_bind_group_layout(cbl)
+            .with_vertex_shader_module(shader_module, "world_vertex_main")
+            .with_fragment_shader_module(shader_module, "fragment_main")
+            .with_topology(PrimitiveTopology::LineList)
+            .with_cull_mode(None)
+            .with_blend_mode(BlendMode::AlphaBlend)
+            .add_vertex_buffer_layout::<LineVertex>();
+        if hdr {
+            builder = builder.add_color_target(HDR_TEXTURE_FORMAT, Some(BlendMode::AlphaBlend.blend_state()));
+        }
+
+        Ok(builder.submit())
+    }
+
+    /// Creates the pipeline of the screen lines of [`DebugDraw`], which are drawn into the surface
+    /// after post-processing
+    #[tracing::instrument(skip_all)]
+    fn crp_debug_screen(shader: &ShaderSource, gfx: &mut Graphics) -> anyhow::Result<PipelineId> {
+        let shader_module = gfx.create_shader_variant(shader, &[])?;
+
+        let pipeline = gfx
+            .create_render_pipeline()
+            .with_label("debug-screen:pipeline")
+            .with_vertex_shader_module(shader_module, "screen_vertex_main")
+            .with_fragment_shader_module(shader_module, "fragment_main")
+            .with_topology(PrimitiveTopology::LineList)
+            .with_cull_mode(None)
+            .without_depth_stencil()
+            .with_blend_mode(BlendMode::AlphaBlend)
+            .add_vertex_buffer_layout::<LineVertex>()
+            .submit();
+
+        Ok(pipeline)
+    }
+
+    /// Creates one pipeline per blend mode from variants of the same shader, which render into the
+    /// surface format or into the HDR texture of the post-processing, and draw either static or
+    /// skinned meshes
+    #[tracing::instrument(skip_all)]
+    fn crp_with_camera_and_material(
+        shader: &ShaderSource,
+        gfx: &mut Graphics,
+        hdr: bool,
+        skinned: bool,
+    ) -> anyhow::Result<HashMap<BlendMode, PipelineId>> {
+        let cbl = gfx.camera_bind_group_layout();
+        let lbl = gfx.light_bind_group_layout();
+        let mbl = gfx.material_bind_group_layout();
+        let jbl = gfx.joint_bind_group_layout();
+
+        let pipelines = BlendMode::ALL
+            .into_iter()
+            .map(|blend_mode| {
+                // Alpha-blended and additive materials differ only in their blend state. The labels
+                // are ordered by skinning, then HDR.
+                let (labels, defines): ([&'static str; 4], &[&str]) = match blend_mode {
+                    BlendMode::Opaque => (
+                        [
+                            "with-camera-material:opaque:pipeline",
+                            "with-camera-material:opaque:hdr:pipeline",
+                            "with-camera-material:opaque:skinned:pipeline",
+                            "with-camera-material:opaque:skinned:hdr:pipeline",
+                        ],
+                        &[],
+                    ),
+                    BlendMode::AlphaTest => (
+                        [
+                            "with-camera-material:alpha-test:pipeline",
+                            "with-camera-material:alpha-test:hdr:pipeline",
+                            "with-camera-material:alpha-test:skinned:pipeline",
+                            "with-camera-material:alpha-test:skinned:hdr:pipeline",
+                        ],
+                        &["ALPHA_TEST"],
+                    ),
+                    BlendMode::AlphaBlend => (
+                        [
+                            "with-camera-material:alpha-blend:pipeline",
+                            "with-camera-material:alpha-blend:hdr:pipeline",
+                            "with-camera-material:alpha-blend:skinned:pipeline",
+                            "with-camera-material:alpha-blend:skinned:hdr:pipeline",
+                        ],
+                        &["ALPHA_BLEND"],
+                    ),
+                    BlendMode::Additive => (
+                        [
+                            "with-camera-material:additive:pipeline",
+                            "with-camera-material:additive:hdr:pipeline",
+                            "with-camera-material:additive:skinned:pipeline",
+                            "with-camera-material:additive:skinned:hdr:pipeline",
+                        ],
+                        &["ALPHA_BLEND"],
+                    ),
+                };
+                let mut defines = defines.to_vec();
+                if skinned {
+                    defines.push("SKINNING");
+                }
+                let shader_module = gfx.create_shader_variant(shader, &defines)?;
+
+                let mut builder = gfx
+                    .create_render_pipeline()
+                    .with_label(labels[2 * skinned as usize + hdr as usize])
+                    .add_bind_group_layout(cbl)
+                    .add_bind_group_layout(lbl)
+                    .add_bind_group_layout(mbl)
+                    .with_vertex_shader_module(shader_module, "vertex_main")
+                    .with_fragment_shader_module(shader_module, "fragment_main")
+                    .with_blend_mode(blend_mode);
+                builder = if skinned {
+                    builder
+                        .add_bind_group_layout(jbl)
+                        .add_vertex_buffer_layout::<SkinnedVertex>()
+                } else {
+                    builder.add_vertex_buffer_layout::<Vertex>()
+                };
+                builder = builder.add_vertex_buffer_layout::<Instance>();
+                if hdr {
+                    builder = builder.add_color_target(HDR_TEXTURE_FORMAT, Some(blend_mode.blend_state()));
+                }
+
+                Ok((blend_mode, builder.submit()))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(pipelines)
+    }
+}
+
+impl WithResources for Renderer {
+    #[tracing::instrument(skip_all)]
+    async fn with_res(res: &Resources) -> anyhow::Result<Self> {
+        let window_receiver = res.write::<EventQueue<WindowEvent>>().subscribe::<Self>();
+        let engine_receiver = res.write::<EventQueue<EngineEvent>>().subscribe::<Self>();
+
+        let shader_ldb = Self::load_shader(res, LIGHT_DEBUG_SHADER).await?;
+        let shader_wcm = Self::load_shader(res, WITH_CAMERA_AND_MATERIAL_SHADER).await?;
+        let shader_shadow = Self::load_shader(res, SHADOW_SHADER).await?;
+        let shader_bloom = Self::load_shader(res, BLOOM_SHADER).await?;
+        let shader_tonemapping = Self::load_shader(res, TONEMAPPING_SHADER).await?;
+        let shader_fxaa = Self::load_shader(res, FXAA_SHADER).await?;
+        let shader_debug = Self::load_shader(res, DEBUG_LINES_SHADER).await?;
+
+        let mut gfx = res.write::<Graphics>();
+
+        let pipeline_ldb = Self::crp_light_debug(&shader_ldb, &mut gfx, false)
+            .context("Creating the light debugging render pipeline")?;
+        let pipelines_wcm = Self::crp_with_camera_and_material(&shader_wcm, &mut gfx, false, false)
+            .context("Creating the render pipelines 'with-camera-material'")?;
+        let pipeline_ldb_hdr = Self::crp_light_debug(&shader_ldb, &mut gfx, true)
+            .context("Creating the HDR light debugging render pipeline")?;
+        let pipelines_wcm_hdr = Self::crp_with_camera_and_material(&shader_wcm, &mut gfx, true, false)
+            .context("Creating the HDR render pipelines 'with-camera-material'")?;
+        let pipelines_wcm_skinned = Self::crp_with_camera_and_material(&shader_wcm, &mut gfx, false, true)
+            .context("Creating the skinned render pipelines 'with-camera-material'")?;
+        let pipelines_wcm_skinned_hdr = Self::crp_with_camera_and_material(&shader_wcm, &mut gfx, true, true)
+            .context("Creating the skinned HDR render pipelines 'with-camera-material'")?;
+        let pipeline_shadow =
+            Self::crp_shadow(&shader_shadow, &mut gfx, false, false).context("Creating the shadow render pipeline")?;
+        let pipeline_shadow_skinned = Self::crp_shadow(&shader_shadow, &mut gfx, true, false)
+            .context("Creating the skinned shadow render pipeline")?;
+        let pipeline_shadow_alpha_test = Self::crp_shadow(&shader_shadow, &mut gfx, false, true)
+            .context("Creating the alpha-tested shadow render pipeline")?;
+        let pipeline_shadow_alpha_test_skinned = Self::crp_shadow(&shader_shadow, &mut gfx, true, true)
+            .context("Creating the skinned alpha-tested shadow render pipeline")?;
+        let pipeline_debug =
+            Self::crp_debug_lines(&shader_debug, &mut gfx, false).context("Creating the debug line render pipeline")?;
+        let pipeline_debug_hdr = Self::crp_debug_lines(&shader_debug, &mut gfx, true)
+            .context("Creating the HDR debug line render pipeline")?;
+        let pipeline_debug_screen = Self::crp_debug_screen(&shader_debug, &mut gfx)
+            .context("Creating the screen space debug line render pipeline")?;
+        let post_process_pipelines =
+            PostProcessPipelines::new(&shader_bloom, &shader_tonemapping, &shader_fxaa, &mut gfx)
+                .context("Creating the post-processing render pipelines")?;
+
+        let uniform_alignment = gfx.limits().min_uniform_buffer_offset_alignment; // 256
+
+        // Each camera occupies one aligned slot, selected with a dynamic offset
+        let camera_uniform_stride = (size_of::<CameraUniform>() as u32).next_multiple_of(uniform_alignment);
+        // One additional slot is reserved for the implicit user interface camera
+        let camera_buffer_size = (gfx.max_cameras() + 1) * camera_uniform_stride;
+        let camera_buffer = gfx.create_buffer(
+            Some("camera-buffer"),
+            camera_buffer_size,
+            BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        );
+
+        let cbl = gfx.camera_bind_group_layout();
+        let camera_bind_group = gfx
+            .create_bind_group(cbl)
+            .with_label(Some("camera-bind-group"))
+            .add_buffer(
+                0,
+                0u64,
+                griffon::wgpu::BufferSize::new(size_of::<CameraUniform>() as u64),
+                camera_buffer,
+            )
+            .submit();
+
+        let light_buffer_size = gfx.light_buffer_size();
+        let light_buffer = gfx.create_buffer(
+            Some("light-buffer"),
+            light_buffer_size,
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        );
+
+        let shadow_atlas_size = gfx.shadow_atlas_size();
+        let shadow_atlas = gfx
+            .create_texture()
+            .with_label(Some("shadow-atlas"))
+            .with_depth_texture()
+            .with_size(shadow_atlas_size, shadow_atlas_size)
+            .submit();
+        let shadow_atlas_view = gfx.create_texture_view(Some("shadow-atlas-view"), shadow_atlas);
+        let shadow_sampler = gfx
+            .create_sampler()
+            .with_label(Some("shadow-sampler"))
+            .with_compare(CompareFunction::LessEqual)
+            .submit();
+
+        let ll = gfx.light_bind_group_layout();
+        let light_bind_group = gfx
+            .create_bind_group(ll)
+            .with_label(Some("light-bind-group"))
+            .add_entire_buffer(0, light_buffer)
+            .add_texture_view(1, shadow_atlas_view)
+            .add_sampler(2, shadow_sampler)
+            .submit();
+
+        // Each shadow caster occupies one aligned slot, selected with a dynamic offset
+        let shadow_uniform_stride = (size_of::<ShadowUniform>() as u32).next_multiple_of(uniform_alignment);
+        let shadow_buffer_size = gfx.max_shadow_casters().max(1) * shadow_uniform_stride;
+        let shadow_buffer = gfx.create_buffer(
+            Some("shadow-buffer"),
+            shadow_buffer_size,
+            BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        );
+
+        let sl = gfx.shadow_bind_group_layout();
+        let shadow_bind_group = gfx
+            .create_bind_group(sl)
+            .with_label(Some("shadow-bind-group"))
+            .add_buffer(
+                0,
+                0u64,
+                griffon::wgpu::BufferSize::new(size_of::<ShadowUniform>() as u64),
+                shadow_buffer,
+            )
+            .submit();
+
+        let joint_buffer_size = gfx.joint_buffer_size();
+        let joint_buffer = gfx.create_buffer(
+            Some("joint-buffer"),
+            joint_buffer_size,
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        );
+
+        let jl = gfx.joint_bind_group_layout();
+        let joint_bind_group = gfx
+            .create_bind_group(jl)
+            .with_label(Some("joint-bind-group"))
+            .add_entire_buffer(0, joint_buffer)
+            .submit();
+
+        let debug_vertex_buffer = gfx.create_buffer(
+            Some("debug-vertex-buffer"),
+            INITIAL_DEBUG_VERTEX_CAPACITY * size_of::<LineVertex>() as u64,
+            BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        );
+
+        let post_process_buffer = gfx.create_buffer(
+            Some("post-process-buffer"),
+            size_of::<PostProcessUniform>() as u64,
+            BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        );
+        let post_process_sampler = gfx
+            .create_sampler()
+            .with_label(Some("post-process-sampler"))
+            .with_settings(&SamplerSettings::default())
+            .submit();
+
+        Ok(Renderer {
+            window_receiver,
+            engine_receiver,
+            renderer_enabled: true,
+            camera_buffer,
+            camera_bind_group,
+            camera_uniform_stride,
+            light_buffer,
+            light_bind_group,
+            default_material_bind_group: gfx.default_material().bind_group,
+            shadow_atlas_view,
+            shadow_buffer,
+            shadow_bind_group,
+            shadow_uniform_stride,
+            shadow_casters_skipped: false,
+            joint_buffer,
+            joint_bind_group,
+            joints_skipped: false,
+            pipeline_ldb,
+            pipelines_wcm,
+            pipeline_ldb_hdr,
+            pipelines_wcm_hdr,
+            pipelines_wcm_skinned,
+            pipelines_wcm_skinned_hdr,
+            pipeline_shadow,
+            pipeline_shadow_skinned,
+            pipeline_shadow_alpha_test,
+            pipeline_shadow_alpha_test_skinned,
+            pipeline_debug,
+            pipeline_debug_hdr,
+            pipeline_debug_screen,
+            debug_vertex_buffer,
+            post_process_buffer,
+            post_process_sampler,
+            post_process_pipelines,
+            post_process_targets: None,
+        })
+    }
+}
+
+#[async_trait]
+impl System for Renderer {
+    #[tracing::instrument(skip_all)]
+    async fn run(&mut self, res: &Resources, _t: Duration, _dt: Duration) {
+        let frame_start = Instant::now();
+        self.handle_events(res);
+
+        if !self.renderer_enabled {
+            res.write::<Statistics>().update_render_stats(
+                0,
+                frame_start.elapsed(),
+                Duration::ZERO,
+                Duration::ZERO,
+                Duration::ZERO,
+            );
+            res.write::<Statistics>().update_culling_stats(0, 0);
+            res.write::<DebugDraw>().clear();
+            return;
+        }
+
+        // The timestamps of earlier frames arrive while the GPU catches up
+        if let Some(pass_durations) = res.read::<Graphics>().collect_gpu_timings() {
+            res.write::<Statistics>().update_gpu_stats(pass_durations);
+        }
+
+        let prepare_start = Instant::now();
+        self.update_render_targets(res);
+        self.update_post_process_targets(res);
+        let draw_data = self.prepare(res);
+        {
+            // Instance buffers that are too small are reallocated, which requires write access
+            let mut gfx = res.write::<Graphics>();
+            for (instance_buffer, instance_data) in &draw_data.instance_buffers {
+                gfx.write_instance_buffer(*instance_buffer, instance_data);
+            }
+            if !draw_data.debug_vertices.is_empty() {
+                gfx.write_vertex_buffer(self.debug_vertex_buffer, &draw_data.debug_vertices);
+            }
+        }
+        let prepare_duration = prepare_start.elapsed();
+
+        let render_graph = self
+            .build_render_graph(&res.read::<Graphics>(), &draw_data)
+            .compile()
+            .unwrap_or_else(|e| panic!("Developer error: invalid render graph: {e}"));
+        let transient_views = res
+            .write::<Graphics>()
+            .acquire_transient_textures(render_graph.transient_textures());
+
+        let gfx = res.read::<Graphics>();
+        let encoder = gfx.create_encoder(Some("main-encoder"));
+        let (draw_calls, draw_duration, submit_duration) = match encoder {
+            Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                self.on_surface_outdated(res);
+                (0, Duration::ZERO, Duration::ZERO)
+            }
+            Err(SurfaceError::OutOfMemory) => {
+                self.on_out_of_memory(res);
+                (0, Duration::ZERO, Duration::ZERO)
+            }
+            Err(SurfaceError::Timeout) => {
+                self.on_timeout();
+                (0, Duration::ZERO, Duration::ZERO)
+            }
+            Err(SurfaceError::Other) => {
+                todo!()
+            }
+            Ok(mut enc) => {
+                let draw_start = Instant::now();
+                let stats = render_graph.execute(&mut enc, &transient_views);
+                let draw_duration = draw_start.elapsed();
+                let submit_start = Instant::now();
+                enc.submit();
+                let submit_duration = submit_start.elapsed();
+                (stats.draw_calls, draw_duration, submit_duration)
+            }
+        };
+
+        res.write::<Statistics>().update_render_stats(
+            draw_calls,
+            frame_start.elapsed(),
+            prepare_duration,
+            draw_duration,
+            submit_duration,
+        );
+        res.write::<Statistics>()
+            .update_culling_stats(draw_data.drawn_instances, draw_data.culled_instances);
+    }
+}
+
+#[derive(Debug)]
+struct DrawData<'a> {
+    cameras: Vec<CameraDrawData>,
+    shadows: Vec<ShadowDrawData>,
+    lights: Vec<LightDrawData>,
+    instances: Vec<InstanceDrawData<'a>>,
+    /// The instance data to write to each instance buffer before drawing
+    instance_buffers: HashMap<BufferId, Vec<Instance>>,
+    /// The lines of [`DebugDraw`] to write to the debug vertex buffer, with the screen lines in
+    /// normalized device coordinates
+    debug_vertices: Vec<LineVertex>,
+    debug_world_vertices: Range<u32>,
+    debug_screen_vertices: Range<u32>,
+    drawn_instances: usize,
+    culled_instances: usize,
+}
+
+/// The latest versions of the color and depth textures of a render target in a [`RenderGraph`]
+#[derive(Debug)]
+struct TargetVersions {
+    target: Option<(TextureViewId, TextureViewId)>,
+    /// The HDR texture of the post-processing, which stands in for the surface
+    hdr: bool,
+    color: TextureHandle,
+    depth: TextureHandle,
+}
+
+#[derive(Debug)]
+struct CameraDrawData {
+    /// The offset of the camera's [`CameraUniform`] in the camera buffer
+    uniform_offset: u32,
+    /// The rectangle of the render target in pixels (x, y, width, height)
+    viewport: [f32; 4],
+    /// The color and depth texture views, or `None` for the surface
+    target: Option<(TextureViewId, TextureViewId)>,
+    /// Draw only user interface instances
+    ui: bool,
+    /// Render into the HDR texture of the post-processing instead of the surface
+    hdr: bool,
+    /// The world-to-view transform of the camera
+    view: Mat4<f32>,
+    /// The transparent submeshes seen by the camera, sorted back to front
+    transparent: Vec<TransparentDrawData>,
+}
+
+#[derive(Debug)]
+struct TransparentDrawData {
+    /// The index of the [`InstanceDrawData`] in [`DrawData`]
+    instance_data: usize,
+    /// The index of the submesh
+    submesh: usize,
+    /// The index of the instance in the instance buffer
+    instance: u32,
+    /// The distance of the instance in front of the camera
+    depth: f32,
+}
+
+#[derive(Debug)]
+struct ShadowDrawData {
+    /// The offset of the light's [`ShadowUniform`] in the shadow buffer
+    uniform_offset: u32,
+    /// The tile of the shadow atlas in pixels (x, y, width, height)
+    viewport: [f32; 4],
+}
+
+#[derive(Debug)]
+struct LightDrawData {
+    vertex_buffer: BufferId,
+    index_buffer: BufferId,
+    num_indices: u32,
+}
+
+/// An entity that is drawn as an instance of its mesh
+type GroupMember<'a> = (Index, &'a Renderable, &'a Transform);
+
+#[derive(Debug)]
+struct InstanceDrawData<'a> {
+    vertex_buffer: BufferId,
+    instance_buffer: BufferId,
+    index_buffer: BufferId,
+    /// The vertex buffer holds [`SkinnedVertex`] data
+    skinned: bool,
+    submeshes: &'a [Submesh],
+    materials: &'a [GpuMaterial],
+    /// The instances inside at least one camera frustum, nearest first
+    instance_indexes: Range<u32>,
+    /// All instances except for the user interface, including those that were culled, since they
+    /// may still cast shadows into the view
+    shadow_instance_indexes: Range<u32>,
+    ui_instance_indexes: Range<u32>,
+    /// The world space center of each instance, in the order of the instance buffer
+    centers: Vec<[f32; 3]>,
+}
+
+impl<'a> InstanceDrawData<'a> {
+    /// Returns the instances drawn by user interface or scene cameras
+    fn indexes_for(&self, ui: bool) -> Range<u32> {
+        if ui {
+            self.ui_instance_indexes.clone()
+        } else {
+            self.instance_indexes.clone()
+        }
+    }
+
+    /// Submeshes referring to a missing material fall back to the first material of the model,
+    /// models without materials to the default material
+    fn material(&self, submesh: &Submesh) -> Option<&'a GpuMaterial> {
+        self.materials.get(submesh.material).or(self.materials.first())
+    }
+
+    fn blend_mode(&self, submesh: &Submesh) -> BlendMode {
+        self.material(submesh).map_or(BlendMode::Opaque, |m| m.blend_mode)
+    }
+}
+
+/// Calculates the world-to-clip transform of the shadow map of a directional or spot light
+fn shadow_projection(lght: &Light) -> Mat4<f32> {
+    let d = lght.direction;
+    let norm = (d.x * d.x + d.y * d.y + d.z * d.z).sqrt();
+    let eye = lght.position;
+    let target = Vec4::new_point(eye.x + d.x, eye.y + d.y, eye.z + d.z);
+    // The up vector must not be parallel to the viewing direction
+    let up = if (d.y / norm).abs() > 0.99 {
+        Vec4::z()
+    } else {
+        Vec4::y()
+    };
+    let light_view = Affine::with_look_at_rh(eye, target, up).to_matrix();
+
+    let projection = match lght.kind {
+        LightKind::Directional => *Ortho::new(
+            2.0 * lght.shadow_extent,
+            2.0 * lght.shadow_extent,
+            SHADOW_NEAR_Z,
+            lght.shadow_range,
+        )
+        .as_matrix(),
+        _ => *Persp::new(1.0, 2.0 * lght.outer_cone_angle, SHADOW_NEAR_Z, lght.shadow_range).as_matrix(),
+    };
+
+    projection * light_view
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ecs::Reg;
+
+    #[test]
+    fn renderer_reg_macro() {
+        type _SR = Reg![Renderer];
+    }
+}
@@ -0,0 +1,293 @@
+use griffon::PostProcessSettings;
+use griffon::assets::shader_source::ShaderSource;
+use griffon::base::encoder::RenderPass;
+use griffon::base::ids::{BindGroupId, BufferId, PipelineId, SamplerId, ShaderModuleId, TextureId, TextureViewId};
+use griffon::base::post_process_settings::HDR_TEXTURE_FORMAT;
+use griffon::base::render_graph::{RenderGraph, TextureHandle};
+use griffon::resources::Graphics;
+use griffon::wgpu::{BlendComponent, BlendFactor, BlendOperation, BlendState};
+use griffon::winit::dpi::PhysicalSize;
+
+/// The render pipelines of the full-screen post-processing passes
+#[derive(Debug)]
+pub struct PostProcessPipelines {
+    bloom_prefilter: PipelineId,
+    bloom_downsample: PipelineId,
+    bloom_upsample: PipelineId,
+    tonemapping: PipelineId,
+    fxaa: PipelineId,
+}
+
+impl PostProcessPipelines {
+    #[tracing::instrument(skip_all)]
+    pub fn new(
+        bloom: &ShaderSource,
+        tonemapping: &ShaderSource,
+        fxaa: &ShaderSource,
+        gfx: &mut Graphics,
+    ) -> anyhow::Result<Self> {
+        let bloom = gfx.create_shader_variant(bloom, &[])?;
+        let tonemapping = gfx.create_shader_variant(tonemapping, &[])?;
+        let fxaa = gfx.create_shader_variant(fxaa, &[])?;
+
+        // The upsampled levels are added to the larger levels of the bloom
+        let additive = BlendState {
+            color: BlendComponent {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            alpha: BlendComponent::REPLACE,
+        };
+
+        Ok(PostProcessPipelines {
+            bloom_prefilter: Self::crp(gfx, "bloom-prefilter:pipeline", bloom, "prefilter_main", true, None),
+            bloom_downsample: Self::crp(gfx, "bloom-downsample:pipeline", bloom, "downsample_main", true, None),
+            bloom_upsample: Self::crp(
+                gfx,
+                "bloom-upsample:pipeline",
+                bloom,
+                "upsample_main",
+                true,
+                Some(additive),
+            ),
+            tonemapping: Self::crp(gfx, "tonemapping:pipeline", tonemapping, "fragment_main", false, None),
+            fxaa: Self::crp(gfx, "fxaa:pipeline", fxaa, "fragment_main", false, None),
+        })
+    }
+
+    /// Creates a full-screen pipeline that renders into an HDR texture or into a texture of the
+    /// surface format, replacing its contents unless a blend state is given
+    fn crp(
+        gfx: &mut Graphics,
+        label: &'static str,
+        shader_module: ShaderModuleId,
+        entry_point: &str,
+        hdr: bool,
+        blend: Option<BlendState>,
+    ) -> PipelineId {
+        let pbl = gfx.post_process_bind_group_layout();
+
+        let mut builder = gfx
+            .create_render_pipeline()
+            .with_label(label)
+            .add_bind_group_layout(pbl)
+            .with_vertex_shader_module(shader_module, "vertex_main")
+            .with_fragment_shader_module(shader_module, entry_point)
+            .with_cull_mode(None)
+            .without_depth_stencil();
+        if hdr {
+            builder = builder.add_color_target(HDR_TEXTURE_FORMAT, blend);
+        } else if let Some(blend) = blend {
+            builder = builder.with_blend_state(blend);
+        }
+
+        builder.submit()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Target {
+    texture: TextureId,
+    view: TextureViewId,
+}
+
+/// The textures of the post-processing passes, which match the size of the surface, and the bind
+/// groups that sample them
+#[derive(Debug)]
+pub struct PostProcessTargets {
+    size: PhysicalSize<u32>,
+    /// The scene in high dynamic range
+    hdr: Target,
+    /// The levels of the bloom, each half the size of the previous one, starting at half the size
+    /// of the surface
+    bloom: Vec<Target>,
+    /// The tonemapped scene, which is anti-aliased into the surface
+    ldr: Target,
+    /// Samples the HDR texture
+    bloom_prefilter: BindGroupId,
+    /// Sample each bloom level but the last
+    bloom_downsample: Vec<BindGroupId>,
+    /// Sample each bloom level but the first
+    bloom_upsample: Vec<BindGroupId>,
+    /// Samples the HDR texture and the first bloom level
+    tonemapping: BindGroupId,
+    /// Samples the tonemapped scene
+    fxaa: BindGroupId,
+}
+
+impl PostProcessTargets {
+    #[tracing::instrument(skip_all)]
+    pub fn new(
+        gfx: &mut Graphics,
+        size: PhysicalSize<u32>,
+        bloom_mip_levels: u32,
+        buffer: BufferId,
+        sampler: SamplerId,
+    ) -> Self {
+        let hdr = Self::create_target(
+            gfx,
+            "post-process-hdr",
+            size.width,
+            size.height,
+            Some(HDR_TEXTURE_FORMAT),
+        );
+        let bloom: Vec<_> = (1..=Self::bloom_levels(size, bloom_mip_levels))
+            .map(|level| {
+                let (width, height) = ((size.width >> level).max(1), (size.height >> level).max(1));
+                Self::create_target(gfx, "post-process-bloom", width, height, Some(HDR_TEXTURE_FORMAT))
+            })
+            .collect();
+        let ldr = Self::create_target(gfx, "post-process-ldr", size.width, size.height, None);
+
+        let mut bind = |source: Target, secondary: Target| {
+            let pbl = gfx.post_process_bind_group_layout();
+            gfx.create_bind_group(pbl)
+                .with_label(Some("post-process-bind-group"))
+                .add_texture_view(0, source.view)
+                .add_sampler(1, sampler)
+                .add_entire_buffer(2, buffer)
+                .add_texture_view(3, secondary.view)
+                .submit()
+        };
+        let bloom_prefilter = bind(hdr, hdr);
+        let bloom_downsample = bloom[..bloom.len() - 1].iter().map(|&b| bind(b, b)).collect();
+        let bloom_upsample = bloom[1..].iter().map(|&b| bind(b, b)).collect();
+        let tonemapping = bind(hdr, bloom[0]);
+        let fxaa = bind(ldr, ldr);
+
+        PostProcessTargets {
+            size,
+            hdr,
+            bloom,
+            ldr,
+            bloom_prefilter,
+            bloom_downsample,
+            bloom_upsample,
+            tonemapping,
+            fxaa,
+        }
+    }
+
+    /// Returns whether the targets were created for the given surface size and settings
+    pub fn matches(&self, size: PhysicalSize<u32>, bloom_mip_levels: u32) -> bool {
+        self.size == size && self.bloom.len() as u32 == Self::bloom_levels(size, bloom_mip_levels)
+    }
+
+    pub fn release(self, gfx: &mut Graphics) {
+        let bind_groups = [self.bloom_prefilter, self.tonemapping, self.fxaa]
+            .into_iter()
+            .chain(self.bloom_downsample)
+            .chain(self.bloom_upsample);
+        for bind_group in bind_groups {
+            gfx.remove_bind_group(bind_group);
+        }
+        for target in [self.hdr, self.ldr].into_iter().chain(self.bloom) {
+            gfx.remove_texture_view(target.view);
+            gfx.remove_texture(target.texture);
+        }
+    }
+
+    /// Returns the HDR texture that the scene is rendered into
+    pub fn hdr_view(&self) -> TextureViewId {
+        self.hdr.view
+    }
+
+    /// Adds the passes that turn the HDR scene into the final image on the surface and returns
+    /// the surface
+    pub fn add_passes(
+        &self,
+        graph: &mut RenderGraph,
+        pipelines: &PostProcessPipelines,
+        settings: &PostProcessSettings,
+        hdr: TextureHandle,
+    ) -> TextureHandle {
+        let fullscreen = |pipeline: PipelineId, bind_group: BindGroupId| {
+            move |rp: &mut RenderPass| {
+                rp.set_pipeline(pipeline)
+                    .set_bind_group(0, bind_group, &[])
+                    .draw(0..3, 0..1);
+            }
+        };
+
+        let mut bloom = None;
+        if settings.bloom.enabled {
+            let mut levels: Vec<TextureHandle> = self
+                .bloom
+                .iter()
+                .map(|b| graph.import_texture("bloom", b.view))
+                .collect();
+
+            let mut pass = graph.add_pass("bloom-prefilter-pass");
+            pass.read_texture(hdr);
+            levels[0] = pass.add_color_attachment(levels[0], None);
+            pass.submit(fullscreen(pipelines.bloom_prefilter, self.bloom_prefilter));
+
+            for (i, &bind_group) in self.bloom_downsample.iter().enumerate() {
+                let mut pass = graph.add_pass("bloom-downsample-pass");
+                pass.read_texture(levels[i]);
+                levels[i + 1] = pass.add_color_attachment(levels[i + 1], None);
+                pass.submit(fullscreen(pipelines.bloom_downsample, bind_group));
+            }
+
+            for (i, &bind_group) in self.bloom_upsample.iter().enumerate().rev() {
+                let mut pass = graph.add_pass("bloom-upsample-pass");
+                pass.read_texture(levels[i + 1]);
+                levels[i] = pass.add_color_attachment(levels[i], None);
+                pass.submit(fullscreen(pipelines.bloom_upsample, bind_group));
+            }
+
+            bloom = Some(levels[0]);
+        }
+
+        let surface = graph.import_surface();
+        let tonemapped = if settings.fxaa.enabled {
+            graph.import_texture("post-process-ldr", self.ldr.view)
+        } else {
+            surface
+        };
+
+        let mut pass = graph.add_pass("tonemapping-pass");
+        pass.read_texture(hdr);
+        if let Some(bloom) = bloom {
+            pass.read_texture(bloom);
+        }
+        let tonemapped = pass.add_color_attachment(tonemapped, None);
+        pass.submit(fullscreen(pipelines.tonemapping, self.tonemapping));
+
+        if !settings.fxaa.enabled {
+            return tonemapped;
+        }
+
+        let mut pass = graph.add_pass("fxaa-pass");
+        pass.read_texture(tonemapped);
+        let surface = pass.add_color_attachment(surface, None);
+        pass.submit(fullscreen(pipelines.fxaa, self.fxaa));
+        surface
+    }
+
+    /// Limits the number of bloom levels such that the smallest one is at least one texel wide
+    fn bloom_levels(size: PhysicalSize<u32>, bloom_mip_levels: u32) -> u32 {
+        bloom_mip_levels.clamp(1, size.width.min(size.height).max(2).ilog2())
+    }
+
+    fn create_target(
+        gfx: &mut Graphics,
+        label: &str,
+        width: u32,
+        height: u32,
+        format: Option<griffon::wgpu::TextureFormat>,
+    ) -> Target {
+        let mut builder = gfx
+            .create_texture()
+            .with_label(Some(label))
+            .with_render_target()
+            .with_size(width, height);
+        if let Some(format) = format {
+            builder = builder.with_format(format);
+        }
+        let texture = builder.submit();
+        let view = gfx.create_texture_view(Some(label), texture);
+        Target { texture, view }
+    }
+}
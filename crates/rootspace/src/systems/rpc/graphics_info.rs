@@ -5,12 +5,13 @@ use std::ops::Range;
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub enum GraphicsInfo {
     InstanceReport(Box<Option<InstanceReport>>),
-    SurfaceCapabilities(SurfaceCapabilities),
+    SurfaceCapabilities(Option<SurfaceCapabilities>),
     AdapterFeatures(griffon::wgpu::Features),
     AdapterLimits(griffon::wgpu::Limits),
     AdapterDownlevelCapabilities(griffon::wgpu::DownlevelCapabilities),
     AdapterInfo(griffon::wgpu::AdapterInfo),
     DeviceAllocatorReport(Option<AllocatorReport>),
+    AllocationCounts(griffon::base::gpu_object_database::AllocationCounts),
 }
 
 impl fmt::Display for GraphicsInfo {
@@ -24,7 +25,11 @@ impl fmt::Display for GraphicsInfo {
                 }
             }
             GraphicsInfo::SurfaceCapabilities(sc) => {
-                writeln!(f, "{sc}")
+                if let Some(sc) = sc {
+                    writeln!(f, "{sc}")
+                } else {
+                    writeln!(f, "no surface available")
+                }
             }
             GraphicsInfo::AdapterFeatures(af) => {
                 writeln!(f, "{af}")
@@ -44,6 +49,9 @@ impl fmt::Display for GraphicsInfo {
             GraphicsInfo::DeviceAllocatorReport(None) => {
                 writeln!(f, "no device allocator report available")
             }
+            GraphicsInfo::AllocationCounts(ac) => {
+                writeln!(f, "{ac}")
+            }
         }
     }
 }
@@ -325,4 +333,5 @@ pub enum GraphicsInfoCategory {
     AdapterDownlevelCapabilities,
     AdapterInfo,
     DeviceAllocatorReport,
+    AllocationCounts,
 }
@@ -49,7 +49,7 @@ impl Rpc {
                 GraphicsInfo::InstanceReport(Box::new(gfx.gen_instance_report().map(Into::into)))
             }
             GraphicsInfoCategory::SurfaceCapabilities => {
-                GraphicsInfo::SurfaceCapabilities(gfx.gen_surface_capabilities().into())
+                GraphicsInfo::SurfaceCapabilities(gfx.gen_surface_capabilities().map(Into::into))
             }
             GraphicsInfoCategory::AdapterFeatures => GraphicsInfo::AdapterFeatures(gfx.gen_adapter_features()),
             GraphicsInfoCategory::AdapterLimits => GraphicsInfo::AdapterLimits(gfx.gen_adapter_limits()),
@@ -60,6 +60,7 @@ impl Rpc {
             GraphicsInfoCategory::DeviceAllocatorReport => {
                 GraphicsInfo::DeviceAllocatorReport(gfx.gen_device_allocator_report().map(Into::into))
             }
+            GraphicsInfoCategory::AllocationCounts => GraphicsInfo::AllocationCounts(gfx.gen_allocation_counts()),
         };
         if tx.send(response).is_err() {
             error!("unable to send the graphics subsystem info response to the RPC server");
@@ -89,7 +90,6 @@ impl System for Rpc {
     async fn run(&mut self, res: &Resources, _t: Duration, _dt: Duration) {
         let events = res.write::<EventQueue<EngineEvent>>().receive(&self.receiver);
         for event in events {
-            #[allow(irrefutable_let_patterns)]
             if let EngineEvent::Exit = event {
                 tracing::trace!("Stopping RPC listener");
                 self.rpc_listener.abort();
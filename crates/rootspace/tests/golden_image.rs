@@ -0,0 +1,372 @@
+//! Renders scenes headless and compares them against the reference images in `tests/golden`.
+//! Set `ROOTSPACE_BLESS_GOLDEN=1` to overwrite the references with the rendered frames.
+#![recursion_limit = "512"]
+
+use std::{
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use assam::AssetDatabaseDeps;
+use ecs::Reg;
+use griffon::wgpu::RequestAdapterError;
+use griffon::winit::{dpi::PhysicalSize, event_loop::EventLoopWindowTarget};
+use griffon::{GraphicsDeps, Settings};
+use image::{Rgba, RgbaImage};
+use tokio::runtime::{Builder as RuntimeBuilder, Runtime};
+
+use rootspace::{Orchestrator, OrchestratorDeps, RpcDeps};
+
+/// Setting this environment variable to `1` makes [`GoldenTest::run`] overwrite the reference
+/// image with the rendered frame instead of comparing the two
+const BLESS_ENV_VAR: &str = "ROOTSPACE_BLESS_GOLDEN";
+
+/// The largest possible value of [`color_delta`] for 8-bit colors
+const MAX_YIQ_DELTA: f32 = 35215.0;
+
+/// Decides when two rendered frames are considered equal
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Tolerance {
+    /// The perceptual color difference in `[0, 1]` above which a pixel counts as mismatched
+    threshold: f32,
+    /// The fraction of mismatched pixels in `[0, 1]` that is still accepted
+    max_mismatch_ratio: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            threshold: 0.1,
+            max_mismatch_ratio: 0.005,
+        }
+    }
+}
+
+/// The outcome of comparing a rendered frame to its reference
+#[derive(Debug, Clone)]
+struct Comparison {
+    mismatched_pixels: usize,
+    total_pixels: usize,
+    /// A faded copy of the reference with mismatched pixels highlighted in red
+    diff: RgbaImage,
+}
+
+impl Comparison {
+    fn mismatch_ratio(&self) -> f32 {
+        if self.total_pixels == 0 {
+            return 0.0;
+        }
+
+        self.mismatched_pixels as f32 / self.total_pixels as f32
+    }
+
+    fn is_within(&self, tolerance: &Tolerance) -> bool {
+        self.mismatch_ratio() <= tolerance.max_mismatch_ratio
+    }
+}
+
+/// Compares two frames pixel by pixel with the perceptual color difference of [`color_delta`]
+fn compare(actual: &RgbaImage, reference: &RgbaImage, tolerance: &Tolerance) -> anyhow::Result<Comparison> {
+    if actual.dimensions() != reference.dimensions() {
+        anyhow::bail!(
+            "The rendered frame has dimensions {:?}, but the reference has dimensions {:?}",
+            actual.dimensions(),
+            reference.dimensions()
+        );
+    }
+
+    let max_delta = tolerance.threshold.powi(2);
+    let mut mismatched_pixels = 0;
+    let diff = RgbaImage::from_fn(reference.width(), reference.height(), |x, y| {
+        let (a, r) = (actual.get_pixel(x, y), reference.get_pixel(x, y));
+        if color_delta(a, r) > max_delta {
+            mismatched_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let faded = (255.0 - 0.1 * (255.0 - luma(r))) as u8;
+            Rgba([faded, faded, faded, 255])
+        }
+    });
+
+    Ok(Comparison {
+        mismatched_pixels,
+        total_pixels: (reference.width() * reference.height()) as usize,
+        diff,
+    })
+}
+
+/// Returns the perceptual difference between two colors in `[0, 1]`, measured as the weighted
+/// squared distance in YIQ color space (Kotsarenko and Ramos, 2010). Alpha is ignored because
+/// rendered frames are opaque.
+fn color_delta(a: &Rgba<u8>, b: &Rgba<u8>) -> f32 {
+    let [ar, ag, ab, _] = a.0.map(f32::from);
+    let [br, bg, bb, _] = b.0.map(f32::from);
+    let (dr, dg, db) = (ar - br, ag - bg, ab - bb);
+
+    let y = dr * 0.298_895_3 + dg * 0.586_622_5 + db * 0.114_482_23;
+    let i = dr * 0.595_977_99 - dg * 0.274_176_1 - db * 0.321_801_9;
+    let q = dr * 0.211_470_17 - dg * 0.522_617_1 + db * 0.311_146_94;
+
+    (0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q) / MAX_YIQ_DELTA
+}
+
+fn luma(c: &Rgba<u8>) -> f32 {
+    let [r, g, b, _] = c.0.map(f32::from);
+    r * 0.298_895_3 + g * 0.586_622_5 + b * 0.114_482_23
+}
+
+/// Renders a scene headless and compares the last frame against a stored PNG reference. On
+/// failure, the rendered frame and a diff image are written next to the reference as
+/// `<name>.actual.png` and `<name>.diff.png`. Rendering requires a GPU adapter, which may be a
+/// software rasterizer like lavapipe. Without one, the test is skipped.
+#[derive(Debug, Clone)]
+struct GoldenTest {
+    name: String,
+    game: String,
+    scene: Option<String>,
+    builtins: bool,
+    frames: usize,
+    size: PhysicalSize<u32>,
+    tolerance: Tolerance,
+    reference_dir: PathBuf,
+}
+
+impl GoldenTest {
+    fn new<S: AsRef<str>>(name: S) -> Self {
+        GoldenTest {
+            name: name.as_ref().to_owned(),
+            game: "rootspace".into(),
+            scene: None,
+            builtins: true,
+            frames: 1,
+            size: PhysicalSize::new(320, 240),
+            tolerance: Tolerance::default(),
+            reference_dir: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden"),
+        }
+    }
+
+    fn with_frames(mut self, frames: usize) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    fn reference_path(&self) -> PathBuf {
+        self.reference_dir.join(format!("{}.png", self.name))
+    }
+
+    /// Renders the configured number of frames with a deterministic clock and returns the last one
+    fn render(&self) -> anyhow::Result<RgbaImage> {
+        let rt = Arc::new(RuntimeBuilder::new_multi_thread().enable_all().build()?);
+        let graphics_settings = Settings {
+            offscreen_size: self.size,
+            ..Default::default()
+        };
+        let deps = GoldenDeps {
+            rt: rt.clone(),
+            game: &self.game,
+            main_scene: self.scene.as_deref(),
+            load_builtins: self.builtins,
+            graphics_settings: &graphics_settings,
+        };
+
+        rt.block_on(async move {
+            let mut state = Orchestrator::with_dependencies::<Reg![], Reg![], Reg![], Reg![], _>(&deps).await?;
+            state.run_frames(self.frames).await;
+            state.read_frame()
+        })
+    }
+
+    /// Renders the scene and compares the result against the reference image. Succeeds without a
+    /// comparison if no GPU adapter is available.
+    fn run(self) -> anyhow::Result<()> {
+        let actual = match self.render() {
+            Ok(actual) => actual,
+            Err(e) if e.chain().any(|c| c.is::<RequestAdapterError>()) => {
+                eprintln!("Skipping golden image test '{}': {e:#}", self.name);
+                return Ok(());
+            }
+            Err(e) => return Err(e.context(format!("Rendering golden image test '{}'", self.name))),
+        };
+        let reference_path = self.reference_path();
+
+        if std::env::var(BLESS_ENV_VAR).is_ok_and(|v| v == "1") {
+            std::fs::create_dir_all(&self.reference_dir)?;
+            actual
+                .save(&reference_path)
+                .with_context(|| format!("Saving the reference image '{}'", reference_path.display()))?;
+            tracing::info!("Updated the reference image '{}'", reference_path.display());
+            return Ok(());
+        }
+
+        let reference = image::open(&reference_path)
+            .with_context(|| {
+                format!(
+                    "Opening the reference image '{}' (set {}=1 to create it)",
+                    reference_path.display(),
+                    BLESS_ENV_VAR
+                )
+            })?
+            .into_rgba8();
+
+        let comparison = match compare(&actual, &reference, &self.tolerance) {
+            Ok(c) if c.is_within(&self.tolerance) => return Ok(()),
+            Ok(c) => Some(c),
+            Err(e) => {
+                tracing::error!("{}", e);
+                None
+            }
+        };
+
+        let actual_path = self.reference_dir.join(format!("{}.actual.png", self.name));
+        actual
+            .save(&actual_path)
+            .with_context(|| format!("Saving the rendered frame '{}'", actual_path.display()))?;
+
+        let Some(comparison) = comparison else {
+            anyhow::bail!(
+                "Golden image test '{}' failed: the dimensions of '{}' do not match the reference",
+                self.name,
+                actual_path.display()
+            );
+        };
+
+        let diff_path = self.reference_dir.join(format!("{}.diff.png", self.name));
+        comparison
+            .diff
+            .save(&diff_path)
+            .with_context(|| format!("Saving the diff image '{}'", diff_path.display()))?;
+
+        anyhow::bail!(
+            "Golden image test '{}' failed: {:.3}% of pixels differ from the reference (at most {:.3}% allowed), see '{}'",
+            self.name,
+            comparison.mismatch_ratio() * 100.0,
+            self.tolerance.max_mismatch_ratio * 100.0,
+            diff_path.display()
+        )
+    }
+}
+
+// Options for fixtures other than the builtin scene
+#[allow(dead_code)]
+impl GoldenTest {
+    /// Selects the game whose asset tree is used
+    fn with_game<S: AsRef<str>>(mut self, game: S) -> Self {
+        self.game = game.as_ref().to_owned();
+        self
+    }
+
+    /// Loads the named scene from the scene group of the asset tree
+    fn with_scene<S: AsRef<str>>(mut self, scene: S) -> Self {
+        self.scene = Some(scene.as_ref().to_owned());
+        self
+    }
+
+    /// Specifies whether the builtin scenes are loaded as well
+    fn with_builtins(mut self, builtins: bool) -> Self {
+        self.builtins = builtins;
+        self
+    }
+
+    fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = PhysicalSize::new(width, height);
+        self
+    }
+
+    fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    fn with_reference_dir<P: AsRef<Path>>(mut self, reference_dir: P) -> Self {
+        self.reference_dir = reference_dir.as_ref().to_owned();
+        self
+    }
+}
+
+#[derive(Debug)]
+struct GoldenDeps<'a> {
+    rt: Arc<Runtime>,
+    game: &'a str,
+    main_scene: Option<&'a str>,
+    load_builtins: bool,
+    graphics_settings: &'a Settings,
+}
+
+impl GraphicsDeps for GoldenDeps<'_> {
+    type CustomEvent = ();
+
+    fn event_loop(&self) -> Option<&EventLoopWindowTarget<Self::CustomEvent>> {
+        None
+    }
+
+    fn settings(&self) -> &Settings {
+        self.graphics_settings
+    }
+}
+
+impl AssetDatabaseDeps for GoldenDeps<'_> {
+    fn name(&self) -> &str {
+        self.game
+    }
+
+    fn force_init(&self) -> bool {
+        false
+    }
+
+    fn within_repo(&self) -> bool {
+        true
+    }
+}
+
+impl OrchestratorDeps for GoldenDeps<'_> {
+    fn runtime(&self) -> Arc<Runtime> {
+        self.rt.clone()
+    }
+
+    fn main_scene(&self) -> Option<&str> {
+        self.main_scene
+    }
+
+    fn load_builtins(&self) -> bool {
+        self.load_builtins
+    }
+}
+
+impl RpcDeps for GoldenDeps<'_> {
+    fn bind_address(&self) -> SocketAddr {
+        // Let the operating system choose a port so that tests may run concurrently
+        (IpAddr::V6(Ipv6Addr::LOCALHOST), 0).into()
+    }
+}
+
+#[test]
+fn color_delta_is_zero_for_equal_colors_and_bounded_by_one() {
+    let black = Rgba([0, 0, 0, 255]);
+    let white = Rgba([255, 255, 255, 255]);
+    let blue = Rgba([0, 0, 255, 255]);
+    let yellow = Rgba([255, 255, 0, 255]);
+    assert_eq!(color_delta(&black, &black), 0.0);
+    assert!(color_delta(&black, &white) > 0.9);
+    assert!(color_delta(&blue, &yellow) <= 1.0);
+}
+
+#[test]
+fn compare_highlights_mismatched_pixels() {
+    let reference = RgbaImage::from_pixel(4, 4, Rgba([128, 128, 128, 255]));
+    let mut actual = reference.clone();
+    actual.put_pixel(1, 2, Rgba([255, 0, 0, 255]));
+    actual.put_pixel(2, 2, Rgba([130, 128, 128, 255]));
+
+    let comparison = compare(&actual, &reference, &Tolerance::default()).unwrap();
+    assert_eq!(comparison.mismatched_pixels, 1);
+    assert_eq!(comparison.diff.get_pixel(1, 2), &Rgba([255, 0, 0, 255]));
+    assert_ne!(comparison.diff.get_pixel(2, 2), &Rgba([255, 0, 0, 255]));
+    assert!(!comparison.is_within(&Tolerance::default()));
+}
+
+#[test]
+fn builtin_cube_grid() {
+    GoldenTest::new("builtin-cube-grid").with_frames(10).run().unwrap();
+}
@@ -331,6 +331,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::default_trait_access)]
     fn impl_default() {
         let _: Hierarchy<Tk> = Default::default();
     }
@@ -17,6 +17,18 @@ impl<T> Urn<T> {
             tmp
         }
     }
+
+    fn give_back_id(&mut self, token: usize) {
+        debug_assert!(token < self.max_token, "token {token} was never taken from this urn");
+        debug_assert!(!self.free_tokens.contains(&token), "token {token} was given back twice");
+        self.free_tokens.push(token);
+    }
+
+    /// Returns the number of tokens that have been taken and not given back
+    #[must_use]
+    pub fn taken(&self) -> usize {
+        self.max_token - self.free_tokens.len()
+    }
 }
 
 impl<T: From<usize>> Urn<T> {
@@ -26,6 +38,13 @@ impl<T: From<usize>> Urn<T> {
     }
 }
 
+impl<T: Into<usize>> Urn<T> {
+    /// Returns a token to the urn, such that it may be taken again
+    pub fn give_back(&mut self, token: T) {
+        self.give_back_id(token.into());
+    }
+}
+
 impl<T> Default for Urn<T> {
     fn default() -> Self {
         Urn {
@@ -35,3 +54,22 @@ impl<T> Default for Urn<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_given_back_are_taken_again() {
+        let mut urn: Urn<usize> = Urn::default();
+        let a = urn.take();
+        let b = urn.take();
+        assert_eq!((a, b), (0, 1));
+        assert_eq!(urn.taken(), 2);
+
+        urn.give_back(a);
+        assert_eq!(urn.taken(), 1);
+        assert_eq!(urn.take(), a);
+        assert_eq!(urn.take(), 2);
+    }
+}